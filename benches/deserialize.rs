@@ -0,0 +1,39 @@
+//! Measures parsing of a /states/all response of the usual size, about 10000 state vectors.
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use opensky_api::states::States;
+
+fn response(count: usize) -> String {
+    let states: Vec<String> = (0..count)
+        .map(|index| {
+            format!(
+                r#"["{index:06x}","DLH{index:<5}","Germany",1700000000,1700000001,
+                {:.4},{:.4},10972.8,false,231.5,87.3,-0.33,null,11277.6,"1000",false,0]"#,
+                -180.0 + (index % 3600) as f64 / 10.0,
+                -90.0 + (index % 1800) as f64 / 10.0,
+            )
+        })
+        .collect();
+
+    format!(r#"{{"time":1700000002,"states":[{}]}}"#, states.join(","))
+}
+
+fn deserialize(c: &mut Criterion) {
+    let body = response(10_000);
+
+    let mut group = c.benchmark_group("deserialize");
+    group.throughput(Throughput::Bytes(body.len() as u64));
+
+    group.bench_function("states", |b| {
+        b.iter(|| serde_json::from_str::<States>(&body).unwrap())
+    });
+    // Building a generic Value tree, which the parser used to do before converting each field
+    group.bench_function("value_tree", |b| {
+        b.iter(|| serde_json::from_str::<serde_json::Value>(&body).unwrap())
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, deserialize);
+criterion_main!(benches);
@@ -0,0 +1,52 @@
+//! Compares the latency of creating a new OpenSkyApi instance (and thus a new connection and
+//! TLS handshake) for every request with reusing one instance and its connection pool.
+
+use std::time::{Duration, Instant};
+
+use opensky_api::{bounding_box::BoundingBox, OpenSkyApi};
+
+const REQUESTS: u32 = 5;
+
+#[tokio::main]
+async fn main() {
+    dotenv::dotenv().ok();
+
+    // A small area keeps the responses short, so that the connection setup dominates
+    let bbox = BoundingBox::new(49.9, 50.1, 8.4, 8.7);
+
+    let mut fresh = Duration::ZERO;
+
+    for _ in 0..REQUESTS {
+        let start = Instant::now();
+        let result = OpenSkyApi::new().get_states().with_bbox(bbox).send().await;
+        fresh += start.elapsed();
+
+        if let Err(e) = result {
+            eprintln!("Error: {:?}", e);
+            return;
+        }
+    }
+
+    let opensky_api = OpenSkyApi::new();
+    let mut shared = Duration::ZERO;
+
+    for _ in 0..REQUESTS {
+        let start = Instant::now();
+        let result = opensky_api.get_states().with_bbox(bbox).send().await;
+        shared += start.elapsed();
+
+        if let Err(e) = result {
+            eprintln!("Error: {:?}", e);
+            return;
+        }
+    }
+
+    println!(
+        "new instance per request: {:?} on average",
+        fresh / REQUESTS
+    );
+    println!(
+        "shared instance:          {:?} on average",
+        shared / REQUESTS
+    );
+}
@@ -0,0 +1,230 @@
+//! Offline lookups in OpenSky's downloadable aircraft database.
+//!
+//! OpenSky publishes its aircraft database as a CSV file (see
+//! <https://opensky-network.org/datasets/metadata/>). Loading it into an AircraftDatabase
+//! allows registration, type, and operator lookups for large numbers of aircraft without
+//! sending one metadata request per aircraft. Both the older double-quoted layout and the
+//! newer single-quoted layout with camelCase headers are supported.
+
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufReader, Read},
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+};
+
+use crate::{errors::Error, metadata::AircraftMetadata};
+
+#[derive(Debug, Default)]
+struct Index {
+    aircraft: HashMap<String, AircraftMetadata>,
+    by_registration: HashMap<String, String>,
+}
+
+/// An in-memory, indexed copy of OpenSky's aircraft database. Clones share the same data, so
+/// a refresh is visible to all of them.
+///
+#[derive(Debug, Clone, Default)]
+pub struct AircraftDatabase {
+    index: Arc<RwLock<Index>>,
+    path: Option<PathBuf>,
+}
+
+impl AircraftDatabase {
+    /// Loads the aircraft database from the CSV file at the given path. The path is kept so
+    /// that the database can be reloaded later using refresh().
+    ///
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref().to_path_buf();
+        let index = read_index(BufReader::new(File::open(&path)?))?;
+
+        Ok(Self {
+            index: Arc::new(RwLock::new(index)),
+            path: Some(path),
+        })
+    }
+
+    /// Loads the aircraft database from any reader providing the CSV file's contents
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self, Error> {
+        Ok(Self {
+            index: Arc::new(RwLock::new(read_index(reader)?)),
+            path: None,
+        })
+    }
+
+    /// Reloads the database from the file it was loaded from, for example after a newer
+    /// version has been downloaded. Lookups continue to use the old data until the new file
+    /// has been read completely. Databases that were not loaded from a file are left as is.
+    ///
+    pub fn refresh(&self) -> Result<(), Error> {
+        if let Some(path) = &self.path {
+            let index = read_index(BufReader::new(File::open(path)?))?;
+
+            *self.index.write().unwrap() = index;
+        }
+
+        Ok(())
+    }
+
+    /// Replaces the contents of the database with the CSV data provided by the reader
+    pub fn refresh_from_reader<R: Read>(&self, reader: R) -> Result<(), Error> {
+        let index = read_index(reader)?;
+
+        *self.index.write().unwrap() = index;
+
+        Ok(())
+    }
+
+    /// Returns the number of aircraft in the database
+    pub fn len(&self) -> usize {
+        self.index.read().unwrap().aircraft.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Looks up an aircraft by its ICAO24 address
+    pub fn get(&self, icao24: &str) -> Option<AircraftMetadata> {
+        self.index
+            .read()
+            .unwrap()
+            .aircraft
+            .get(&icao24.trim().to_lowercase())
+            .cloned()
+    }
+
+    /// Looks up an aircraft by its registration (e.g. "D-AIBD"). The comparison ignores case
+    /// and dashes.
+    ///
+    pub fn by_registration(&self, registration: &str) -> Option<AircraftMetadata> {
+        let index = self.index.read().unwrap();
+
+        index
+            .by_registration
+            .get(&normalize_registration(registration))
+            .and_then(|icao24| index.aircraft.get(icao24))
+            .cloned()
+    }
+
+    /// Returns all aircraft with the given ICAO type designator (e.g. "A320")
+    pub fn by_typecode(&self, typecode: &str) -> Vec<AircraftMetadata> {
+        self.filter(|aircraft| matches_ignore_case(&aircraft.typecode, typecode))
+    }
+
+    /// Returns all aircraft operated by the operator with the given ICAO code (e.g. "DLH")
+    pub fn by_operator_icao(&self, operator_icao: &str) -> Vec<AircraftMetadata> {
+        self.filter(|aircraft| matches_ignore_case(&aircraft.operator_icao, operator_icao))
+    }
+
+    fn filter<F: Fn(&AircraftMetadata) -> bool>(&self, f: F) -> Vec<AircraftMetadata> {
+        self.index
+            .read()
+            .unwrap()
+            .aircraft
+            .values()
+            .filter(|aircraft| f(aircraft))
+            .cloned()
+            .collect()
+    }
+}
+
+fn matches_ignore_case(field: &Option<String>, value: &str) -> bool {
+    field
+        .as_deref()
+        .is_some_and(|field| field.eq_ignore_ascii_case(value.trim()))
+}
+
+fn normalize_registration(registration: &str) -> String {
+    registration
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+fn read_index<R: Read>(mut reader: R) -> Result<Index, Error> {
+    let mut contents = Vec::new();
+    reader.read_to_end(&mut contents)?;
+
+    // The newer database files quote every field using single quotes
+    let quote = if contents.first() == Some(&b'\'') {
+        b'\''
+    } else {
+        b'"'
+    };
+
+    let mut csv = csv::ReaderBuilder::new()
+        .quote(quote)
+        .flexible(true)
+        .from_reader(contents.as_slice());
+
+    let headers: Vec<String> = csv
+        .headers()?
+        .iter()
+        .map(|header| header.trim().to_lowercase())
+        .collect();
+
+    let column = |names: &[&str]| headers.iter().position(|h| names.contains(&h.as_str()));
+
+    let icao24 = column(&["icao24"]).ok_or(Error::InvalidDatabase("missing icao24 column"))?;
+    let registration = column(&["registration"]);
+    let manufacturer_name = column(&["manufacturername"]);
+    let manufacturer_icao = column(&["manufacturericao"]);
+    let model = column(&["model"]);
+    let typecode = column(&["typecode"]);
+    let serial_number = column(&["serialnumber"]);
+    let icao_aircraft_class = column(&["icaoaircraftclass", "icaoaircrafttype"]);
+    let operator = column(&["operator"]);
+    let operator_callsign = column(&["operatorcallsign"]);
+    let operator_icao = column(&["operatoricao"]);
+    let operator_iata = column(&["operatoriata"]);
+    let owner = column(&["owner"]);
+    let country = column(&["country"]);
+
+    let mut index = Index::default();
+
+    for record in csv.records() {
+        let record = record?;
+
+        let field = |column: Option<usize>| {
+            column
+                .and_then(|column| record.get(column))
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(String::from)
+        };
+
+        let Some(address) = field(Some(icao24)).map(|address| address.to_lowercase()) else {
+            continue;
+        };
+
+        let aircraft = AircraftMetadata {
+            icao24: address.clone(),
+            registration: field(registration),
+            manufacturer_name: field(manufacturer_name),
+            manufacturer_icao: field(manufacturer_icao),
+            model: field(model),
+            typecode: field(typecode),
+            serial_number: field(serial_number),
+            icao_aircraft_class: field(icao_aircraft_class),
+            operator: field(operator),
+            operator_callsign: field(operator_callsign),
+            operator_icao: field(operator_icao),
+            operator_iata: field(operator_iata),
+            owner: field(owner),
+            country: field(country),
+        };
+
+        if let Some(registration) = &aircraft.registration {
+            index
+                .by_registration
+                .insert(normalize_registration(registration), address.clone());
+        }
+
+        index.aircraft.insert(address, aircraft);
+    }
+
+    Ok(index)
+}
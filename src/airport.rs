@@ -0,0 +1,81 @@
+//! ICAO airport codes.
+
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::errors::Error;
+
+/// A 4-character ICAO airport code, e.g. "EDDF" or "KJFK". Unlike 3-letter IATA codes (e.g.
+/// "FRA"), these are what OpenSky uses to identify airports.
+///
+/// Codes are stored in upper case. Besides letters, digits are accepted as well, since small
+/// airfields have codes like "EG74" or "K1N7".
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct AirportIcao {
+    code: [u8; 4],
+}
+
+impl AirportIcao {
+    /// Returns the code, e.g. "EDDF"
+    pub fn as_str(&self) -> &str {
+        // Only ASCII alphanumerics are accepted by from_str()
+        std::str::from_utf8(&self.code).unwrap_or_default()
+    }
+}
+
+impl FromStr for AirportIcao {
+    type Err = Error;
+
+    /// Parses exactly four ASCII letters or digits, ignoring surrounding whitespace and case
+    fn from_str(code: &str) -> Result<Self, Self::Err> {
+        let trimmed = code.trim();
+
+        match <[u8; 4]>::try_from(trimmed.as_bytes()) {
+            Ok(bytes) if bytes.iter().all(u8::is_ascii_alphanumeric) => Ok(Self {
+                code: bytes.map(|byte| byte.to_ascii_uppercase()),
+            }),
+            _ => Err(Error::InvalidAirport(code.to_string())),
+        }
+    }
+}
+
+impl TryFrom<String> for AirportIcao {
+    type Error = Error;
+
+    fn try_from(code: String) -> Result<Self, Self::Error> {
+        code.parse()
+    }
+}
+
+impl From<AirportIcao> for String {
+    fn from(airport: AirportIcao) -> Self {
+        airport.as_str().to_string()
+    }
+}
+
+impl AsRef<str> for AirportIcao {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl PartialEq<str> for AirportIcao {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str().eq_ignore_ascii_case(other.trim())
+    }
+}
+
+impl PartialEq<&str> for AirportIcao {
+    fn eq(&self, other: &&str) -> bool {
+        self == *other
+    }
+}
+
+impl fmt::Display for AirportIcao {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
@@ -0,0 +1,344 @@
+//! Airspace boundaries, e.g. FIRs, TMAs, or custom sectors, loaded from GeoJSON.
+//!
+//! Every Polygon or MultiPolygon feature of a GeoJSON FeatureCollection becomes an Airspace.
+//! The name is taken from the "name" property (or "id" if there is no name), and the optional
+//! "lower" and "upper" properties give the vertical limits in meters.
+
+use std::{collections::HashMap, fs, path::Path};
+
+use serde_json::Value;
+
+use crate::{errors::Error, states::StateVector, states::States, tracks::Waypoint};
+
+/// A polygon in (longitude, latitude) coordinates, as in GeoJSON
+#[derive(Debug, Clone)]
+pub struct Polygon {
+    pub exterior: Vec<(f64, f64)>,
+    pub holes: Vec<Vec<(f64, f64)>>,
+}
+
+impl Polygon {
+    /// Returns true if the point lies inside the exterior ring and outside of all holes
+    pub fn contains(&self, latitude: f64, longitude: f64) -> bool {
+        ring_contains(&self.exterior, latitude, longitude)
+            && !self
+                .holes
+                .iter()
+                .any(|hole| ring_contains(hole, latitude, longitude))
+    }
+}
+
+/// A named volume of airspace
+#[derive(Debug, Clone)]
+pub struct Airspace {
+    pub name: String,
+    pub polygons: Vec<Polygon>,
+    /// Lower limit in meters, None if the airspace starts at the surface
+    pub lower: Option<f64>,
+    /// Upper limit in meters, None if the airspace is unlimited
+    pub upper: Option<f64>,
+}
+
+impl Airspace {
+    /// Returns true if the position lies within the airspace. If the altitude is unknown only
+    /// the lateral boundaries are checked.
+    ///
+    pub fn contains(&self, latitude: f64, longitude: f64, altitude: Option<f64>) -> bool {
+        if let Some(altitude) = altitude {
+            if self.lower.is_some_and(|lower| altitude < lower)
+                || self.upper.is_some_and(|upper| altitude > upper)
+            {
+                return false;
+            }
+        }
+
+        self.polygons
+            .iter()
+            .any(|polygon| polygon.contains(latitude, longitude))
+    }
+}
+
+/// A set of airspaces that positions can be tagged with
+#[derive(Debug, Clone, Default)]
+pub struct Airspaces {
+    pub airspaces: Vec<Airspace>,
+}
+
+impl Airspaces {
+    /// Loads the airspaces from a GeoJSON file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Self::from_geojson(&fs::read_to_string(path)?)
+    }
+
+    /// Parses the airspaces from a GeoJSON FeatureCollection or a single Feature. Features
+    /// with other geometries than Polygon and MultiPolygon are skipped.
+    ///
+    pub fn from_geojson(geojson: &str) -> Result<Self, Error> {
+        let value: Value = serde_json::from_str(geojson)?;
+
+        let features = match value.get("type").and_then(Value::as_str) {
+            Some("FeatureCollection") => value
+                .get("features")
+                .and_then(Value::as_array)
+                .ok_or(Error::InvalidGeoJson("missing features"))?
+                .iter()
+                .collect(),
+            Some("Feature") => vec![&value],
+            _ => {
+                return Err(Error::InvalidGeoJson(
+                    "expected a Feature or FeatureCollection",
+                ))
+            }
+        };
+
+        let mut airspaces = Vec::new();
+
+        for (index, feature) in features.into_iter().enumerate() {
+            let geometry = feature.get("geometry").unwrap_or(&Value::Null);
+
+            let Some(polygons) = parse_geometry(geometry)? else {
+                continue;
+            };
+
+            let properties = feature.get("properties").unwrap_or(&Value::Null);
+
+            let name = properties
+                .get("name")
+                .or_else(|| feature.get("id"))
+                .and_then(|name| match name {
+                    Value::String(name) => Some(name.clone()),
+                    Value::Number(number) => Some(number.to_string()),
+                    _ => None,
+                })
+                .unwrap_or_else(|| format!("airspace {}", index));
+
+            airspaces.push(Airspace {
+                name,
+                polygons,
+                lower: properties.get("lower").and_then(Value::as_f64),
+                upper: properties.get("upper").and_then(Value::as_f64),
+            });
+        }
+
+        Ok(Self { airspaces })
+    }
+
+    /// Returns all airspaces containing the position
+    pub fn containing(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        altitude: Option<f64>,
+    ) -> Vec<&Airspace> {
+        self.airspaces
+            .iter()
+            .filter(|airspace| airspace.contains(latitude, longitude, altitude))
+            .collect()
+    }
+
+    /// Returns the names of all airspaces the aircraft is in, using its barometric altitude
+    /// (or geometric altitude if the barometric one is unknown). Aircraft on the ground are
+    /// at an altitude of zero.
+    ///
+    pub fn tag_state(&self, state: &StateVector) -> Vec<&str> {
+        let (Some(latitude), Some(longitude)) = (state.latitude, state.longitude) else {
+            return Vec::new();
+        };
+
+        let altitude = if state.on_ground {
+            Some(0.0)
+        } else {
+            state.baro_altitude.or(state.geo_altitude).map(f64::from)
+        };
+
+        self.names(latitude, longitude, altitude)
+    }
+
+    /// Returns the names of all airspaces the waypoint is in
+    pub fn tag_waypoint(&self, waypoint: &Waypoint) -> Vec<&str> {
+        let (Some(latitude), Some(longitude)) = (waypoint.latitude, waypoint.longitude) else {
+            return Vec::new();
+        };
+
+        let altitude = if waypoint.on_ground {
+            Some(0.0)
+        } else {
+            waypoint.baro_altitude
+        };
+
+        self.names(latitude, longitude, altitude)
+    }
+
+    /// Returns the number of aircraft in every airspace. Airspaces without aircraft are
+    /// included with a count of zero.
+    ///
+    pub fn occupancy(&self, states: &States) -> HashMap<&str, usize> {
+        let mut counts: HashMap<&str, usize> = self
+            .airspaces
+            .iter()
+            .map(|airspace| (airspace.name.as_str(), 0))
+            .collect();
+
+        for state in &states.states {
+            for name in self.tag_state(state) {
+                *counts.entry(name).or_default() += 1;
+            }
+        }
+
+        counts
+    }
+
+    fn names(&self, latitude: f64, longitude: f64, altitude: Option<f64>) -> Vec<&str> {
+        self.containing(latitude, longitude, altitude)
+            .into_iter()
+            .map(|airspace| airspace.name.as_str())
+            .collect()
+    }
+}
+
+/// An area of arbitrary shape, e.g. an airport perimeter or a country border, made up of one
+/// or more polygons. Unlike an Airspace it has no vertical limits.
+///
+#[derive(Debug, Clone, Default)]
+pub struct Geofence {
+    pub polygons: Vec<Polygon>,
+}
+
+impl Geofence {
+    /// Creates a geofence from the vertices of a polygon given as (latitude, longitude) pairs.
+    /// The polygon is closed automatically.
+    ///
+    pub fn new(vertices: &[(f64, f64)]) -> Self {
+        Self::from(Polygon {
+            exterior: vertices
+                .iter()
+                .map(|&(latitude, longitude)| (longitude, latitude))
+                .collect(),
+            holes: Vec::new(),
+        })
+    }
+
+    /// Parses a geofence from a GeoJSON Polygon or MultiPolygon geometry, or from a Feature or
+    /// FeatureCollection, in which case the polygons of all features are combined
+    ///
+    pub fn from_geojson(geojson: &str) -> Result<Self, Error> {
+        let value: Value = serde_json::from_str(geojson)?;
+
+        let polygons = match value.get("type").and_then(Value::as_str) {
+            Some("Polygon" | "MultiPolygon") => parse_geometry(&value)?.unwrap_or_default(),
+            Some("Feature" | "FeatureCollection") => Airspaces::from_geojson(geojson)?
+                .airspaces
+                .into_iter()
+                .flat_map(|airspace| airspace.polygons)
+                .collect(),
+            _ => {
+                return Err(Error::InvalidGeoJson(
+                    "expected a Polygon, MultiPolygon, Feature, or FeatureCollection",
+                ))
+            }
+        };
+
+        Ok(Self { polygons })
+    }
+
+    /// Returns true if the point lies within any of the polygons
+    pub fn contains(&self, latitude: f64, longitude: f64) -> bool {
+        self.polygons
+            .iter()
+            .any(|polygon| polygon.contains(latitude, longitude))
+    }
+}
+
+impl From<Polygon> for Geofence {
+    fn from(polygon: Polygon) -> Self {
+        Self {
+            polygons: vec![polygon],
+        }
+    }
+}
+
+impl States {
+    /// Returns a copy of this snapshot with only the aircraft within the geofence. Aircraft
+    /// without a position are removed.
+    ///
+    pub fn within(&self, geofence: &Geofence) -> States {
+        States {
+            time: self.time,
+            states: self
+                .states
+                .iter()
+                .filter(|state| match (state.latitude, state.longitude) {
+                    (Some(latitude), Some(longitude)) => geofence.contains(latitude, longitude),
+                    _ => false,
+                })
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
+/// Returns the polygons of a Polygon or MultiPolygon geometry, or None for other geometries
+fn parse_geometry(geometry: &Value) -> Result<Option<Vec<Polygon>>, Error> {
+    match geometry.get("type").and_then(Value::as_str) {
+        Some("Polygon") => Ok(Some(vec![parse_polygon(&geometry["coordinates"])?])),
+        Some("MultiPolygon") => Ok(Some(
+            geometry["coordinates"]
+                .as_array()
+                .ok_or(Error::InvalidGeoJson("invalid MultiPolygon"))?
+                .iter()
+                .map(parse_polygon)
+                .collect::<Result<_, _>>()?,
+        )),
+        _ => Ok(None),
+    }
+}
+
+fn parse_polygon(coordinates: &Value) -> Result<Polygon, Error> {
+    let mut rings = coordinates
+        .as_array()
+        .ok_or(Error::InvalidGeoJson("invalid Polygon"))?
+        .iter()
+        .map(parse_ring);
+
+    let exterior = rings
+        .next()
+        .ok_or(Error::InvalidGeoJson("Polygon without rings"))??;
+
+    Ok(Polygon {
+        exterior,
+        holes: rings.collect::<Result<_, _>>()?,
+    })
+}
+
+fn parse_ring(ring: &Value) -> Result<Vec<(f64, f64)>, Error> {
+    ring.as_array()
+        .ok_or(Error::InvalidGeoJson("invalid linear ring"))?
+        .iter()
+        .map(|position| {
+            match (
+                position.get(0).and_then(Value::as_f64),
+                position.get(1).and_then(Value::as_f64),
+            ) {
+                (Some(longitude), Some(latitude)) => Ok((longitude, latitude)),
+                _ => Err(Error::InvalidGeoJson("invalid position")),
+            }
+        })
+        .collect()
+}
+
+/// Even-odd ray casting test of a point against a ring of (longitude, latitude) coordinates
+fn ring_contains(ring: &[(f64, f64)], latitude: f64, longitude: f64) -> bool {
+    let mut inside = false;
+
+    for (i, &(x1, y1)) in ring.iter().enumerate() {
+        let (x2, y2) = ring[(i + 1) % ring.len()];
+
+        if (y1 > latitude) != (y2 > latitude)
+            && longitude < x1 + (latitude - y1) * (x2 - x1) / (y2 - y1)
+        {
+            inside = !inside;
+        }
+    }
+
+    inside
+}
@@ -0,0 +1,174 @@
+//! Analyses over whole snapshots of state vectors.
+
+use std::collections::BTreeMap;
+
+use crate::{bounding_box::BoundingBox, states::States};
+
+/// The width in meters of the altitude bands counted by States::stats()
+pub const ALTITUDE_BAND_WIDTH: f32 = 1000.0;
+
+/// The number of scaled median absolute deviations from the median beyond which a sample is
+/// considered an outlier
+///
+const OUTLIER_THRESHOLD: f64 = 3.0;
+
+/// Scales the median absolute deviation to the standard deviation of a normal distribution
+const MAD_SCALE: f64 = 1.4826;
+
+/// The difference between geometric (GNSS) and barometric altitude over a region, which
+/// approximates the deviation of the local pressure from the standard atmosphere used for
+/// pressure altitudes. All values are in meters.
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AltitudeDeviation {
+    /// Median of the geometric minus barometric altitude of all samples
+    pub median: f64,
+    /// Mean of the samples that are not outliers
+    pub mean: f64,
+    /// Standard deviation of the samples that are not outliers
+    pub std_dev: f64,
+    /// Number of samples that are not outliers
+    pub samples: usize,
+    /// Number of samples that were rejected as outliers
+    pub outliers: usize,
+}
+
+/// A summary of the traffic in a snapshot
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StatesStats {
+    /// Number of state vectors
+    pub total: usize,
+    pub on_ground: usize,
+    pub airborne: usize,
+    /// Number of aircraft per origin country
+    pub by_country: BTreeMap<String, usize>,
+    /// Number of aircraft per category, with None for aircraft without a category (e.g. from
+    /// requests which were not extended)
+    ///
+    pub by_category: BTreeMap<Option<u32>, usize>,
+    /// Number of airborne aircraft per barometric altitude band, keyed by the lower bound of
+    /// the band in meters. The bands are ALTITUDE_BAND_WIDTH wide.
+    ///
+    pub altitude_bands: BTreeMap<i32, usize>,
+    /// Mean velocity in m/s of the aircraft reporting one, or None if no aircraft does
+    pub mean_velocity: Option<f64>,
+    /// Highest velocity in m/s, or None if no aircraft reports one
+    pub max_velocity: Option<f32>,
+}
+
+impl States {
+    /// Counts the aircraft in this snapshot by country, category, on ground or airborne, and
+    /// altitude band, and computes their mean and maximum velocity
+    ///
+    pub fn stats(&self) -> StatesStats {
+        let mut stats = StatesStats {
+            total: self.states.len(),
+            ..Default::default()
+        };
+        let mut velocities = Vec::new();
+
+        for state in &self.states {
+            if state.on_ground {
+                stats.on_ground += 1;
+            } else {
+                stats.airborne += 1;
+
+                if let Some(altitude) = state.baro_altitude {
+                    let band = (altitude / ALTITUDE_BAND_WIDTH).floor() * ALTITUDE_BAND_WIDTH;
+                    *stats.altitude_bands.entry(band as i32).or_default() += 1;
+                }
+            }
+
+            *stats
+                .by_country
+                .entry(state.origin_country.clone())
+                .or_default() += 1;
+            *stats.by_category.entry(state.category).or_default() += 1;
+
+            velocities.extend(state.velocity);
+        }
+
+        if !velocities.is_empty() {
+            stats.mean_velocity = Some(
+                velocities.iter().map(|&v| f64::from(v)).sum::<f64>() / velocities.len() as f64,
+            );
+            stats.max_velocity = velocities.into_iter().reduce(f32::max);
+        }
+
+        stats
+    }
+
+    /// Estimates the deviation of the local altimeter setting from the geometric and barometric
+    /// altitudes of all airborne aircraft. Samples further than three (scaled) median absolute
+    /// deviations from the median are rejected as outliers, which removes aircraft with faulty
+    /// altitude encoders.
+    ///
+    /// Returns None if no airborne aircraft reports both altitudes.
+    ///
+    pub fn altitude_deviation(&self) -> Option<AltitudeDeviation> {
+        altitude_deviation(
+            self.states
+                .iter()
+                .filter(|state| !state.on_ground)
+                .filter_map(|state| state.altitude_difference())
+                .map(f64::from)
+                .collect(),
+        )
+    }
+
+    /// Like altitude_deviation(), but only includes aircraft within the bounding box
+    pub fn altitude_deviation_in(&self, bbox: &BoundingBox) -> Option<AltitudeDeviation> {
+        altitude_deviation(
+            self.states
+                .iter()
+                .filter(|state| !state.on_ground)
+                .filter(|state| match (state.latitude, state.longitude) {
+                    (Some(latitude), Some(longitude)) => bbox.contains(latitude, longitude),
+                    _ => false,
+                })
+                .filter_map(|state| state.altitude_difference())
+                .map(f64::from)
+                .collect(),
+        )
+    }
+}
+
+fn altitude_deviation(mut samples: Vec<f64>) -> Option<AltitudeDeviation> {
+    let median = median_of(&mut samples)?;
+
+    let mut deviations: Vec<f64> = samples.iter().map(|s| (s - median).abs()).collect();
+    let mad = median_of(&mut deviations)? * MAD_SCALE;
+
+    let inliers: Vec<f64> = samples
+        .iter()
+        .copied()
+        .filter(|sample| (sample - median).abs() <= OUTLIER_THRESHOLD * mad)
+        .collect();
+
+    let mean = inliers.iter().sum::<f64>() / inliers.len() as f64;
+    let variance = inliers.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / inliers.len() as f64;
+
+    Some(AltitudeDeviation {
+        median,
+        mean,
+        std_dev: variance.sqrt(),
+        samples: inliers.len(),
+        outliers: samples.len() - inliers.len(),
+    })
+}
+
+fn median_of(values: &mut [f64]) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+
+    values.sort_by(f64::total_cmp);
+
+    let middle = values.len() / 2;
+
+    if values.len().is_multiple_of(2) {
+        Some((values[middle - 1] + values[middle]) / 2.0)
+    } else {
+        Some(values[middle])
+    }
+}
@@ -0,0 +1,161 @@
+//! OAuth2 client credentials authentication, which OpenSky uses instead of basic
+//! authentication for new accounts.
+
+use std::{fmt, time::Duration};
+
+use serde::Deserialize;
+use tokio::sync::Mutex;
+
+use crate::{errors::Error, time::Instant};
+
+/// Tokens are refreshed this long before they expire, so that they do not expire while a
+/// request is on its way
+///
+pub const TOKEN_REFRESH_MARGIN: Duration = Duration::from_secs(30);
+
+/// The token endpoint of OpenSky's authentication server
+pub const OPENSKY_TOKEN_URL: &str =
+    "https://auth.opensky-network.org/auth/realms/opensky-network/protocol/openid-connect/token";
+
+/// The client ID and secret of an OpenSky API client
+#[derive(Clone)]
+pub struct OAuth2Credentials {
+    client_id: String,
+    client_secret: String,
+    token_url: String,
+}
+
+impl fmt::Debug for OAuth2Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OAuth2Credentials")
+            .field("client_id", &self.client_id)
+            .field("client_secret", &"********")
+            .field("token_url", &self.token_url)
+            .finish()
+    }
+}
+
+impl OAuth2Credentials {
+    /// Creates new credentials which obtain tokens from OPENSKY_TOKEN_URL
+    pub fn new(client_id: String, client_secret: String) -> Self {
+        Self {
+            client_id,
+            client_secret,
+            token_url: String::from(OPENSKY_TOKEN_URL),
+        }
+    }
+
+    /// Overrides the URL tokens are obtained from
+    pub fn with_token_url(mut self, token_url: String) -> Self {
+        self.token_url = token_url;
+
+        self
+    }
+
+    pub fn client_id(&self) -> &str {
+        &self.client_id
+    }
+
+    /// Obtains a new access token from the authentication server
+    pub(crate) async fn request_token(
+        &self,
+        client: &reqwest::Client,
+    ) -> Result<AccessToken, Error> {
+        let res = client
+            .post(&self.token_url)
+            .form(&[
+                ("grant_type", "client_credentials"),
+                ("client_id", &self.client_id),
+                ("client_secret", &self.client_secret),
+            ])
+            .send()
+            .await?;
+
+        match res.status() {
+            reqwest::StatusCode::OK => {
+                let bytes = res.bytes().await?.to_vec();
+
+                Ok(serde_json::from_slice(&bytes)?)
+            }
+            status => Err(Error::Http(status)),
+        }
+    }
+}
+
+/// An access token issued by the authentication server
+#[derive(Clone, Deserialize)]
+pub struct AccessToken {
+    pub access_token: String,
+    /// The number of seconds the token is valid for after it was issued
+    #[serde(default)]
+    pub expires_in: u64,
+}
+
+impl fmt::Debug for AccessToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AccessToken")
+            .field("access_token", &"********")
+            .field("expires_in", &self.expires_in)
+            .finish()
+    }
+}
+
+#[derive(Debug)]
+struct CachedToken {
+    access_token: String,
+    refresh_at: Instant,
+}
+
+/// Caches the access token of an OAuth2 client and refreshes it shortly before it expires.
+/// Concurrent requests wait for a single refresh instead of each requesting a new token.
+///
+#[derive(Debug)]
+pub(crate) struct TokenProvider {
+    credentials: OAuth2Credentials,
+    cached: Mutex<Option<CachedToken>>,
+}
+
+impl TokenProvider {
+    pub(crate) fn new(credentials: OAuth2Credentials) -> Self {
+        Self {
+            credentials,
+            cached: Mutex::new(None),
+        }
+    }
+
+    /// Returns the cached access token, or obtains a new one if there is none or it is about
+    /// to expire
+    ///
+    pub(crate) async fn token(&self, client: &reqwest::Client) -> Result<String, Error> {
+        let mut cached = self.cached.lock().await;
+
+        if let Some(token) = cached.as_ref() {
+            if Instant::now() < token.refresh_at {
+                return Ok(token.access_token.clone());
+            }
+        }
+
+        let token = self.credentials.request_token(client).await?;
+        let access_token = token.access_token.clone();
+
+        *cached = Some(CachedToken {
+            access_token: token.access_token,
+            refresh_at: Instant::now()
+                + Duration::from_secs(token.expires_in).saturating_sub(TOKEN_REFRESH_MARGIN),
+        });
+
+        Ok(access_token)
+    }
+
+    /// Discards the cached access token and obtains a new one
+    pub(crate) async fn refresh(&self, client: &reqwest::Client) -> Result<String, Error> {
+        self.invalidate().await;
+
+        self.token(client).await
+    }
+
+    /// Discards the cached access token, so the next request obtains a new one
+    pub(crate) async fn invalidate(&self) {
+        *self.cached.lock().await = None;
+    }
+}
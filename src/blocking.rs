@@ -0,0 +1,513 @@
+//! A synchronous interface to the API for programs which do not use an async runtime.
+//!
+//! Every request is run to completion on a single-threaded tokio runtime owned by the
+//! blocking OpenSkyApi. Like reqwest's blocking client, the requests must not be sent from
+//! within an async runtime, where they would panic.
+//!
+
+use std::{collections::HashMap, ops::Range, sync::Arc};
+
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use tokio::runtime::Runtime;
+
+use crate::{
+    airport::AirportIcao,
+    bounding_box::BoundingBox,
+    congestion::{CongestionReport, CongestionRequest},
+    context::RequestContext,
+    errors::Error,
+    flights::{
+        AirportFlightsRequest, Flight, FlightsRequest, FlightsRequestBuilder, RouteFlightsRequest,
+    },
+    metadata::{
+        Aircraft, AircraftMetadata, EnrichRequest, MetadataBatchRequest, MetadataCache,
+        MetadataRequest, WithMetadata,
+    },
+    response::WithRaw,
+    states::{
+        NearbyStates, OwnStatesRequestBuilder, RadiusStateRequest, StateRequest,
+        StateRequestBuilder, States, TiledStateRequest,
+    },
+    tracks::{FlightTrack, TrackBatchRequest, TrackRequest},
+    validation::{ValidationReport, Validators},
+};
+
+/// The blocking counterpart of crate::OpenSkyApi, created using crate::OpenSkyApi::blocking()
+pub struct OpenSkyApi {
+    api: crate::OpenSkyApi,
+    runtime: Arc<Runtime>,
+}
+
+impl OpenSkyApi {
+    /// Wraps the async OpenSkyApi. Panics if the runtime cannot be started.
+    pub(crate) fn new(api: crate::OpenSkyApi) -> Self {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("failed to start the runtime of the blocking client");
+
+        Self {
+            api,
+            runtime: Arc::new(runtime),
+        }
+    }
+
+    fn wrap<R>(&self, request: R) -> Blocking<R> {
+        Blocking {
+            request,
+            runtime: self.runtime.clone(),
+        }
+    }
+
+    /// Discards the cached OAuth2 access token and obtains a new one
+    pub fn force_refresh(&self) -> Result<(), Error> {
+        self.runtime.block_on(self.api.force_refresh())
+    }
+
+    /// Returns the context shared by the requests created from this OpenSkyApi instance
+    pub fn context(&self) -> &RequestContext {
+        self.api.context()
+    }
+
+    /// Returns the cache used for aircraft metadata lookups
+    pub fn metadata_cache(&self) -> &MetadataCache {
+        self.api.metadata_cache()
+    }
+
+    /// See crate::OpenSkyApi::get_states()
+    pub fn get_states(&self) -> Blocking<StateRequestBuilder> {
+        self.wrap(self.api.get_states())
+    }
+
+    /// See crate::OpenSkyApi::get_states_now()
+    pub fn get_states_now(&self) -> Blocking<StateRequestBuilder> {
+        self.wrap(self.api.get_states_now())
+    }
+
+    /// See crate::OpenSkyApi::get_own_states()
+    pub fn get_own_states(&self) -> Blocking<OwnStatesRequestBuilder> {
+        self.wrap(self.api.get_own_states())
+    }
+
+    /// See crate::OpenSkyApi::get_flights()
+    pub fn get_flights(&self, begin: u64, end: u64) -> Blocking<FlightsRequestBuilder> {
+        self.wrap(self.api.get_flights(begin, end))
+    }
+
+    /// See crate::OpenSkyApi::get_flights_in()
+    pub fn get_flights_in(&self, range: Range<DateTime<Utc>>) -> Blocking<FlightsRequestBuilder> {
+        self.wrap(self.api.get_flights_in(range))
+    }
+
+    /// See crate::OpenSkyApi::get_route_flights()
+    pub fn get_route_flights(
+        &self,
+        departure: AirportIcao,
+        arrival: AirportIcao,
+        begin: u64,
+        end: u64,
+    ) -> Blocking<RouteFlightsRequest> {
+        self.wrap(self.api.get_route_flights(departure, arrival, begin, end))
+    }
+
+    /// See crate::OpenSkyApi::get_arrivals()
+    pub fn get_arrivals(
+        &self,
+        airport: AirportIcao,
+        begin: u64,
+        end: u64,
+    ) -> Blocking<AirportFlightsRequest> {
+        self.wrap(self.api.get_arrivals(airport, begin, end))
+    }
+
+    /// See crate::OpenSkyApi::get_departures()
+    pub fn get_departures(
+        &self,
+        airport: AirportIcao,
+        begin: u64,
+        end: u64,
+    ) -> Blocking<AirportFlightsRequest> {
+        self.wrap(self.api.get_departures(airport, begin, end))
+    }
+
+    /// See crate::OpenSkyApi::get_arrivals_on()
+    pub fn get_arrivals_on<Tz: TimeZone>(
+        &self,
+        airport: AirportIcao,
+        date: NaiveDate,
+        tz: &Tz,
+    ) -> Blocking<AirportFlightsRequest> {
+        self.wrap(self.api.get_arrivals_on(airport, date, tz))
+    }
+
+    /// See crate::OpenSkyApi::get_departures_on()
+    pub fn get_departures_on<Tz: TimeZone>(
+        &self,
+        airport: AirportIcao,
+        date: NaiveDate,
+        tz: &Tz,
+    ) -> Blocking<AirportFlightsRequest> {
+        self.wrap(self.api.get_departures_on(airport, date, tz))
+    }
+
+    /// See crate::OpenSkyApi::get_congestion()
+    pub fn get_congestion(
+        &self,
+        airport: AirportIcao,
+        begin: u64,
+        end: u64,
+    ) -> Blocking<CongestionRequest> {
+        self.wrap(self.api.get_congestion(airport, begin, end))
+    }
+
+    /// See crate::OpenSkyApi::get_congestion_on_days()
+    pub fn get_congestion_on_days<Tz: TimeZone>(
+        &self,
+        airport: AirportIcao,
+        first: NaiveDate,
+        last: NaiveDate,
+        tz: &Tz,
+    ) -> Blocking<CongestionRequest> {
+        self.wrap(self.api.get_congestion_on_days(airport, first, last, tz))
+    }
+
+    /// See crate::OpenSkyApi::get_aircraft_metadata()
+    pub fn get_aircraft_metadata(&self, icao24: String) -> Blocking<MetadataRequest> {
+        self.wrap(self.api.get_aircraft_metadata(icao24))
+    }
+
+    /// See crate::OpenSkyApi::get_aircraft_metadata_many()
+    pub fn get_aircraft_metadata_many<I>(&self, icao24s: I) -> Blocking<MetadataBatchRequest>
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        self.wrap(self.api.get_aircraft_metadata_many(icao24s))
+    }
+
+    /// See crate::OpenSkyApi::enrich()
+    pub fn enrich<T: Aircraft>(&self, records: Vec<T>) -> Blocking<EnrichRequest<T>> {
+        self.wrap(self.api.enrich(records))
+    }
+
+    /// See crate::OpenSkyApi::get_track()
+    pub fn get_track(&self, icao24: String, time: u64) -> Blocking<TrackRequest> {
+        self.wrap(self.api.get_track(icao24, time))
+    }
+
+    /// See crate::OpenSkyApi::get_track_for_flight()
+    pub fn get_track_for_flight(&self, flight: &Flight) -> Blocking<TrackRequest> {
+        self.wrap(self.api.get_track_for_flight(flight))
+    }
+
+    /// See crate::OpenSkyApi::get_tracks_for_flights()
+    pub fn get_tracks_for_flights(&self, flights: &[Flight]) -> Blocking<TrackBatchRequest> {
+        self.wrap(self.api.get_tracks_for_flights(flights))
+    }
+}
+
+/// A request or request builder whose send() blocks until the response has been received.
+/// Methods which do not send anything, like to_curl(), are available through get_ref().
+///
+#[derive(Debug, Clone)]
+pub struct Blocking<R> {
+    request: R,
+    runtime: Arc<Runtime>,
+}
+
+impl<R> Blocking<R> {
+    /// Returns the wrapped async request
+    pub fn get_ref(&self) -> &R {
+        &self.request
+    }
+
+    /// Returns the wrapped async request
+    pub fn into_inner(self) -> R {
+        self.request
+    }
+
+    fn map<S>(self, f: impl FnOnce(R) -> S) -> Blocking<S> {
+        Blocking {
+            request: f(self.request),
+            runtime: self.runtime,
+        }
+    }
+}
+
+impl Blocking<StateRequestBuilder> {
+    /// See StateRequestBuilder::with_bbox()
+    pub fn with_bbox(self, bbox: impl Into<BoundingBox>) -> Self {
+        self.map(|request| request.with_bbox(bbox))
+    }
+
+    /// See StateRequestBuilder::at_time()
+    pub fn at_time(self, timestamp: u64) -> Self {
+        self.map(|request| request.at_time(timestamp))
+    }
+
+    /// See StateRequestBuilder::at_datetime()
+    pub fn at_datetime(self, datetime: DateTime<Utc>) -> Self {
+        self.map(|request| request.at_datetime(datetime))
+    }
+
+    /// See StateRequestBuilder::at_snapped_time()
+    pub fn at_snapped_time(self, timestamp: u64) -> Self {
+        self.map(|request| request.at_snapped_time(timestamp))
+    }
+
+    /// See StateRequestBuilder::with_icao24()
+    pub fn with_icao24(self, address: String) -> Self {
+        self.map(|request| request.with_icao24(address))
+    }
+
+    /// See StateRequestBuilder::extended()
+    pub fn extended(self) -> Self {
+        self.map(StateRequestBuilder::extended)
+    }
+
+    /// See StateRequestBuilder::with_validators()
+    pub fn with_validators(self, validators: Validators) -> Self {
+        self.map(|request| request.with_validators(validators))
+    }
+
+    /// See StateRequestBuilder::within_radius()
+    pub fn within_radius(
+        self,
+        latitude: f64,
+        longitude: f64,
+        radius_km: f64,
+    ) -> Blocking<RadiusStateRequest> {
+        self.map(|request| request.within_radius(latitude, longitude, radius_km))
+    }
+
+    /// See StateRequestBuilder::tiled()
+    pub fn tiled(self, max_tile_degrees: f64) -> Result<Blocking<TiledStateRequest>, Error> {
+        Ok(Blocking {
+            request: self.request.tiled(max_tile_degrees)?,
+            runtime: self.runtime,
+        })
+    }
+
+    /// Consumes this builder and returns a new StateRequest
+    pub fn consume(self) -> Blocking<StateRequest> {
+        self.map(StateRequestBuilder::consume)
+    }
+
+    /// Returns a new StateRequest with a copy of the builder's parameters
+    pub fn finish(&self) -> Blocking<StateRequest> {
+        Blocking {
+            request: self.request.finish(),
+            runtime: self.runtime.clone(),
+        }
+    }
+
+    pub fn send(self) -> Result<States, Error> {
+        self.consume().send()
+    }
+
+    pub fn send_keep_raw(self) -> Result<WithRaw<States>, Error> {
+        self.consume().send_keep_raw()
+    }
+}
+
+impl Blocking<OwnStatesRequestBuilder> {
+    /// See OwnStatesRequestBuilder::at_time()
+    pub fn at_time(self, timestamp: u64) -> Self {
+        self.map(|request| request.at_time(timestamp))
+    }
+
+    /// See OwnStatesRequestBuilder::at_datetime()
+    pub fn at_datetime(self, datetime: DateTime<Utc>) -> Self {
+        self.map(|request| request.at_datetime(datetime))
+    }
+
+    /// See OwnStatesRequestBuilder::with_icao24()
+    pub fn with_icao24(self, address: String) -> Self {
+        self.map(|request| request.with_icao24(address))
+    }
+
+    /// See OwnStatesRequestBuilder::with_serial()
+    pub fn with_serial(self, serial: u64) -> Self {
+        self.map(|request| request.with_serial(serial))
+    }
+
+    /// See OwnStatesRequestBuilder::with_validators()
+    pub fn with_validators(self, validators: Validators) -> Self {
+        self.map(|request| request.with_validators(validators))
+    }
+
+    /// Consumes this builder and returns a new StateRequest
+    pub fn consume(self) -> Blocking<StateRequest> {
+        self.map(OwnStatesRequestBuilder::consume)
+    }
+
+    pub fn send(self) -> Result<States, Error> {
+        self.consume().send()
+    }
+}
+
+impl Blocking<StateRequest> {
+    pub fn send(&self) -> Result<States, Error> {
+        self.runtime.block_on(self.request.send())
+    }
+
+    pub fn send_with_report(&self) -> Result<(States, ValidationReport), Error> {
+        self.runtime.block_on(self.request.send_with_report())
+    }
+
+    pub fn send_keep_raw(&self) -> Result<WithRaw<States>, Error> {
+        self.runtime.block_on(self.request.send_keep_raw())
+    }
+}
+
+impl Blocking<TiledStateRequest> {
+    /// See TiledStateRequest::with_concurrency()
+    pub fn with_concurrency(self, concurrency: usize) -> Self {
+        self.map(|request| request.with_concurrency(concurrency))
+    }
+
+    pub fn send(&self) -> Result<States, Error> {
+        self.runtime.block_on(self.request.send())
+    }
+}
+
+impl Blocking<RadiusStateRequest> {
+    pub fn send(&self) -> Result<NearbyStates, Error> {
+        self.runtime.block_on(self.request.send())
+    }
+}
+
+impl Blocking<FlightsRequestBuilder> {
+    /// See FlightsRequestBuilder::in_interval()
+    pub fn in_interval(&mut self, begin: u64, end: u64) -> &mut Self {
+        self.request.in_interval(begin, end);
+
+        self
+    }
+
+    /// See FlightsRequestBuilder::in_time_range()
+    pub fn in_time_range(&mut self, range: Range<DateTime<Utc>>) -> &mut Self {
+        self.request.in_time_range(range);
+
+        self
+    }
+
+    /// See FlightsRequestBuilder::by_aircraft()
+    pub fn by_aircraft(&mut self, address: String) -> &mut Self {
+        self.request.by_aircraft(address);
+
+        self
+    }
+
+    /// Consumes this builder and returns a new FlightsRequest
+    pub fn consume(self) -> Blocking<FlightsRequest> {
+        self.map(FlightsRequestBuilder::consume)
+    }
+
+    /// Returns a new FlightsRequest with a copy of the builder's parameters
+    pub fn finish(&self) -> Blocking<FlightsRequest> {
+        Blocking {
+            request: self.request.finish(),
+            runtime: self.runtime.clone(),
+        }
+    }
+
+    pub fn send(self) -> Result<Vec<Flight>, Error> {
+        self.consume().send()
+    }
+
+    pub fn send_keep_raw(self) -> Result<WithRaw<Vec<Flight>>, Error> {
+        self.consume().send_keep_raw()
+    }
+}
+
+impl Blocking<FlightsRequest> {
+    pub fn send(&self) -> Result<Vec<Flight>, Error> {
+        self.runtime.block_on(self.request.send())
+    }
+
+    pub fn send_keep_raw(&self) -> Result<WithRaw<Vec<Flight>>, Error> {
+        self.runtime.block_on(self.request.send_keep_raw())
+    }
+}
+
+impl Blocking<AirportFlightsRequest> {
+    /// See AirportFlightsRequest::with_concurrency()
+    pub fn with_concurrency(self, concurrency: usize) -> Self {
+        self.map(|request| request.with_concurrency(concurrency))
+    }
+
+    pub fn send(&self) -> Result<Vec<Flight>, Error> {
+        self.runtime.block_on(self.request.send())
+    }
+
+    pub fn send_keep_raw(&self) -> Result<Vec<WithRaw<Vec<Flight>>>, Error> {
+        self.runtime.block_on(self.request.send_keep_raw())
+    }
+}
+
+impl Blocking<RouteFlightsRequest> {
+    /// See RouteFlightsRequest::with_concurrency()
+    pub fn with_concurrency(self, concurrency: usize) -> Self {
+        self.map(|request| request.with_concurrency(concurrency))
+    }
+
+    pub fn send(&self) -> Result<Vec<Flight>, Error> {
+        self.runtime.block_on(self.request.send())
+    }
+}
+
+impl Blocking<CongestionRequest> {
+    pub fn send(&self) -> Result<CongestionReport, Error> {
+        self.runtime.block_on(self.request.send())
+    }
+}
+
+impl Blocking<MetadataRequest> {
+    pub fn send(&self) -> Result<Option<AircraftMetadata>, Error> {
+        self.runtime.block_on(self.request.send())
+    }
+}
+
+impl Blocking<TrackRequest> {
+    pub fn send(&self) -> Result<Option<FlightTrack>, Error> {
+        self.runtime.block_on(self.request.send())
+    }
+
+    pub fn send_keep_raw(&self) -> Result<WithRaw<Option<FlightTrack>>, Error> {
+        self.runtime.block_on(self.request.send_keep_raw())
+    }
+}
+
+impl Blocking<TrackBatchRequest> {
+    /// See TrackBatchRequest::with_concurrency()
+    pub fn with_concurrency(self, concurrency: usize) -> Self {
+        self.map(|request| request.with_concurrency(concurrency))
+    }
+
+    pub fn send(&self) -> Result<Vec<Option<FlightTrack>>, Error> {
+        self.runtime.block_on(self.request.send())
+    }
+}
+
+impl<T: Aircraft> Blocking<EnrichRequest<T>> {
+    /// See EnrichRequest::with_concurrency()
+    pub fn with_concurrency(self, concurrency: usize) -> Self {
+        self.map(|request| request.with_concurrency(concurrency))
+    }
+
+    pub fn send(self) -> Result<Vec<WithMetadata<T>>, Error> {
+        self.runtime.block_on(self.request.send())
+    }
+}
+
+impl Blocking<MetadataBatchRequest> {
+    /// See MetadataBatchRequest::with_concurrency()
+    pub fn with_concurrency(self, concurrency: usize) -> Self {
+        self.map(|request| request.with_concurrency(concurrency))
+    }
+
+    pub fn send(&self) -> Result<HashMap<String, AircraftMetadata>, Error> {
+        self.runtime.block_on(self.request.send())
+    }
+}
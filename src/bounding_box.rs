@@ -1,13 +1,45 @@
-#[derive(Debug, Clone, Copy)]
+use crate::errors::Error;
+
+/// The largest number of tiles BoundingBox::tiles() splits a bounding box into
+pub const MAX_TILES: usize = 10_000;
+
+/// Mean radius of the Earth in kilometers, used for great-circle distances
+pub(crate) const EARTH_RADIUS_KM: f64 = 6371.0;
+
+/// Returns the great-circle distance in kilometers between two points
+pub(crate) fn great_circle_km(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let d_lat = (lat2 - lat1).to_radians();
+    let d_lon = (lon2 - lon1).to_radians();
+
+    let h = (d_lat / 2.0).sin().powi(2)
+        + lat1.to_radians().cos() * lat2.to_radians().cos() * (d_lon / 2.0).sin().powi(2);
+
+    2.0 * EARTH_RADIUS_KM * h.sqrt().min(1.0).asin()
+}
+
+/// Returns the initial great-circle bearing from the first to the second point in degrees
+/// clockwise from north, in the range [0, 360)
+///
+pub(crate) fn initial_bearing(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let (lat1, lat2) = (lat1.to_radians(), lat2.to_radians());
+    let d_lon = (lon2 - lon1).to_radians();
+
+    let y = d_lon.sin() * lat2.cos();
+    let x = lat1.cos() * lat2.sin() - lat1.sin() * lat2.cos() * d_lon.cos();
+
+    y.atan2(x).to_degrees().rem_euclid(360.0)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct BoundingBox {
-    pub lat_min: f32,
-    pub lat_max: f32,
-    pub long_min: f32,
-    pub long_max: f32,
+    pub lat_min: f64,
+    pub lat_max: f64,
+    pub long_min: f64,
+    pub long_max: f64,
 }
 
 impl BoundingBox {
-    pub fn new(lat_min: f32, lat_max: f32, long_min: f32, long_max: f32) -> Self {
+    pub fn new(lat_min: f64, lat_max: f64, long_min: f64, long_max: f64) -> Self {
         Self {
             lat_min,
             lat_max,
@@ -15,4 +47,143 @@ impl BoundingBox {
             long_max,
         }
     }
+
+    /// Creates a new BoundingBox, checking it like validate()
+    pub fn try_new(
+        lat_min: f64,
+        lat_max: f64,
+        long_min: f64,
+        long_max: f64,
+    ) -> Result<Self, Error> {
+        let bbox = Self::new(lat_min, lat_max, long_min, long_max);
+        bbox.validate()?;
+
+        Ok(bbox)
+    }
+
+    /// Checks that the latitudes are within ±90 and the longitudes within ±180 degrees, and
+    /// that the minimums are not greater than the maximums. Boxes crossing the antimeridian
+    /// are not supported by the API and have to be split in two.
+    ///
+    pub fn validate(&self) -> Result<(), Error> {
+        let latitudes = -90.0..=90.0;
+        let longitudes = -180.0..=180.0;
+
+        if !latitudes.contains(&self.lat_min) || !latitudes.contains(&self.lat_max) {
+            return Err(Error::InvalidRequest(format!(
+                "bounding box latitudes {} to {} are outside of ±90 degrees",
+                self.lat_min, self.lat_max
+            )));
+        }
+
+        if !longitudes.contains(&self.long_min) || !longitudes.contains(&self.long_max) {
+            return Err(Error::InvalidRequest(format!(
+                "bounding box longitudes {} to {} are outside of ±180 degrees",
+                self.long_min, self.long_max
+            )));
+        }
+
+        if self.lat_min > self.lat_max || self.long_min > self.long_max {
+            return Err(Error::InvalidRequest(String::from(
+                "bounding box minimums must not be greater than its maximums",
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Returns true if the point lies within this bounding box, including its edges
+    pub fn contains(&self, latitude: f64, longitude: f64) -> bool {
+        (self.lat_min..=self.lat_max).contains(&latitude)
+            && (self.long_min..=self.long_max).contains(&longitude)
+    }
+
+    /// Returns the smallest bounding box containing every point within radius_km kilometers
+    /// (great-circle distance) of the given point. Circles containing a pole or crossing the
+    /// antimeridian span all longitudes, because OpenSky's bounding boxes cannot wrap around.
+    ///
+    pub fn enclosing_circle(latitude: f64, longitude: f64, radius_km: f64) -> Self {
+        let angle = (radius_km / EARTH_RADIUS_KM).min(std::f64::consts::PI);
+        let lat = latitude;
+
+        let lat_min = lat - angle.to_degrees();
+        let lat_max = lat + angle.to_degrees();
+
+        let (long_min, long_max) = if lat_min <= -90.0 || lat_max >= 90.0 {
+            (-180.0, 180.0)
+        } else {
+            let d_lon = (angle.sin() / lat.to_radians().cos())
+                .min(1.0)
+                .asin()
+                .to_degrees();
+            let (min, max) = (longitude - d_lon, longitude + d_lon);
+
+            if min < -180.0 || max > 180.0 {
+                (-180.0, 180.0)
+            } else {
+                (min, max)
+            }
+        };
+
+        Self::new(lat_min.max(-90.0), lat_max.min(90.0), long_min, long_max)
+    }
+
+    /// Splits this bounding box into a grid of equally sized tiles which each span at most
+    /// max_degrees of latitude and of longitude. Neighbouring tiles share their edges.
+    ///
+    /// Returns Error::InvalidRequest if max_degrees is not a positive number, or if the box
+    /// would be split into more than MAX_TILES tiles.
+    ///
+    pub fn tiles(&self, max_degrees: f64) -> Result<Vec<BoundingBox>, Error> {
+        if !max_degrees.is_finite() || max_degrees <= 0.0 {
+            return Err(Error::InvalidRequest(format!(
+                "tiles must span a positive number of degrees, got {}",
+                max_degrees
+            )));
+        }
+
+        let count = |span: f64| (span / max_degrees).ceil().max(1.0);
+        let (rows, columns) = (
+            count(self.lat_max - self.lat_min),
+            count(self.long_max - self.long_min),
+        );
+
+        if rows * columns > MAX_TILES as f64 {
+            return Err(Error::InvalidRequest(format!(
+                "tiles of {} degrees would split the bounding box into more than {} tiles",
+                max_degrees, MAX_TILES
+            )));
+        }
+
+        let (rows, columns) = (rows as usize, columns as usize);
+        let lat_step = (self.lat_max - self.lat_min) / rows as f64;
+        let long_step = (self.long_max - self.long_min) / columns as f64;
+
+        let mut tiles = Vec::with_capacity(rows * columns);
+
+        for row in 0..rows {
+            for column in 0..columns {
+                // The last tiles end exactly at the maximums, regardless of rounding
+                let lat_max = if row + 1 == rows {
+                    self.lat_max
+                } else {
+                    self.lat_min + lat_step * (row + 1) as f64
+                };
+                let long_max = if column + 1 == columns {
+                    self.long_max
+                } else {
+                    self.long_min + long_step * (column + 1) as f64
+                };
+
+                tiles.push(Self::new(
+                    self.lat_min + lat_step * row as f64,
+                    lat_max,
+                    self.long_min + long_step * column as f64,
+                    long_max,
+                ));
+            }
+        }
+
+        Ok(tiles)
+    }
 }
@@ -0,0 +1,326 @@
+//! Clustering of flight tracks into traffic flows.
+//!
+//! Every track is resampled to the same number of points, evenly spaced along its path, so
+//! tracks of different lengths and sampling rates can be compared. The distance between two
+//! resampled tracks is measured with one of the TrackDistance metrics, and the tracks are
+//! grouped using k-medoids, which makes the representative track of each cluster (its medoid)
+//! an actual flown track instead of an average of several.
+
+use crate::{bounding_box::great_circle_km, tracks::FlightTrack};
+
+/// The default number of points every track is resampled to
+pub const DEFAULT_SAMPLES: usize = 32;
+
+/// The default maximum number of k-medoids iterations
+pub const DEFAULT_MAX_ITERATIONS: usize = 50;
+
+/// How the distance between two resampled tracks is measured
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrackDistance {
+    /// The mean great-circle distance between corresponding points. Tracks flown in opposite
+    /// directions are far apart.
+    ///
+    Pointwise,
+    /// Dynamic time warping over the great-circle distances between points, normalized by
+    /// the number of points. This tolerates tracks that follow the same path at a different
+    /// pace, e.g. when one of them holds.
+    ///
+    Dtw,
+}
+
+/// Groups flight tracks into a fixed number of clusters
+#[derive(Debug, Clone)]
+pub struct TrackClustering {
+    clusters: usize,
+    samples: usize,
+    metric: TrackDistance,
+    max_iterations: usize,
+}
+
+impl TrackClustering {
+    /// Creates a new TrackClustering which groups tracks into the given number of clusters,
+    /// using DTW distances between tracks resampled to DEFAULT_SAMPLES points
+    ///
+    pub fn new(clusters: usize) -> Self {
+        Self {
+            clusters: clusters.max(1),
+            samples: DEFAULT_SAMPLES,
+            metric: TrackDistance::Dtw,
+            max_iterations: DEFAULT_MAX_ITERATIONS,
+        }
+    }
+
+    /// Sets the number of points every track is resampled to
+    pub fn with_samples(mut self, samples: usize) -> Self {
+        self.samples = samples.max(2);
+
+        self
+    }
+
+    /// Sets the metric used to measure the distance between two tracks
+    pub fn with_metric(mut self, metric: TrackDistance) -> Self {
+        self.metric = metric;
+
+        self
+    }
+
+    /// Sets the maximum number of iterations before the clustering stops, even if the
+    /// clusters still change
+    ///
+    pub fn with_max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = max_iterations;
+
+        self
+    }
+
+    /// Returns the distance in kilometers between two tracks, or None if either of them has
+    /// fewer than two positions
+    ///
+    pub fn distance(&self, a: &FlightTrack, b: &FlightTrack) -> Option<f64> {
+        let a = resample(a, self.samples)?;
+        let b = resample(b, self.samples)?;
+
+        Some(self.metric.measure(&a, &b))
+    }
+
+    /// Clusters the tracks. Tracks with fewer than two positions are not assigned to any
+    /// cluster. The result is deterministic for the same input.
+    ///
+    pub fn cluster(&self, tracks: &[FlightTrack]) -> Clusters {
+        let resampled: Vec<Option<Vec<(f64, f64)>>> = tracks
+            .iter()
+            .map(|track| resample(track, self.samples))
+            .collect();
+
+        // Indices of the tracks that take part in the clustering
+        let valid: Vec<usize> = (0..tracks.len())
+            .filter(|&i| resampled[i].is_some())
+            .collect();
+
+        let n = valid.len();
+        let mut distances = vec![0.0; n * n];
+
+        for i in 0..n {
+            for j in (i + 1)..n {
+                let a = resampled[valid[i]].as_ref().unwrap();
+                let b = resampled[valid[j]].as_ref().unwrap();
+                let distance = self.metric.measure(a, b);
+
+                distances[i * n + j] = distance;
+                distances[j * n + i] = distance;
+            }
+        }
+
+        let distance = |i: usize, j: usize| distances[i * n + j];
+        let k = self.clusters.min(n);
+
+        let mut medoids = initial_medoids(n, k, &distance);
+        let mut assignments = assign(n, &medoids, &distance);
+
+        for _ in 0..self.max_iterations {
+            let updated: Vec<usize> = (0..medoids.len())
+                .map(|cluster| {
+                    let members: Vec<usize> =
+                        (0..n).filter(|&i| assignments[i] == cluster).collect();
+
+                    members
+                        .iter()
+                        .copied()
+                        .min_by(|&a, &b| {
+                            let cost_a: f64 = members.iter().map(|&m| distance(a, m)).sum();
+                            let cost_b: f64 = members.iter().map(|&m| distance(b, m)).sum();
+
+                            cost_a.total_cmp(&cost_b).then(a.cmp(&b))
+                        })
+                        .unwrap_or(medoids[cluster])
+                })
+                .collect();
+
+            if updated == medoids {
+                break;
+            }
+
+            medoids = updated;
+            assignments = assign(n, &medoids, &distance);
+        }
+
+        let cost = (0..n).map(|i| distance(i, medoids[assignments[i]])).sum();
+
+        let mut track_assignments = vec![None; tracks.len()];
+        for (i, &track) in valid.iter().enumerate() {
+            track_assignments[track] = Some(assignments[i]);
+        }
+
+        Clusters {
+            assignments: track_assignments,
+            medoids: medoids.into_iter().map(|medoid| valid[medoid]).collect(),
+            cost,
+        }
+    }
+}
+
+impl TrackDistance {
+    fn measure(&self, a: &[(f64, f64)], b: &[(f64, f64)]) -> f64 {
+        let point = |p: (f64, f64), q: (f64, f64)| great_circle_km(p.0, p.1, q.0, q.1);
+
+        match self {
+            TrackDistance::Pointwise => {
+                a.iter().zip(b).map(|(&p, &q)| point(p, q)).sum::<f64>() / a.len() as f64
+            }
+            TrackDistance::Dtw => {
+                let m = b.len();
+                let mut previous = vec![f64::INFINITY; m + 1];
+                let mut current = vec![f64::INFINITY; m + 1];
+                previous[0] = 0.0;
+
+                for &p in a {
+                    current[0] = f64::INFINITY;
+
+                    for (j, &q) in b.iter().enumerate() {
+                        let best = previous[j].min(previous[j + 1]).min(current[j]);
+                        current[j + 1] = point(p, q) + best;
+                    }
+
+                    std::mem::swap(&mut previous, &mut current);
+                }
+
+                previous[m] / (a.len() + b.len()) as f64
+            }
+        }
+    }
+}
+
+/// The result of TrackClustering::cluster()
+#[derive(Debug, Clone)]
+pub struct Clusters {
+    /// The cluster of every track, in the order the tracks were given. None for tracks with
+    /// fewer than two positions.
+    ///
+    pub assignments: Vec<Option<usize>>,
+    /// The index of the medoid track of every cluster
+    pub medoids: Vec<usize>,
+    /// The sum of the distances of all tracks to the medoids of their clusters
+    pub cost: f64,
+}
+
+impl Clusters {
+    /// Returns the number of clusters
+    pub fn len(&self) -> usize {
+        self.medoids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.medoids.is_empty()
+    }
+
+    /// Returns the indices of the tracks in the given cluster
+    pub fn members(&self, cluster: usize) -> Vec<usize> {
+        self.assignments
+            .iter()
+            .enumerate()
+            .filter(|(_, assignment)| **assignment == Some(cluster))
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Returns the representative track of every cluster, given the tracks that were clustered
+    pub fn medoid_tracks<'a>(&self, tracks: &'a [FlightTrack]) -> Vec<&'a FlightTrack> {
+        self.medoids.iter().map(|&medoid| &tracks[medoid]).collect()
+    }
+}
+
+/// Resamples the positions of a track to the given number of points, evenly spaced along the
+/// path. Returns None if the track has fewer than two positions.
+///
+pub fn resample(track: &FlightTrack, samples: usize) -> Option<Vec<(f64, f64)>> {
+    let points: Vec<(f64, f64)> = track
+        .path
+        .iter()
+        .filter_map(|waypoint| Some((waypoint.latitude?, waypoint.longitude?)))
+        .collect();
+
+    if points.len() < 2 || samples < 2 {
+        return None;
+    }
+
+    let mut cumulative = vec![0.0];
+    for pair in points.windows(2) {
+        let length = great_circle_km(pair[0].0, pair[0].1, pair[1].0, pair[1].1);
+        cumulative.push(cumulative.last().unwrap() + length);
+    }
+
+    let total = *cumulative.last().unwrap();
+    let mut resampled = Vec::with_capacity(samples);
+    let mut segment = 0;
+
+    for i in 0..samples {
+        let target = total * i as f64 / (samples - 1) as f64;
+
+        while segment + 2 < points.len() && cumulative[segment + 1] < target {
+            segment += 1;
+        }
+
+        let length = cumulative[segment + 1] - cumulative[segment];
+        let t = if length > 0.0 {
+            ((target - cumulative[segment]) / length).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+
+        let (a, b) = (points[segment], points[segment + 1]);
+        resampled.push((a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t));
+    }
+
+    Some(resampled)
+}
+
+/// Picks the most central track as the first medoid, then repeatedly the track furthest from
+/// all medoids picked so far
+///
+fn initial_medoids(n: usize, k: usize, distance: &impl Fn(usize, usize) -> f64) -> Vec<usize> {
+    if n == 0 {
+        return Vec::new();
+    }
+
+    let central = (0..n)
+        .min_by(|&a, &b| {
+            let cost_a: f64 = (0..n).map(|i| distance(a, i)).sum();
+            let cost_b: f64 = (0..n).map(|i| distance(b, i)).sum();
+
+            cost_a.total_cmp(&cost_b)
+        })
+        .unwrap();
+
+    let mut medoids = vec![central];
+
+    while medoids.len() < k {
+        let furthest = (0..n)
+            .filter(|i| !medoids.contains(i))
+            .max_by(|&a, &b| {
+                let nearest = |i: usize| {
+                    medoids
+                        .iter()
+                        .map(|&m| distance(i, m))
+                        .fold(f64::INFINITY, f64::min)
+                };
+
+                nearest(a).total_cmp(&nearest(b)).then(b.cmp(&a))
+            })
+            .unwrap();
+
+        medoids.push(furthest);
+    }
+
+    medoids
+}
+
+/// Assigns every track to the cluster of its nearest medoid
+fn assign(n: usize, medoids: &[usize], distance: &impl Fn(usize, usize) -> f64) -> Vec<usize> {
+    (0..n)
+        .map(|i| {
+            (0..medoids.len())
+                .min_by(|&a, &b| distance(i, medoids[a]).total_cmp(&distance(i, medoids[b])))
+                .unwrap()
+        })
+        .collect()
+}
@@ -0,0 +1,188 @@
+//! Movement statistics for airports, computed from their arrivals and departures.
+
+use crate::{
+    errors::Error,
+    flights::{AirportFlightsRequest, Flight},
+};
+
+const HOUR: u64 = 3600;
+
+/// The number of arrivals and departures within one hour
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HourlyMovements {
+    /// Start of the hour in seconds since the Unix Epoch
+    pub start: u64,
+    pub arrivals: usize,
+    pub departures: usize,
+}
+
+impl HourlyMovements {
+    /// Returns the number of arrivals and departures
+    pub fn total(&self) -> usize {
+        self.arrivals + self.departures
+    }
+}
+
+/// The busiest window of consecutive hours of a CongestionReport
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RollingPeak {
+    /// Start of the first hour of the window in seconds since the Unix Epoch
+    pub start: u64,
+    /// End of the last hour of the window in seconds since the Unix Epoch
+    pub end: u64,
+    pub arrivals: usize,
+    pub departures: usize,
+}
+
+impl RollingPeak {
+    /// Returns the number of arrivals and departures
+    pub fn total(&self) -> usize {
+        self.arrivals + self.departures
+    }
+}
+
+/// Hourly arrival and departure counts of an airport over a time interval. Arrivals are
+/// counted in the hour the aircraft was last seen, departures in the hour it was first seen.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CongestionReport {
+    pub airport: String,
+    /// Start of the report in seconds since the Unix Epoch. Hours are counted from here, so a
+    /// report starting at local midnight has local hours.
+    ///
+    pub begin: u64,
+    /// End of the report in seconds since the Unix Epoch
+    pub end: u64,
+    pub hours: Vec<HourlyMovements>,
+}
+
+impl CongestionReport {
+    /// Computes the report from the arrivals and departures of the airport. Flights outside of
+    /// [begin, end] are ignored.
+    ///
+    pub fn from_flights(
+        airport: &str,
+        begin: u64,
+        end: u64,
+        arrivals: &[Flight],
+        departures: &[Flight],
+    ) -> Self {
+        let count = end.saturating_sub(begin) / HOUR + 1;
+
+        let mut hours: Vec<HourlyMovements> = (0..count)
+            .map(|hour| HourlyMovements {
+                start: begin + hour * HOUR,
+                arrivals: 0,
+                departures: 0,
+            })
+            .collect();
+
+        let hour_of = |time: u64| {
+            (begin..=end)
+                .contains(&time)
+                .then(|| ((time - begin) / HOUR) as usize)
+        };
+
+        for flight in arrivals {
+            if let Some(hour) = hour_of(flight.last_seen) {
+                hours[hour].arrivals += 1;
+            }
+        }
+
+        for flight in departures {
+            if let Some(hour) = hour_of(flight.first_seen) {
+                hours[hour].departures += 1;
+            }
+        }
+
+        Self {
+            airport: airport.trim().to_uppercase(),
+            begin,
+            end,
+            hours,
+        }
+    }
+
+    pub fn total_arrivals(&self) -> usize {
+        self.hours.iter().map(|hour| hour.arrivals).sum()
+    }
+
+    pub fn total_departures(&self) -> usize {
+        self.hours.iter().map(|hour| hour.departures).sum()
+    }
+
+    /// Returns the number of arrivals per departure over the whole report, or None if there
+    /// were no departures
+    ///
+    pub fn arrival_departure_ratio(&self) -> Option<f64> {
+        let departures = self.total_departures();
+
+        (departures > 0).then(|| self.total_arrivals() as f64 / departures as f64)
+    }
+
+    /// Returns the hour with the most movements. Ties are resolved in favor of the earliest
+    /// hour.
+    ///
+    pub fn busiest_hour(&self) -> Option<&HourlyMovements> {
+        self.hours.iter().rev().max_by_key(|hour| hour.total())
+    }
+
+    /// Returns the total movements of every window of the given number of consecutive hours,
+    /// keyed by the start of the window
+    ///
+    pub fn rolling_totals(&self, window_hours: usize) -> Vec<(u64, usize)> {
+        self.hours
+            .windows(window_hours.max(1))
+            .map(|window| (window[0].start, window.iter().map(|h| h.total()).sum()))
+            .collect()
+    }
+
+    /// Returns the window of the given number of consecutive hours with the most movements,
+    /// or None if the report is shorter than the window
+    ///
+    pub fn rolling_peak(&self, window_hours: usize) -> Option<RollingPeak> {
+        self.hours
+            .windows(window_hours.max(1))
+            .rev()
+            .max_by_key(|window| window.iter().map(|h| h.total()).sum::<usize>())
+            .map(|window| RollingPeak {
+                start: window[0].start,
+                end: window[window.len() - 1].start + HOUR,
+                arrivals: window.iter().map(|h| h.arrivals).sum(),
+                departures: window.iter().map(|h| h.departures).sum(),
+            })
+    }
+}
+
+/// Gets the arrivals and departures of an airport and computes a CongestionReport from them
+#[derive(Debug, Clone)]
+pub struct CongestionRequest {
+    arrivals: AirportFlightsRequest,
+    departures: AirportFlightsRequest,
+}
+
+impl CongestionRequest {
+    /// Creates a new CongestionRequest from requests for the arrivals and departures of the
+    /// same airport over the same interval
+    ///
+    pub fn new(arrivals: AirportFlightsRequest, departures: AirportFlightsRequest) -> Self {
+        Self {
+            arrivals,
+            departures,
+        }
+    }
+
+    pub async fn send(&self) -> Result<CongestionReport, Error> {
+        let arrivals = self.arrivals.send().await?;
+        let departures = self.departures.send().await?;
+        let (begin, end) = self.arrivals.interval();
+
+        Ok(CongestionReport::from_flights(
+            self.arrivals.airport(),
+            begin,
+            end,
+            &arrivals,
+            &departures,
+        ))
+    }
+}
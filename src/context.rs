@@ -0,0 +1,642 @@
+//! State shared by all requests created from the same OpenSkyApi instance.
+
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use log::{debug, warn};
+use tokio::sync::Semaphore;
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::transport::{SharedTransport, Transport};
+use crate::{
+    auth::{OAuth2Credentials, TokenProvider},
+    credentials::CredentialPool,
+    endpoints::Endpoints,
+    errors::Error,
+    http_cache::HttpCache,
+    metrics::{MetricsObserver, Observers},
+    middleware::{Middleware, MiddlewareStack},
+    policy::{EndpointClass, Policies, RequestPolicy},
+    states::{ANONYMOUS_RESOLUTION, AUTHENTICATED_RESOLUTION},
+    states_cache::StatesCache,
+    time::Instant,
+};
+
+/// The URL requests are sent to unless it is overridden using OpenSkyApi::with_base_url()
+pub const DEFAULT_BASE_URL: &str = "https://opensky-network.org";
+
+/// The User-Agent sent unless it is overridden using OpenSkyApi::with_user_agent(), so that
+/// OpenSky's operators can identify traffic from this crate
+///
+pub const DEFAULT_USER_AGENT: &str = concat!("opensky_api.rs/", env!("CARGO_PKG_VERSION"));
+
+/// The login, base URL, endpoint paths, request policies, and request limits used by the
+/// requests created from an OpenSkyApi instance. Cloning a RequestContext is cheap, and clones
+/// share the same limits.
+///
+#[derive(Debug, Clone)]
+pub struct RequestContext {
+    login: Option<Arc<(String, String)>>,
+    oauth2: Option<Arc<TokenProvider>>,
+    pool: Option<Arc<CredentialPool>>,
+    client: reqwest::Client,
+    base_url: Arc<str>,
+    endpoints: Arc<Endpoints>,
+    policies: Policies,
+    limiter: Option<Arc<Semaphore>>,
+    middleware: MiddlewareStack,
+    user_agent: Option<reqwest::header::HeaderValue>,
+    anonymous_fallback: bool,
+    anonymous_fallbacks: Arc<AtomicU64>,
+    http_cache: Option<HttpCache>,
+    states_cache: Option<StatesCache>,
+    observers: Observers,
+    #[cfg(not(target_arch = "wasm32"))]
+    transport: Option<SharedTransport>,
+}
+
+impl Default for RequestContext {
+    fn default() -> Self {
+        Self {
+            login: None,
+            oauth2: None,
+            pool: None,
+            client: client_builder()
+                .build()
+                .expect("failed to build the HTTP client"),
+            base_url: Arc::from(DEFAULT_BASE_URL),
+            endpoints: Arc::default(),
+            policies: Policies::default(),
+            limiter: None,
+            middleware: MiddlewareStack::default(),
+            user_agent: None,
+            anonymous_fallback: false,
+            anonymous_fallbacks: Arc::default(),
+            http_cache: None,
+            states_cache: None,
+            observers: Observers::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            transport: None,
+        }
+    }
+}
+
+impl RequestContext {
+    pub(crate) fn new(login: Option<(String, String)>) -> Self {
+        Self {
+            login: login.map(Arc::new),
+            ..Self::default()
+        }
+    }
+
+    pub(crate) fn with_oauth2(credentials: OAuth2Credentials) -> Self {
+        Self {
+            oauth2: Some(Arc::new(TokenProvider::new(credentials))),
+            ..Self::default()
+        }
+    }
+
+    pub(crate) fn with_pool(pool: CredentialPool) -> Self {
+        Self {
+            pool: Some(Arc::new(pool)),
+            ..Self::default()
+        }
+    }
+
+    /// Returns the accounts requests are spread over, if there are several
+    pub fn credential_pool(&self) -> Option<&CredentialPool> {
+        self.pool.as_deref()
+    }
+
+    pub(crate) fn set_client(&mut self, client: reqwest::Client) {
+        self.client = client;
+    }
+
+    /// Overrides the User-Agent of the client for every request
+    pub(crate) fn set_user_agent(&mut self, user_agent: reqwest::header::HeaderValue) {
+        self.user_agent = Some(user_agent);
+    }
+
+    pub(crate) fn set_base_url(&mut self, base_url: &str) {
+        self.base_url = Arc::from(base_url.trim_end_matches('/'));
+    }
+
+    /// Returns the scheme and host (and optionally a path prefix) of all request URLs
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    pub(crate) fn set_endpoints(&mut self, endpoints: Endpoints) {
+        self.endpoints = Arc::new(endpoints);
+    }
+
+    pub(crate) fn set_max_concurrent_requests(&mut self, max: usize) {
+        self.limiter = Some(Arc::new(Semaphore::new(max.max(1))));
+    }
+
+    pub(crate) fn set_policy(&mut self, class: EndpointClass, policy: RequestPolicy) {
+        self.policies.set(class, policy);
+    }
+
+    pub(crate) fn add_middleware<M: Middleware + 'static>(&mut self, middleware: M) {
+        self.middleware.push(middleware);
+    }
+
+    pub(crate) fn set_anonymous_fallback(&mut self, anonymous_fallback: bool) {
+        self.anonymous_fallback = anonymous_fallback;
+    }
+
+    /// Returns the number of requests which were sent again anonymously because the
+    /// credentials were rejected
+    ///
+    pub fn anonymous_fallbacks(&self) -> u64 {
+        self.anonymous_fallbacks.load(Ordering::Relaxed)
+    }
+
+    /// Returns true if requests are sent with a login, OAuth2 credentials, or a credential pool
+    pub fn is_authenticated(&self) -> bool {
+        self.login.is_some() || self.oauth2.is_some() || self.pool.is_some()
+    }
+
+    /// Returns the time resolution in seconds of the state vectors available to this context:
+    /// states::AUTHENTICATED_RESOLUTION with credentials, otherwise
+    /// states::ANONYMOUS_RESOLUTION
+    ///
+    pub fn time_resolution(&self) -> u64 {
+        if self.is_authenticated() {
+            AUTHENTICATED_RESOLUTION
+        } else {
+            ANONYMOUS_RESOLUTION
+        }
+    }
+
+    pub(crate) fn set_http_cache(&mut self, http_cache: HttpCache) {
+        self.http_cache = Some(http_cache);
+    }
+
+    /// Returns the cache used for conditional requests, if they are enabled
+    pub fn http_cache(&self) -> Option<&HttpCache> {
+        self.http_cache.as_ref()
+    }
+
+    pub(crate) fn set_states_cache(&mut self, states_cache: StatesCache) {
+        self.states_cache = Some(states_cache);
+    }
+
+    /// Returns the cache of state vector responses, if it is enabled
+    pub fn states_cache(&self) -> Option<&StatesCache> {
+        self.states_cache.as_ref()
+    }
+
+    /// Sends requests using the transport instead of the client
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn set_transport<T: Transport + 'static>(&mut self, transport: T) {
+        self.transport = Some(SharedTransport(Arc::new(transport)));
+    }
+
+    pub(crate) fn add_observer<O: MetricsObserver + 'static>(&mut self, observer: O) {
+        self.observers.push(observer);
+    }
+
+    /// Returns the observers which are told about every request
+    pub fn observers(&self) -> &Observers {
+        &self.observers
+    }
+
+    /// Reports a parsing failure of a response from an endpoint of the given class to the
+    /// observers, and passes the result on
+    ///
+    pub(crate) fn observe_parse<T>(
+        &self,
+        class: EndpointClass,
+        result: Result<T, Error>,
+    ) -> Result<T, Error> {
+        self.observers.record_parse(class, result)
+    }
+
+    /// Returns the middleware every request and response is passed through
+    pub fn middleware(&self) -> &MiddlewareStack {
+        &self.middleware
+    }
+
+    /// Returns the retry and timeout policies of all classes of endpoints
+    pub fn policies(&self) -> &Policies {
+        &self.policies
+    }
+
+    pub(crate) fn endpoints(&self) -> &Endpoints {
+        &self.endpoints
+    }
+
+    /// Returns the number of additional requests which could be sent right now without
+    /// waiting, or None if the number of requests in flight is not limited
+    ///
+    pub fn available_permits(&self) -> Option<usize> {
+        self.limiter
+            .as_ref()
+            .map(|limiter| limiter.available_permits())
+    }
+
+    /// Builds the URL of a request to the given endpoint path. Credentials are never part of
+    /// the URL, they are sent in the Authorization header instead.
+    ///
+    pub(crate) fn url(&self, path: &str, args: &str) -> String {
+        format!("{}{}{}", self.base_url, path, args)
+    }
+
+    /// Renders a GET request of the URL as an equivalent curl command. The login is passed
+    /// with -u, and the password is masked unless include_credentials is true. OAuth2 tokens
+    /// are never included, instead the command expects the token in the OPENSKY_TOKEN
+    /// environment variable.
+    ///
+    pub(crate) fn curl(&self, url: &str, include_credentials: bool) -> String {
+        let mut command = String::from("curl");
+
+        // Requests with a credential pool are shown with its first account
+        let pooled = self.pool.as_ref().and_then(|pool| pool.select_first());
+
+        if let Some(login) = pooled.as_ref().or(self.login.as_ref()) {
+            let password = if include_credentials {
+                login.1.clone()
+            } else {
+                String::from("********")
+            };
+
+            command.push_str(&format!(
+                " -u {}",
+                shell_quote(&format!("{}:{}", login.0, password))
+            ));
+        }
+
+        if self.oauth2.is_some() {
+            let token = if include_credentials {
+                "$OPENSKY_TOKEN"
+            } else {
+                "********"
+            };
+
+            command.push_str(&format!(" -H \"Authorization: Bearer {}\"", token));
+        }
+
+        command.push(' ');
+        command.push_str(&shell_quote(url));
+
+        command
+    }
+
+    /// Sends a GET request to an endpoint of the given class and reads the whole response
+    /// body, retrying according to the RequestPolicy of the class. If the number of requests
+    /// in flight is limited, this waits until the request may be sent, and the request counts
+    /// towards the limit until its body has been read.
+    ///
+    pub(crate) async fn get(
+        &self,
+        class: EndpointClass,
+        url: &str,
+    ) -> Result<(reqwest::StatusCode, Vec<u8>), Error> {
+        let states_cache = self
+            .states_cache
+            .as_ref()
+            .filter(|_| class == EndpointClass::States);
+
+        if let Some(body) = states_cache.and_then(|cache| cache.get(url)) {
+            debug!("answering {url} from the states cache");
+            return Ok((reqwest::StatusCode::OK, body.to_vec()));
+        }
+
+        let (status, bytes) = self.fetch(class, url).await?;
+
+        if let Some(cache) = states_cache.filter(|_| status.is_success()) {
+            cache.store(url, &bytes);
+        }
+
+        Ok((status, bytes))
+    }
+
+    async fn fetch(
+        &self,
+        class: EndpointClass,
+        url: &str,
+    ) -> Result<(reqwest::StatusCode, Vec<u8>), Error> {
+        #[cfg(feature = "tracing")]
+        {
+            use tracing::Instrument;
+
+            let span = tracing::debug_span!(
+                "opensky_request",
+                endpoint = ?class,
+                url = %url,
+                status = tracing::field::Empty,
+                duration_ms = tracing::field::Empty,
+            );
+
+            let start = Instant::now();
+            let result = self
+                .get_with_retries(class, url)
+                .instrument(span.clone())
+                .await;
+
+            if let Ok((status, _)) = &result {
+                span.record("status", status.as_u16());
+            }
+
+            span.record("duration_ms", start.elapsed().as_millis() as u64);
+
+            result
+        }
+
+        #[cfg(not(feature = "tracing"))]
+        self.get_with_retries(class, url).await
+    }
+
+    async fn get_with_retries(
+        &self,
+        class: EndpointClass,
+        url: &str,
+    ) -> Result<(reqwest::StatusCode, Vec<u8>), Error> {
+        let policy = *self.policies.get(class);
+        let mut attempt = 0;
+        let mut rate_limit_waits = 0;
+        let mut rate_limit_waited = Duration::ZERO;
+        let mut account_switches = 0;
+
+        loop {
+            let start = Instant::now();
+            let result = self.get_once(class, url, &policy).await;
+            self.observers
+                .record_attempt(class, &result, start.elapsed());
+
+            if let Ok((reqwest::StatusCode::TOO_MANY_REQUESTS, retry_after, _)) = &result {
+                // Another account of the pool may not be rate limited. Every account is tried
+                // at most once per call, and a zero Retry-After is treated like a plain wait.
+                let may_switch = !retry_after.is_some_and(|wait| wait.is_zero())
+                    && self
+                        .pool
+                        .as_ref()
+                        .is_some_and(|pool| account_switches < pool.len() && pool.has_available());
+
+                if may_switch {
+                    debug!("rate limited, switching accounts");
+
+                    account_switches += 1;
+                    continue;
+                }
+
+                rate_limit_waits += 1;
+
+                let wait = retry_after.unwrap_or_else(|| policy.delay(rate_limit_waits));
+
+                match policy.rate_limit_budget {
+                    Some(budget) if rate_limit_waited + wait <= budget => {
+                        debug!("rate limited, retrying in {:?}", wait);
+
+                        rate_limit_waited += wait;
+                        crate::time::sleep(wait).await;
+
+                        continue;
+                    }
+                    _ => {
+                        return Err(Error::RateLimited {
+                            retry_after: *retry_after,
+                        })
+                    }
+                }
+            }
+
+            let retry = match &result {
+                Ok((status, _, _)) => status.is_server_error(),
+                Err(Error::Reqwest(err)) => is_transient(err),
+                Err(_) => false,
+            };
+
+            if !retry || attempt >= policy.max_retries {
+                return result.map(|(status, _, bytes)| (status, bytes));
+            }
+
+            attempt += 1;
+            debug!("retrying request ({}/{})", attempt, policy.max_retries);
+
+            let delay = policy.delay(attempt);
+
+            if !delay.is_zero() {
+                crate::time::sleep(delay).await;
+            }
+        }
+    }
+
+    async fn get_once(
+        &self,
+        class: EndpointClass,
+        url: &str,
+        policy: &RequestPolicy,
+    ) -> Result<(reqwest::StatusCode, Option<Duration>, Vec<u8>), Error> {
+        let _permit = match &self.limiter {
+            Some(limiter) => Some(
+                limiter
+                    .acquire()
+                    .await
+                    .expect("the request limiter is never closed"),
+            ),
+            None => None,
+        };
+
+        #[cfg(not(feature = "tracing"))]
+        debug!("url = {}", url);
+
+        let pooled = self.pool.as_ref().and_then(|pool| pool.select());
+        let login = pooled
+            .as_ref()
+            .map(|(_, login)| login)
+            .or(self.login.as_ref());
+
+        let cached = self
+            .http_cache
+            .as_ref()
+            .and_then(|http_cache| http_cache.get(url));
+
+        let request = |login: Option<&Arc<(String, String)>>, token: Option<String>| {
+            let mut request = self.client.get(url);
+
+            if let Some(user_agent) = &self.user_agent {
+                request = request.header(reqwest::header::USER_AGENT, user_agent.clone());
+            }
+
+            if let Some(timeout) = policy.timeout {
+                request = request.timeout(timeout);
+            }
+
+            if let Some(login) = login {
+                request = request.basic_auth(&login.0, Some(&login.1));
+            }
+
+            if let Some(token) = token {
+                request = request.bearer_auth(token);
+            }
+
+            if let Some(cached) = &cached {
+                request = cached.add_validators(request);
+            }
+
+            request
+        };
+
+        let send = |request: reqwest::RequestBuilder| async {
+            let mut request = request.build()?;
+            self.middleware.on_request(&mut request);
+
+            let mut res = self.execute(request).await?;
+            self.middleware.on_response(&mut res);
+
+            Ok::<_, Error>(res)
+        };
+
+        let token = match &self.oauth2 {
+            Some(oauth2) => Some(oauth2.token(&self.client).await?),
+            None => None,
+        };
+
+        let mut res = send(request(login, token)).await?;
+
+        // The token may have been revoked before it expired, so get a new one and try again
+        if res.status() == reqwest::StatusCode::UNAUTHORIZED {
+            if let Some(oauth2) = &self.oauth2 {
+                debug!("access token rejected, refreshing");
+
+                let token = oauth2.refresh(&self.client).await?;
+                res = send(request(login, Some(token))).await?;
+            }
+        }
+
+        let rejected = matches!(
+            res.status(),
+            reqwest::StatusCode::UNAUTHORIZED | reqwest::StatusCode::FORBIDDEN
+        );
+        let authenticated = login.is_some() || self.oauth2.is_some();
+
+        if self.anonymous_fallback && rejected && authenticated {
+            warn!(
+                "credentials rejected with {}, sending the request anonymously",
+                res.status()
+            );
+
+            self.anonymous_fallbacks.fetch_add(1, Ordering::Relaxed);
+            self.observers
+                .record_anonymous_fallback(class, res.status());
+
+            res = send(request(None, None)).await?;
+        }
+
+        let status = res.status();
+        let retry_after = retry_after(res.headers());
+
+        if let (Some(pool), Some((index, _))) = (&self.pool, &pooled) {
+            pool.record(*index, status, res.headers(), retry_after);
+        }
+
+        if let Some(http_cache) = &self.http_cache {
+            if status == reqwest::StatusCode::NOT_MODIFIED {
+                if let Some(cached) = &cached {
+                    debug!("not modified, using the cached response");
+
+                    return Ok((reqwest::StatusCode::OK, None, cached.body.to_vec()));
+                }
+            }
+
+            let headers = res.headers().clone();
+            let bytes = res.bytes().await?.to_vec();
+
+            if status.is_success() {
+                http_cache.store(url, &headers, &bytes);
+            }
+
+            return Ok((status, retry_after, bytes));
+        }
+
+        let bytes = res.bytes().await?.to_vec();
+
+        Ok((status, retry_after, bytes))
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn execute(&self, request: reqwest::Request) -> Result<reqwest::Response, Error> {
+        match &self.transport {
+            Some(transport) => transport.0.execute(request).await,
+            None => Ok(self.client.execute(request).await?),
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    async fn execute(&self, request: reqwest::Request) -> Result<reqwest::Response, Error> {
+        Ok(self.client.execute(request).await?)
+    }
+
+    /// Discards the cached OAuth2 access token and obtains a new one. Does nothing if the
+    /// requests are not authenticated using OAuth2.
+    ///
+    pub(crate) async fn force_refresh(&self) -> Result<(), Error> {
+        if let Some(oauth2) = &self.oauth2 {
+            oauth2.refresh(&self.client).await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns whether the request failed for a reason that may go away by itself
+#[cfg(not(target_arch = "wasm32"))]
+fn is_transient(err: &reqwest::Error) -> bool {
+    err.is_timeout() || err.is_connect()
+}
+
+/// Returns whether the request failed for a reason that may go away by itself. Browsers do not
+/// tell connection errors apart from other errors of the fetch API.
+///
+#[cfg(target_arch = "wasm32")]
+fn is_transient(err: &reqwest::Error) -> bool {
+    err.is_timeout() || err.is_request()
+}
+
+/// Returns a client builder with the default User-Agent, which all clients created by this
+/// crate start from
+///
+pub(crate) fn client_builder() -> reqwest::ClientBuilder {
+    reqwest::Client::builder().user_agent(DEFAULT_USER_AGENT)
+}
+
+/// Reads the time to wait from the Retry-After header, which is either a number of seconds or
+/// an HTTP date, or from the X-Rate-Limit-Retry-After-Seconds header OpenSky sends instead
+///
+fn retry_after(headers: &reqwest::header::HeaderMap) -> Option<Duration> {
+    if let Some(value) = headers
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+    {
+        let value = value.trim();
+
+        if let Ok(seconds) = value.parse::<u64>() {
+            return Some(Duration::from_secs(seconds));
+        }
+
+        if let Ok(date) = chrono::DateTime::parse_from_rfc2822(value) {
+            let seconds = (date.timestamp() - chrono::Utc::now().timestamp()).max(0);
+
+            return Some(Duration::from_secs(seconds as u64));
+        }
+    }
+
+    headers
+        .get("X-Rate-Limit-Retry-After-Seconds")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .map(Duration::from_secs)
+}
+
+/// Quotes a string for POSIX shells
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
@@ -0,0 +1,207 @@
+//! Rotation among several OpenSky accounts.
+//!
+//! Every OpenSky account has its own daily credit budget. For research workloads which need
+//! more than one account's budget, a CredentialPool spreads the requests of one OpenSkyApi
+//! instance over several accounts, and keeps track of how much each account has used.
+
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use crate::time::Instant;
+
+/// How long an account is skipped after it was rate limited, unless the server said when to
+/// try again
+///
+pub const DEFAULT_RATE_LIMIT_PAUSE: Duration = Duration::from_secs(60);
+
+/// The shortest time an account is skipped after it was rate limited, even if the server asked
+/// to try again immediately
+///
+pub const MIN_RATE_LIMIT_PAUSE: Duration = Duration::from_secs(1);
+
+/// When a CredentialPool switches to the next account
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Rotation {
+    /// Every request uses the next account
+    #[default]
+    PerRequest,
+    /// Requests use the same account until it is rate limited or out of credits
+    OnRateLimit,
+}
+
+#[derive(Debug, Default)]
+struct AccountState {
+    requests: u64,
+    remaining_credits: Option<u64>,
+    rate_limited_until: Option<Instant>,
+}
+
+impl AccountState {
+    fn is_available(&self, now: Instant) -> bool {
+        self.rate_limited_until.is_none_or(|until| until <= now)
+            && self.remaining_credits != Some(0)
+    }
+}
+
+#[derive(Debug)]
+struct Account {
+    login: Arc<(String, String)>,
+    state: Mutex<AccountState>,
+}
+
+/// The usage of one account of a CredentialPool
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountUsage {
+    pub username: String,
+    /// The number of requests sent with the account
+    pub requests: u64,
+    /// The credits left according to the last response, if the server reported them
+    pub remaining_credits: Option<u64>,
+    /// The time until the account is used again after it was rate limited
+    pub rate_limited_for: Option<Duration>,
+}
+
+/// A set of OpenSky accounts which requests are spread over
+#[derive(Debug, Default)]
+pub struct CredentialPool {
+    accounts: Vec<Account>,
+    rotation: Rotation,
+    next: AtomicUsize,
+}
+
+impl CredentialPool {
+    /// Creates an empty CredentialPool, which rotates accounts per request
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_account(mut self, username: String, password: String) -> Self {
+        self.accounts.push(Account {
+            login: Arc::new((username, password)),
+            state: Mutex::default(),
+        });
+
+        self
+    }
+
+    pub fn with_rotation(mut self, rotation: Rotation) -> Self {
+        self.rotation = rotation;
+
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.accounts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty()
+    }
+
+    /// Returns the usage of every account, in the order they were added
+    pub fn usage(&self) -> Vec<AccountUsage> {
+        let now = Instant::now();
+
+        self.accounts
+            .iter()
+            .map(|account| {
+                let state = account.state.lock().unwrap();
+
+                AccountUsage {
+                    username: account.login.0.clone(),
+                    requests: state.requests,
+                    remaining_credits: state.remaining_credits,
+                    rate_limited_for: state
+                        .rate_limited_until
+                        .filter(|&until| until > now)
+                        .map(|until| until - now),
+                }
+            })
+            .collect()
+    }
+
+    /// Returns whether any account can be used right now
+    pub fn has_available(&self) -> bool {
+        let now = Instant::now();
+
+        self.accounts
+            .iter()
+            .any(|account| account.state.lock().unwrap().is_available(now))
+    }
+
+    /// Picks the account for the next request. If no account is available, the one which is
+    /// rate limited for the shortest time is used. Returns None if the pool is empty.
+    ///
+    pub(crate) fn select(&self) -> Option<(usize, Arc<(String, String)>)> {
+        let count = self.accounts.len();
+
+        if count == 0 {
+            return None;
+        }
+
+        let now = Instant::now();
+        let start = match self.rotation {
+            Rotation::PerRequest => self.next.fetch_add(1, Ordering::Relaxed),
+            Rotation::OnRateLimit => self.next.load(Ordering::Relaxed),
+        } % count;
+
+        let index = (0..count)
+            .map(|offset| (start + offset) % count)
+            .find(|&index| self.accounts[index].state.lock().unwrap().is_available(now))
+            .unwrap_or_else(|| {
+                (0..count)
+                    .min_by_key(|&index| {
+                        self.accounts[index]
+                            .state
+                            .lock()
+                            .unwrap()
+                            .rate_limited_until
+                    })
+                    .unwrap_or(start)
+            });
+
+        if self.rotation == Rotation::OnRateLimit {
+            self.next.store(index, Ordering::Relaxed);
+        }
+
+        Some((index, self.accounts[index].login.clone()))
+    }
+
+    /// Returns the first account without counting it as used
+    pub(crate) fn select_first(&self) -> Option<Arc<(String, String)>> {
+        self.accounts.first().map(|account| account.login.clone())
+    }
+
+    /// Updates the usage of an account after a response
+    pub(crate) fn record(
+        &self,
+        index: usize,
+        status: reqwest::StatusCode,
+        headers: &reqwest::header::HeaderMap,
+        retry_after: Option<Duration>,
+    ) {
+        let mut state = self.accounts[index].state.lock().unwrap();
+        state.requests += 1;
+
+        if let Some(remaining) = headers
+            .get("X-Rate-Limit-Remaining")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse().ok())
+        {
+            state.remaining_credits = Some(remaining);
+        }
+
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            let pause = retry_after
+                .unwrap_or(DEFAULT_RATE_LIMIT_PAUSE)
+                .max(MIN_RATE_LIMIT_PAUSE);
+
+            state.rate_limited_until = Some(Instant::now() + pause);
+        }
+    }
+}
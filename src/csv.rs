@@ -0,0 +1,315 @@
+//! CSV export and import of state vectors, and export of flights, for post-processing in tools
+//! like pandas or R.
+//!
+//! Every row holds one state vector, preceded by the time of the snapshot it belongs to. The
+//! columns are named after the fields of StateVector and always appear in the order of
+//! COLUMNS. Unknown values are empty, and the sensor serials are joined with semicolons.
+//!
+//! Flights are written one per row, with the columns of FLIGHT_COLUMNS.
+
+use std::{
+    io::{Read, Write},
+    str::FromStr,
+};
+
+use crate::{
+    errors::Error,
+    flights::Flight,
+    states::{StateVector, States},
+};
+
+/// The header row written by StatesCsvWriter
+pub const COLUMNS: [&str; 19] = [
+    "time",
+    "icao24",
+    "callsign",
+    "origin_country",
+    "time_position",
+    "last_contact",
+    "longitude",
+    "latitude",
+    "baro_altitude",
+    "on_ground",
+    "velocity",
+    "true_track",
+    "vertical_rate",
+    "sensors",
+    "geo_altitude",
+    "squawk",
+    "spi",
+    "position_source",
+    "category",
+];
+
+/// The header row written by FlightsCsvWriter, named after the fields of Flight
+pub const FLIGHT_COLUMNS: [&str; 12] = [
+    "icao24",
+    "first_seen",
+    "est_departure_airport",
+    "last_seen",
+    "est_arrival_airport",
+    "callsign",
+    "est_departure_airport_horiz_distance",
+    "est_departure_airport_vert_distance",
+    "est_arrival_airport_horiz_distance",
+    "est_arrival_airport_vert_distance",
+    "departure_airport_candidates_count",
+    "arrival_airport_candidates_count",
+];
+
+/// Writes one or more snapshots into a single CSV document. The header row is written
+/// together with the first snapshot.
+///
+#[derive(Debug)]
+pub struct StatesCsvWriter<W: Write> {
+    csv: ::csv::Writer<W>,
+    header_written: bool,
+}
+
+impl<W: Write> StatesCsvWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            csv: ::csv::Writer::from_writer(writer),
+            header_written: false,
+        }
+    }
+
+    /// Appends the rows of a snapshot
+    pub fn write(&mut self, states: &States) -> Result<(), Error> {
+        if !self.header_written {
+            self.csv.write_record(COLUMNS)?;
+            self.header_written = true;
+        }
+
+        for state in &states.states {
+            self.csv.write_record(record(states.time, state))?;
+        }
+
+        Ok(())
+    }
+
+    /// Flushes and returns the underlying writer
+    pub fn into_inner(self) -> Result<W, Error> {
+        self.csv
+            .into_inner()
+            .map_err(|error| Error::Io(error.into_error()))
+    }
+}
+
+/// Writes flights into a single CSV document, e.g. the results of several requests one after
+/// the other. The header row is written together with the first flights.
+///
+#[derive(Debug)]
+pub struct FlightsCsvWriter<W: Write> {
+    csv: ::csv::Writer<W>,
+    header_written: bool,
+}
+
+impl<W: Write> FlightsCsvWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            csv: ::csv::Writer::from_writer(writer),
+            header_written: false,
+        }
+    }
+
+    /// Appends one row per flight
+    pub fn write(&mut self, flights: &[Flight]) -> Result<(), Error> {
+        if !self.header_written {
+            self.csv.write_record(FLIGHT_COLUMNS)?;
+            self.header_written = true;
+        }
+
+        for flight in flights {
+            self.csv.write_record(flight_record(flight))?;
+        }
+
+        Ok(())
+    }
+
+    /// Flushes and returns the underlying writer
+    pub fn into_inner(self) -> Result<W, Error> {
+        self.csv
+            .into_inner()
+            .map_err(|error| Error::Io(error.into_error()))
+    }
+}
+
+/// Writes the flights as a CSV document, including the header row
+pub fn write_flights<W: Write>(writer: W, flights: &[Flight]) -> Result<(), Error> {
+    let mut csv = FlightsCsvWriter::new(writer);
+    csv.write(flights)?;
+    csv.into_inner()?;
+
+    Ok(())
+}
+
+fn flight_record(flight: &Flight) -> [String; 12] {
+    [
+        flight.icao24.clone(),
+        flight.first_seen.to_string(),
+        optional(flight.est_departure_airport),
+        flight.last_seen.to_string(),
+        optional(flight.est_arrival_airport),
+        optional(flight.callsign.as_ref()),
+        optional(flight.est_departure_airport_horiz_distance),
+        optional(flight.est_departure_airport_vert_distance),
+        optional(flight.est_arrival_airport_horiz_distance),
+        optional(flight.est_arrival_airport_vert_distance),
+        flight.departure_airport_candidates_count.to_string(),
+        flight.arrival_airport_candidates_count.to_string(),
+    ]
+}
+
+fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_default()
+}
+
+fn record(time: u64, state: &StateVector) -> [String; 19] {
+    let sensors = state.sensors.as_ref().map(|sensors| {
+        sensors
+            .iter()
+            .map(u64::to_string)
+            .collect::<Vec<_>>()
+            .join(";")
+    });
+
+    [
+        time.to_string(),
+        state.icao24.clone(),
+        optional(state.callsign.as_ref()),
+        state.origin_country.clone(),
+        optional(state.time_position),
+        state.last_contact.to_string(),
+        optional(state.longitude),
+        optional(state.latitude),
+        optional(state.baro_altitude),
+        state.on_ground.to_string(),
+        optional(state.velocity),
+        optional(state.true_track),
+        optional(state.vertical_rate),
+        optional(sensors),
+        optional(state.geo_altitude),
+        optional(state.squawk),
+        state.spi.to_string(),
+        state.position_source.to_string(),
+        optional(state.category),
+    ]
+}
+
+impl States {
+    /// Writes this snapshot as a CSV document, including the header row
+    pub fn write_csv<W: Write>(&self, writer: W) -> Result<(), Error> {
+        let mut csv = StatesCsvWriter::new(writer);
+        csv.write(self)?;
+        csv.into_inner()?;
+
+        Ok(())
+    }
+
+    /// Reads a CSV document written by write_csv() or StatesCsvWriter. Consecutive rows with
+    /// the same time are collected into one snapshot. The columns may appear in any order, and
+    /// only the category column may be missing.
+    ///
+    pub fn read_csv<R: Read>(reader: R) -> Result<Vec<States>, Error> {
+        let mut csv = ::csv::Reader::from_reader(reader);
+        let headers = csv.headers()?.clone();
+
+        let mut columns = [None; 19];
+        for (index, name) in COLUMNS.iter().enumerate() {
+            columns[index] = headers.iter().position(|header| header.trim() == *name);
+        }
+
+        let mut snapshots: Vec<States> = Vec::new();
+
+        for record in csv.records() {
+            let record = record?;
+            let row = Row {
+                record: &record,
+                columns: &columns,
+            };
+
+            let time = row.required(0)?;
+            let state = StateVector {
+                icao24: row.required(1)?,
+                callsign: row.optional(2)?,
+                origin_country: row.required(3)?,
+                time_position: row.optional(4)?,
+                last_contact: row.required(5)?,
+                longitude: row.optional(6)?,
+                latitude: row.optional(7)?,
+                baro_altitude: row.optional(8)?,
+                on_ground: row.required(9)?,
+                velocity: row.optional(10)?,
+                true_track: row.optional(11)?,
+                vertical_rate: row.optional(12)?,
+                sensors: row.sensors(13)?,
+                geo_altitude: row.optional(14)?,
+                squawk: row.optional(15)?,
+                spi: row.required(16)?,
+                position_source: row.required(17)?,
+                category: if columns[18].is_some() {
+                    row.optional(18)?
+                } else {
+                    None
+                },
+            };
+
+            match snapshots.last_mut() {
+                Some(snapshot) if snapshot.time == time => snapshot.states.push(state),
+                _ => snapshots.push(States {
+                    time,
+                    states: vec![state],
+                }),
+            }
+        }
+
+        Ok(snapshots)
+    }
+}
+
+/// A single record together with the positions of the known columns
+struct Row<'a> {
+    record: &'a ::csv::StringRecord,
+    columns: &'a [Option<usize>; 19],
+}
+
+impl Row<'_> {
+    fn field(&self, column: usize) -> Result<Option<&str>, Error> {
+        let index = self.columns[column]
+            .ok_or_else(|| Error::InvalidCsv(format!("missing {} column", COLUMNS[column])))?;
+
+        Ok(self.record.get(index).filter(|value| !value.is_empty()))
+    }
+
+    fn parse<T: FromStr>(column: usize, value: &str) -> Result<T, Error> {
+        value
+            .parse()
+            .map_err(|_| Error::InvalidCsv(format!("invalid {} {value:?}", COLUMNS[column])))
+    }
+
+    fn required<T: FromStr>(&self, column: usize) -> Result<T, Error> {
+        let value = self
+            .field(column)?
+            .ok_or_else(|| Error::InvalidCsv(format!("missing {}", COLUMNS[column])))?;
+
+        Self::parse(column, value)
+    }
+
+    fn optional<T: FromStr>(&self, column: usize) -> Result<Option<T>, Error> {
+        self.field(column)?
+            .map(|value| Self::parse(column, value))
+            .transpose()
+    }
+
+    fn sensors(&self, column: usize) -> Result<Option<Vec<u64>>, Error> {
+        self.field(column)?
+            .map(|value| {
+                value
+                    .split(';')
+                    .map(|serial| Self::parse(column, serial))
+                    .collect()
+            })
+            .transpose()
+    }
+}
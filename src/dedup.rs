@@ -0,0 +1,52 @@
+//! Removal of state vectors which did not change since the previous poll.
+//!
+//! OpenSky only updates most positions every 5-10 seconds, so consecutive snapshots mostly
+//! contain the same positions again. A PositionDedup remembers the position time of every
+//! aircraft in the previous snapshot and lets only new positions through.
+
+use std::collections::HashMap;
+
+use crate::states::States;
+
+/// Keeps the position time (time_position) of every aircraft of the previous snapshot. Only
+/// aircraft of the latest snapshot are remembered, so memory use does not grow while polling.
+///
+#[derive(Debug, Clone, Default)]
+pub struct PositionDedup {
+    seen: HashMap<String, Option<u64>>,
+}
+
+impl PositionDedup {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Removes every state vector whose icao24 and time_position are the same as in the
+    /// previous snapshot passed to this function
+    ///
+    pub fn retain_updated(&mut self, states: &mut States) {
+        let previous = std::mem::take(&mut self.seen);
+
+        self.seen = states
+            .states
+            .iter()
+            .map(|state| (state.icao24.clone(), state.time_position))
+            .collect();
+
+        states
+            .states
+            .retain(|state| previous.get(&state.icao24) != Some(&state.time_position));
+    }
+
+    /// Like retain_updated(), but takes and returns the snapshot by value
+    pub fn updated(&mut self, mut states: States) -> States {
+        self.retain_updated(&mut states);
+
+        states
+    }
+
+    /// Forgets all positions, so that the next snapshot is let through completely
+    pub fn clear(&mut self) {
+        self.seen.clear();
+    }
+}
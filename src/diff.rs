@@ -0,0 +1,72 @@
+//! Changes between consecutive snapshots of state vectors.
+
+use std::collections::HashMap;
+
+use crate::states::{StateVector, States};
+
+/// A state vector which is in both snapshots and has been updated since the previous one
+#[derive(Debug, Clone, Copy)]
+pub struct StateUpdate<'a> {
+    pub previous: &'a StateVector,
+    pub current: &'a StateVector,
+}
+
+/// The differences between two snapshots, with state vectors matched by their ICAO24 address.
+/// Added and updated state vectors are in the order of the current snapshot, removed ones in
+/// the order of the previous snapshot.
+///
+#[derive(Debug, Clone, Default)]
+pub struct StatesDiff<'a> {
+    /// Aircraft which are only in the current snapshot
+    pub added: Vec<&'a StateVector>,
+    /// Aircraft which are only in the previous snapshot
+    pub removed: Vec<&'a StateVector>,
+    /// Aircraft which are in both snapshots, and were heard from again in between
+    pub updated: Vec<StateUpdate<'a>>,
+}
+
+impl StatesDiff<'_> {
+    /// Returns true if nothing changed between the snapshots
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.updated.is_empty()
+    }
+}
+
+impl States {
+    /// Compares this snapshot with a previous one. An aircraft in both snapshots counts as
+    /// updated if its last contact changed, so aircraft which were not heard from in between
+    /// are neither added, removed, nor updated.
+    ///
+    pub fn diff<'a>(&'a self, previous: &'a States) -> StatesDiff<'a> {
+        let previous_by_icao24: HashMap<&str, &StateVector> = previous
+            .states
+            .iter()
+            .map(|state| (state.icao24.as_str(), state))
+            .collect();
+        let current_by_icao24: HashMap<&str, &StateVector> = self
+            .states
+            .iter()
+            .map(|state| (state.icao24.as_str(), state))
+            .collect();
+
+        let mut diff = StatesDiff::default();
+
+        for current in &self.states {
+            match previous_by_icao24.get(current.icao24.as_str()) {
+                None => diff.added.push(current),
+                Some(previous) if previous.last_contact != current.last_contact => {
+                    diff.updated.push(StateUpdate { previous, current })
+                }
+                Some(_) => {}
+            }
+        }
+
+        diff.removed = previous
+            .states
+            .iter()
+            .filter(|state| !current_by_icao24.contains_key(state.icao24.as_str()))
+            .collect();
+
+        diff
+    }
+}
@@ -0,0 +1,172 @@
+//! Downsampling of recorded per-aircraft state vector series, to shrink long recordings before
+//! analysis or export.
+//!
+//! A Downsampler always keeps the first and last samples of a series, and the samples on both
+//! sides of every takeoff and landing (changes of on_ground). Samples where the aircraft turned
+//! or changed altitude by more than the configured thresholds since the last kept sample are
+//! kept as well, so the shape of the trajectory survives the downsampling.
+
+use crate::{bounding_box::great_circle_km, states::StateVector};
+
+/// Removes samples from a series of state vectors belonging to a single aircraft
+#[derive(Debug, Clone)]
+pub struct Downsampler {
+    interval: Option<u64>,
+    distance: Option<f64>,
+    max_interval: Option<u64>,
+    turn_threshold: Option<f32>,
+    altitude_threshold: Option<f32>,
+}
+
+impl Downsampler {
+    /// Creates a Downsampler which keeps at most one sample per interval, given in seconds,
+    /// in addition to the takeoff, landing, and turn points.
+    ///
+    pub fn every(interval: u64) -> Self {
+        Self {
+            interval: Some(interval),
+            distance: None,
+            max_interval: None,
+            turn_threshold: Some(15.0),
+            altitude_threshold: None,
+        }
+    }
+
+    /// Creates a Downsampler which adapts to the movement of the aircraft: a sample is kept
+    /// once the aircraft moved at least the given distance in meters, turned, or changed its
+    /// altitude by 300 meters since the last kept sample. Aircraft that are parked produce
+    /// only a few samples this way, while turning aircraft keep their shape.
+    ///
+    pub fn adaptive(distance: f64) -> Self {
+        Self {
+            interval: None,
+            distance: Some(distance),
+            max_interval: None,
+            turn_threshold: Some(15.0),
+            altitude_threshold: Some(300.0),
+        }
+    }
+
+    /// Sets the change of true track in degrees above which a sample is considered a turn
+    /// point and always kept. None disables turn detection.
+    ///
+    pub fn with_turn_threshold(mut self, degrees: Option<f32>) -> Self {
+        self.turn_threshold = degrees;
+
+        self
+    }
+
+    /// Sets the change of altitude in meters above which a sample is always kept. None
+    /// disables the altitude check.
+    ///
+    pub fn with_altitude_threshold(mut self, meters: Option<f32>) -> Self {
+        self.altitude_threshold = meters;
+
+        self
+    }
+
+    /// Forces a sample to be kept at least this often (in seconds), even if the aircraft did
+    /// not move
+    ///
+    pub fn with_max_interval(mut self, interval: u64) -> Self {
+        self.max_interval = Some(interval);
+
+        self
+    }
+
+    /// Returns the downsampled series. The series must be ordered by time and only contain
+    /// state vectors of a single aircraft.
+    ///
+    pub fn apply(&self, series: &[StateVector]) -> Vec<StateVector> {
+        self.keep(series)
+            .into_iter()
+            .map(|index| series[index].clone())
+            .collect()
+    }
+
+    /// Returns the indices of the samples that are kept
+    pub fn keep(&self, series: &[StateVector]) -> Vec<usize> {
+        let mut kept: Vec<usize> = Vec::new();
+
+        for (index, state) in series.iter().enumerate() {
+            let Some(&last_index) = kept.last() else {
+                kept.push(index);
+                continue;
+            };
+
+            let last = &series[last_index];
+            let previous = &series[index - 1];
+            let next = series.get(index + 1);
+
+            let is_transition = state.on_ground != previous.on_ground
+                || next.is_some_and(|next| next.on_ground != state.on_ground);
+
+            if next.is_none() || is_transition || self.is_significant(last, state) {
+                kept.push(index);
+            }
+        }
+
+        kept
+    }
+
+    fn is_significant(&self, last: &StateVector, state: &StateVector) -> bool {
+        let elapsed = sample_time(state).saturating_sub(sample_time(last));
+
+        if let Some(interval) = self.interval {
+            if elapsed >= interval {
+                return true;
+            }
+        }
+
+        if let Some(max_interval) = self.max_interval {
+            if elapsed >= max_interval {
+                return true;
+            }
+        }
+
+        if let Some(threshold) = self.turn_threshold {
+            if let (Some(a), Some(b)) = (last.true_track, state.true_track) {
+                let change = (b - a).rem_euclid(360.0);
+
+                if change.min(360.0 - change) >= threshold {
+                    return true;
+                }
+            }
+        }
+
+        if let Some(threshold) = self.altitude_threshold {
+            if let (Some(a), Some(b)) = (altitude(last), altitude(state)) {
+                if (b - a).abs() >= threshold {
+                    return true;
+                }
+            }
+        }
+
+        if let Some(threshold) = self.distance {
+            if let Some(distance) = distance(last, state) {
+                if distance >= threshold {
+                    return true;
+                }
+            }
+        }
+
+        false
+    }
+}
+
+/// The time of the position of a sample, or its last contact if it has no position
+fn sample_time(state: &StateVector) -> u64 {
+    state.time_position.unwrap_or(state.last_contact)
+}
+
+fn altitude(state: &StateVector) -> Option<f32> {
+    state.baro_altitude.or(state.geo_altitude)
+}
+
+/// Great-circle distance between the positions of two samples in meters
+fn distance(a: &StateVector, b: &StateVector) -> Option<f64> {
+    let (lat1, lon1) = (a.latitude?, a.longitude?);
+    let (lat2, lon2) = (b.latitude?, b.longitude?);
+
+    Some(great_circle_km(lat1, lon1, lat2, lon2) * 1000.0)
+}
@@ -0,0 +1,46 @@
+/// The paths of the API endpoints used by the requests. These can be overridden to adopt new
+/// versions of the OpenSky REST API, or to point requests at a staging environment, without
+/// waiting for a new release of this crate.
+///
+/// All paths are relative to the host and must start with a '/'.
+///
+#[derive(Debug, Clone)]
+pub struct Endpoints {
+    /// The path used to get all state vectors, "/api/states/all" by default
+    pub states_all: String,
+    /// The path used to get the state vectors of your own sensors, "/api/states/own" by default
+    pub states_own: String,
+    /// The path used to get flights in a time interval, "/api/flights/all" by default
+    pub flights_all: String,
+    /// The path used to get the flights of a single aircraft, "/api/flights/aircraft" by
+    /// default
+    ///
+    pub flights_aircraft: String,
+    /// The path used to get flights arriving at an airport, "/api/flights/arrival" by default
+    pub flights_arrival: String,
+    /// The path used to get flights departing from an airport, "/api/flights/departure" by
+    /// default
+    ///
+    pub flights_departure: String,
+    /// The path used to get the track of an aircraft, "/api/tracks/all" by default
+    pub tracks: String,
+    /// The path prefix used to look up aircraft metadata, which is followed by the ICAO24
+    /// address. "/api/metadata/aircraft/icao/" by default
+    ///
+    pub metadata_aircraft: String,
+}
+
+impl Default for Endpoints {
+    fn default() -> Self {
+        Self {
+            states_all: String::from("/api/states/all"),
+            states_own: String::from("/api/states/own"),
+            flights_all: String::from("/api/flights/all"),
+            flights_aircraft: String::from("/api/flights/aircraft"),
+            flights_arrival: String::from("/api/flights/arrival"),
+            flights_departure: String::from("/api/flights/departure"),
+            tracks: String::from("/api/tracks/all"),
+            metadata_aircraft: String::from("/api/metadata/aircraft/icao/"),
+        }
+    }
+}
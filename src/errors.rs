@@ -1,5 +1,8 @@
-/// This error type wraps other crate's errors
+/// This error type wraps other crate's errors. Some variants only exist if the feature they
+/// belong to is enabled, so the enum is non-exhaustive.
+///
 #[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
 pub enum Error {
     #[error("Server request failed: {0}")]
     Reqwest(#[from] reqwest::Error),
@@ -7,9 +10,63 @@ pub enum Error {
     #[error("Server returned HTTP error code: {0}")]
     Http(reqwest::StatusCode),
 
+    /// The server answered with HTTP 429. retry_after is the time the server asked to wait
+    /// before the next request, if it said so.
+    ///
+    #[error("Rate limited by the server, retry after {retry_after:?}")]
+    RateLimited {
+        retry_after: Option<std::time::Duration>,
+    },
+
     #[error("Unable to parse response as string: {0}")]
     InvalidString(#[from] std::string::FromUtf8Error),
 
     #[error("Unable to parse response as Json: {0}")]
     InvalidJson(#[from] serde_json::error::Error),
+
+    #[error("Invalid value of the {0} header")]
+    InvalidHeader(&'static str),
+
+    #[error("Invalid request: {0}")]
+    InvalidRequest(String),
+
+    #[error("Invalid squawk: {0}")]
+    InvalidSquawk(String),
+
+    #[error("Invalid ICAO airport code: {0}")]
+    InvalidAirport(String),
+
+    #[error("Invalid GeoJSON: {0}")]
+    InvalidGeoJson(&'static str),
+
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[cfg(any(feature = "aircraft-db", feature = "csv"))]
+    #[error("Unable to parse CSV: {0}")]
+    Csv(#[from] csv::Error),
+
+    #[cfg(feature = "csv")]
+    #[error("Invalid CSV: {0}")]
+    InvalidCsv(String),
+
+    #[cfg(feature = "aircraft-db")]
+    #[error("Invalid aircraft database: {0}")]
+    InvalidDatabase(&'static str),
+
+    #[cfg(feature = "parquet")]
+    #[error("Parquet error: {0}")]
+    Parquet(#[from] parquet::errors::ParquetError),
+
+    #[cfg(feature = "sqlite")]
+    #[error("SQLite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+
+    #[cfg(feature = "timescale")]
+    #[error("Postgres error: {0}")]
+    Postgres(#[from] tokio_postgres::Error),
+
+    #[cfg(feature = "timescale")]
+    #[error("The sink has been closed")]
+    SinkClosed,
 }
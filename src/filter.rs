@@ -0,0 +1,230 @@
+//! Client-side filtering of state vectors and flights.
+//!
+//! The REST API can only filter state vectors by bounding box and ICAO24 address, and flights
+//! not at all beyond the endpoint used. StatesFilter and FlightsFilter combine further
+//! conditions, which are checked locally after the response has been received.
+
+use crate::{
+    airport::AirportIcao,
+    flights::Flight,
+    states::{StateVector, States},
+};
+
+/// A set of conditions a state vector has to meet. Conditions which are not set are not
+/// checked, so an empty filter matches every state vector. State vectors which lack a value a
+/// condition needs (e.g. an altitude band for an aircraft without altitude) do not match.
+///
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StatesFilter {
+    altitude: Option<(f32, f32)>,
+    velocity: Option<(f32, f32)>,
+    on_ground: Option<bool>,
+    countries: Vec<String>,
+    categories: Vec<u32>,
+    callsign_prefix: Option<String>,
+    military: Option<bool>,
+}
+
+impl StatesFilter {
+    /// Creates an empty StatesFilter, which matches every state vector
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requires a barometric altitude between min and max meters (inclusive). The geometric
+    /// altitude is used for aircraft without a barometric altitude.
+    ///
+    pub fn with_altitude(mut self, min: f32, max: f32) -> Self {
+        self.altitude = Some((min, max));
+
+        self
+    }
+
+    /// Requires a ground speed between min and max meters per second (inclusive)
+    pub fn with_velocity(mut self, min: f32, max: f32) -> Self {
+        self.velocity = Some((min, max));
+
+        self
+    }
+
+    /// Requires the aircraft to be on the ground, or airborne
+    pub fn on_ground(mut self, on_ground: bool) -> Self {
+        self.on_ground = Some(on_ground);
+
+        self
+    }
+
+    /// Requires the given origin country, e.g. "Germany". Calling this function multiple times
+    /// allows any of the countries.
+    ///
+    pub fn with_country(mut self, country: &str) -> Self {
+        self.countries.push(country.to_string());
+
+        self
+    }
+
+    /// Requires the given aircraft category, which is only known for extended state vectors.
+    /// Calling this function multiple times allows any of the categories.
+    ///
+    pub fn with_category(mut self, category: u32) -> Self {
+        self.categories.push(category);
+
+        self
+    }
+
+    /// Requires a callsign starting with the given prefix, e.g. an airline's ICAO code like
+    /// "DLH". The padding OpenSky adds to callsigns is ignored, and the comparison is
+    /// case-insensitive.
+    ///
+    pub fn with_callsign_prefix(mut self, prefix: &str) -> Self {
+        self.callsign_prefix = Some(prefix.trim().to_ascii_uppercase());
+
+        self
+    }
+
+    /// Requires the ICAO24 address to be, or not to be, in a block commonly reserved for
+    /// military aircraft. See StateVector::is_likely_military().
+    ///
+    pub fn military(mut self, military: bool) -> Self {
+        self.military = Some(military);
+
+        self
+    }
+
+    /// Returns true if the state vector meets all conditions
+    pub fn matches(&self, state: &StateVector) -> bool {
+        let within = |value: Option<f32>, band: Option<(f32, f32)>| match band {
+            Some((min, max)) => value.is_some_and(|value| (min..=max).contains(&value)),
+            None => true,
+        };
+
+        within(state.baro_altitude.or(state.geo_altitude), self.altitude)
+            && within(state.velocity, self.velocity)
+            && self
+                .on_ground
+                .is_none_or(|on_ground| state.on_ground == on_ground)
+            && self
+                .military
+                .is_none_or(|military| state.is_likely_military() == military)
+            && (self.countries.is_empty() || self.countries.contains(&state.origin_country))
+            && (self.categories.is_empty()
+                || state
+                    .category
+                    .is_some_and(|category| self.categories.contains(&category)))
+            && self.callsign_prefix.as_ref().is_none_or(|prefix| {
+                state.callsign.as_deref().is_some_and(|callsign| {
+                    callsign.trim().to_ascii_uppercase().starts_with(prefix)
+                })
+            })
+    }
+}
+
+impl States {
+    /// Returns a copy of this snapshot with only the state vectors matching the filter
+    pub fn filter(&self, filter: &StatesFilter) -> States {
+        States {
+            time: self.time,
+            states: self
+                .states
+                .iter()
+                .filter(|state| filter.matches(state))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// Removes every state vector which does not match the filter
+    pub fn retain_matching(&mut self, filter: &StatesFilter) {
+        self.states.retain(|state| filter.matches(state));
+    }
+}
+
+/// A set of conditions a flight has to meet. Like with StatesFilter, conditions which are not
+/// set are not checked, and flights which lack a value a condition needs do not match.
+///
+/// Callsigns are compared without the padding OpenSky adds to them, and case-insensitively.
+///
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FlightsFilter {
+    callsign_prefixes: Vec<String>,
+    callsigns: Vec<String>,
+    departures: Vec<AirportIcao>,
+    arrivals: Vec<AirportIcao>,
+}
+
+impl FlightsFilter {
+    /// Creates an empty FlightsFilter, which matches every flight
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requires a callsign starting with the given prefix, e.g. an airline's ICAO code like
+    /// "DLH". Calling this function multiple times allows any of the prefixes.
+    ///
+    pub fn with_callsign_prefix(mut self, prefix: &str) -> Self {
+        self.callsign_prefixes
+            .push(prefix.trim().to_ascii_uppercase());
+
+        self
+    }
+
+    /// Requires exactly the given callsign, e.g. "DLH9LF". Calling this function multiple
+    /// times allows any of the callsigns.
+    ///
+    pub fn with_callsign(mut self, callsign: &str) -> Self {
+        self.callsigns.push(callsign.trim().to_ascii_uppercase());
+
+        self
+    }
+
+    /// Requires the given estimated departure airport. Calling this function multiple times
+    /// allows any of the airports.
+    ///
+    pub fn departed_from(mut self, airport: AirportIcao) -> Self {
+        self.departures.push(airport);
+
+        self
+    }
+
+    /// Requires the given estimated arrival airport. Calling this function multiple times
+    /// allows any of the airports.
+    ///
+    pub fn arrived_at(mut self, airport: AirportIcao) -> Self {
+        self.arrivals.push(airport);
+
+        self
+    }
+
+    /// Returns true if the flight meets all conditions
+    pub fn matches(&self, flight: &Flight) -> bool {
+        let callsign = flight
+            .callsign
+            .as_deref()
+            .map(|callsign| callsign.trim().to_ascii_uppercase());
+        let any_of = |airports: &[AirportIcao], airport: Option<AirportIcao>| {
+            airports.is_empty() || airport.is_some_and(|airport| airports.contains(&airport))
+        };
+
+        (self.callsign_prefixes.is_empty()
+            || callsign.as_ref().is_some_and(|callsign| {
+                self.callsign_prefixes
+                    .iter()
+                    .any(|prefix| callsign.starts_with(prefix))
+            }))
+            && (self.callsigns.is_empty()
+                || callsign
+                    .as_ref()
+                    .is_some_and(|callsign| self.callsigns.contains(callsign)))
+            && any_of(&self.departures, flight.est_departure_airport)
+            && any_of(&self.arrivals, flight.est_arrival_airport)
+    }
+
+    /// Returns copies of the flights matching the filter, in their original order
+    pub fn filter(&self, flights: &[Flight]) -> Vec<Flight> {
+        flights
+            .iter()
+            .filter(|flight| self.matches(flight))
+            .cloned()
+            .collect()
+    }
+}
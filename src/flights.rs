@@ -1,99 +1,240 @@
-use std::sync::Arc;
+use crate::{
+    airport::AirportIcao, context::RequestContext, errors::Error, policy::EndpointClass,
+    response::WithRaw, time,
+};
+use std::{
+    io::Write,
+    ops::{Range, RangeInclusive},
+};
 
-use crate::errors::Error;
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use futures_util::{future, stream, Stream, StreamExt, TryStreamExt};
 use log::debug;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 
-#[derive(Debug, Deserialize)]
+/// The longest interval in seconds OpenSky accepts for requests of all flights
+pub const MAX_FLIGHTS_INTERVAL: u64 = 2 * 60 * 60;
+
+/// The longest interval in seconds OpenSky accepts for requests of the flights of a single
+/// aircraft
+///
+pub const MAX_AIRCRAFT_INTERVAL: u64 = 30 * 24 * 60 * 60;
+
+/// The longest interval in seconds OpenSky accepts for arrival and departure requests.
+/// AirportFlightsRequest splits longer intervals into chunks of this length.
+///
+pub const MAX_AIRPORT_INTERVAL: u64 = 2 * 24 * 60 * 60;
+
+/// The largest gap in seconds between two records of the same flight joined by
+/// merge_fragments()
+///
+pub const DEFAULT_MAX_FRAGMENT_GAP: u64 = 30 * 60;
+
+/// The default number of chunks an AirportFlightsRequest requests at the same time
+pub const DEFAULT_CHUNK_CONCURRENCY: usize = 1;
+
+/// A flight as returned by OpenSky's /flights endpoints. Flights serialize with snake_case field
+/// names, and deserialize from both these and OpenSky's camelCase names. to_opensky_json()
+/// converts a flight back into OpenSky's layout.
+///
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Flight {
     pub icao24: String,
-    #[serde(rename(deserialize = "firstSeen"))]
+    #[serde(rename(deserialize = "firstSeen"), alias = "first_seen")]
     pub first_seen: u64,
-    #[serde(rename(deserialize = "estDepartureAirport"))]
-    pub est_departure_airport: Option<String>,
-    #[serde(rename(deserialize = "lastSeen"))]
+    #[serde(
+        rename(deserialize = "estDepartureAirport"),
+        alias = "est_departure_airport",
+        default,
+        deserialize_with = "lenient_airport"
+    )]
+    pub est_departure_airport: Option<AirportIcao>,
+    #[serde(rename(deserialize = "lastSeen"), alias = "last_seen")]
     pub last_seen: u64,
-    #[serde(rename(deserialize = "estArrivalAirport"))]
-    pub est_arrival_airport: Option<String>,
+    #[serde(
+        rename(deserialize = "estArrivalAirport"),
+        alias = "est_arrival_airport",
+        default,
+        deserialize_with = "lenient_airport"
+    )]
+    pub est_arrival_airport: Option<AirportIcao>,
     pub callsign: Option<String>,
-    #[serde(rename(deserialize = "estDepartureAirportHorizDistance"))]
+    #[serde(
+        rename(deserialize = "estDepartureAirportHorizDistance"),
+        alias = "est_departure_airport_horiz_distance"
+    )]
     pub est_departure_airport_horiz_distance: Option<u32>,
-    #[serde(rename(deserialize = "estDepartureAirportVertDistance"))]
+    #[serde(
+        rename(deserialize = "estDepartureAirportVertDistance"),
+        alias = "est_departure_airport_vert_distance"
+    )]
     pub est_departure_airport_vert_distance: Option<u32>,
-    #[serde(rename(deserialize = "estArrivalAirportHorizDistance"))]
+    #[serde(
+        rename(deserialize = "estArrivalAirportHorizDistance"),
+        alias = "est_arrival_airport_horiz_distance"
+    )]
     pub est_arrival_airport_horiz_distance: Option<u32>,
-    #[serde(rename(deserialize = "estArrivalAirportVertDistance"))]
+    #[serde(
+        rename(deserialize = "estArrivalAirportVertDistance"),
+        alias = "est_arrival_airport_vert_distance"
+    )]
     pub est_arrival_airport_vert_distance: Option<u32>,
-    #[serde(rename(deserialize = "departureAirportCandidatesCount"))]
+    #[serde(
+        rename(deserialize = "departureAirportCandidatesCount"),
+        alias = "departure_airport_candidates_count"
+    )]
     pub departure_airport_candidates_count: u16,
-    #[serde(rename(deserialize = "arrivalAirportCandidatesCount"))]
+    #[serde(
+        rename(deserialize = "arrivalAirportCandidatesCount"),
+        alias = "arrival_airport_candidates_count"
+    )]
     pub arrival_airport_candidates_count: u16,
 }
 
 #[derive(Debug, Clone)]
-struct FlightsRequest {
-    login: Option<Arc<(String, String)>>,
+pub struct FlightsRequest {
+    context: RequestContext,
     begin: u64,
     end: u64,
     icao24_address: Option<String>,
 }
 
-#[derive(Debug, Clone)]
-struct ArrivalsRequest {}
-
 impl FlightsRequest {
     pub async fn send(&self) -> Result<Vec<Flight>, Error> {
-        let login_part = if let Some(login) = &self.login {
-            format!("{}:{}@", login.0, login.1)
-        } else {
-            String::new()
-        };
+        self.validate()?;
 
-        let mut args = String::new();
+        let (status, bytes) = self
+            .context
+            .get(EndpointClass::Flights, &self.url())
+            .await?;
 
-        args.push_str(&format!("?begin={}&end={}", self.begin, self.end));
+        self.context
+            .observe_parse(EndpointClass::Flights, parse_flights(status, &bytes))
+    }
 
-        let endpoint = "all";
+    /// Sends the request like send(), and keeps the original response body next to the
+    /// parsed flights
+    ///
+    pub async fn send_keep_raw(&self) -> Result<WithRaw<Vec<Flight>>, Error> {
+        self.validate()?;
 
-        let url = format!(
-            "https://{}opensky-network.org/api/flights/{}{}",
-            login_part, endpoint, args
-        );
+        let (status, bytes) = self
+            .context
+            .get(EndpointClass::Flights, &self.url())
+            .await?;
 
-        debug!("url = {}", url);
+        Ok(WithRaw {
+            parsed: self
+                .context
+                .observe_parse(EndpointClass::Flights, parse_flights(status, &bytes))?,
+            raw: bytes,
+        })
+    }
 
-        let res = reqwest::get(url).await?;
+    /// Renders this request as an equivalent curl command, for debugging and bug reports. The
+    /// password is masked.
+    ///
+    pub fn to_curl(&self) -> String {
+        self.context.curl(&self.url(), false)
+    }
 
-        match res.status() {
-            reqwest::StatusCode::OK => {
-                let bytes = res.bytes().await?.to_vec();
+    /// Renders this request as an equivalent curl command, including the password
+    pub fn to_curl_with_credentials(&self) -> String {
+        self.context.curl(&self.url(), true)
+    }
 
-                let result: Vec<Flight> = match serde_json::from_slice(&bytes) {
-                    Ok(result) => result,
-                    Err(e) => {
-                        debug!("Error: {:?}", e);
-                        return Err(Error::InvalidJson(e));
-                    }
-                };
+    /// Returns the longest interval OpenSky accepts for this request: MAX_AIRCRAFT_INTERVAL if
+    /// it is limited to an aircraft, otherwise MAX_FLIGHTS_INTERVAL
+    ///
+    pub fn max_interval(&self) -> u64 {
+        if self.icao24_address.is_some() {
+            MAX_AIRCRAFT_INTERVAL
+        } else {
+            MAX_FLIGHTS_INTERVAL
+        }
+    }
+
+    /// Checks the parameters of this request, which send() does before sending it. Returns
+    /// Error::InvalidRequest if the end is not after the beginning, or if the interval is
+    /// longer than max_interval().
+    ///
+    pub fn validate(&self) -> Result<(), Error> {
+        validate_interval(self.begin, self.end, Some(self.max_interval()))
+    }
 
-                Ok(result)
+    /// Returns the URL this request is sent to. Requests limited to an aircraft use the
+    /// /flights/aircraft endpoint, all others /flights/all.
+    ///
+    pub(crate) fn url(&self) -> String {
+        let endpoints = self.context.endpoints();
 
-            }
-            status => Err(Error::Http(status)),
+        match &self.icao24_address {
+            Some(address) => self.context.url(
+                &endpoints.flights_aircraft,
+                &format!(
+                    "?icao24={}&begin={}&end={}",
+                    address.to_lowercase(),
+                    self.begin,
+                    self.end
+                ),
+            ),
+            None => self.context.url(
+                &endpoints.flights_all,
+                &format!("?begin={}&end={}", self.begin, self.end),
+            ),
         }
     }
 }
 
+/// Returns Error::InvalidRequest if end is not after begin, or if the interval is longer than
+/// max_interval
+///
+fn validate_interval(begin: u64, end: u64, max_interval: Option<u64>) -> Result<(), Error> {
+    if end <= begin {
+        return Err(Error::InvalidRequest(format!(
+            "the end ({}) must be after the beginning ({}), are they swapped?",
+            end, begin
+        )));
+    }
+
+    if let Some(max_interval) = max_interval.filter(|max| end - begin > *max) {
+        return Err(Error::InvalidRequest(format!(
+            "the interval of {} seconds is longer than the {} seconds this endpoint accepts",
+            end - begin,
+            max_interval
+        )));
+    }
+
+    Ok(())
+}
+
+fn parse_flights(status: reqwest::StatusCode, bytes: &[u8]) -> Result<Vec<Flight>, Error> {
+    match status {
+        reqwest::StatusCode::OK => {
+            let result: Vec<Flight> = match serde_json::from_slice(bytes) {
+                Ok(result) => result,
+                Err(e) => {
+                    debug!("Error: {:?}", e);
+                    return Err(Error::InvalidJson(e));
+                }
+            };
+
+            Ok(result)
+        }
+        status => Err(Error::Http(status)),
+    }
+}
 
 pub struct FlightsRequestBuilder {
     inner: FlightsRequest,
 }
 
 impl FlightsRequestBuilder {
-    pub fn new(login: Option<Arc<(String, String)>>, begin: u64, end: u64) -> Self {
+    pub fn new(context: RequestContext, begin: u64, end: u64) -> Self {
         Self {
             inner: FlightsRequest {
-                login,
+                context,
                 begin,
                 end,
                 icao24_address: None,
@@ -106,7 +247,9 @@ impl FlightsRequestBuilder {
     /// interval. The beginning and ending times are numbers that represent times in seconds since
     /// the Unix Epoch.
     ///
-    /// The interval must not span greater than 2 hours, otherwise the request will fail.
+    /// The interval must not be longer than 2 hours (MAX_FLIGHTS_INTERVAL), or 30 days
+    /// (MAX_AIRCRAFT_INTERVAL) if the request is limited to an aircraft using by_aircraft(),
+    /// otherwise sending the request fails.
     ///
     pub fn in_interval(&mut self, begin: u64, end: u64) -> &mut Self {
         self.inner.begin = begin;
@@ -115,8 +258,17 @@ impl FlightsRequestBuilder {
         self
     }
 
+    /// Sets the beginning and end of the flight request interval like in_interval(), using
+    /// DateTimes instead of seconds since the Unix Epoch. The range excludes its end, so the
+    /// last second before range.end is sent as OpenSky's inclusive end.
+    ///
+    pub fn in_time_range(&mut self, range: Range<DateTime<Utc>>) -> &mut Self {
+        self.in_interval(time::seconds(range.start), time::inclusive_end(range.end))
+    }
+
     /// This method can be used to filter the flight data by a specific aircraft. The aircraft
-    /// ICAO24 address is in hex string representation.
+    /// ICAO24 address is in hex string representation. The request is then sent to the
+    /// /flights/aircraft endpoint, which accepts intervals of up to 30 days.
     ///
     pub fn by_aircraft(&mut self, address: String) -> &mut Self {
         self.inner.icao24_address = Some(address);
@@ -144,6 +296,13 @@ impl FlightsRequestBuilder {
     pub async fn send(self) -> Result<Vec<Flight>, Error> {
         self.inner.send().await
     }
+
+    /// Consumes this FlightsRequestBuilder and sends the request to the API, keeping the
+    /// original response body next to the parsed flights
+    ///
+    pub async fn send_keep_raw(self) -> Result<WithRaw<Vec<Flight>>, Error> {
+        self.inner.send_keep_raw().await
+    }
 }
 
 impl From<FlightsRequestBuilder> for FlightsRequest {
@@ -151,3 +310,600 @@ impl From<FlightsRequestBuilder> for FlightsRequest {
         frb.consume()
     }
 }
+
+/// Whether an AirportFlightsRequest gets the flights arriving at or departing from an airport
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AirportMovement {
+    Arrival,
+    Departure,
+}
+
+/// Gets the flights arriving at or departing from an airport within a time interval. Intervals
+/// longer than MAX_AIRPORT_INTERVAL are sent as several requests, by default one after the
+/// other (see with_concurrency()).
+///
+#[derive(Debug, Clone)]
+pub struct AirportFlightsRequest {
+    context: RequestContext,
+    movement: AirportMovement,
+    airport: AirportIcao,
+    begin: u64,
+    end: u64,
+    concurrency: usize,
+}
+
+impl AirportFlightsRequest {
+    /// Creates a new AirportFlightsRequest for the airport with the given ICAO code. The
+    /// beginning and ending times are in seconds since the Unix Epoch.
+    ///
+    pub fn new(
+        context: RequestContext,
+        movement: AirportMovement,
+        airport: AirportIcao,
+        begin: u64,
+        end: u64,
+    ) -> Self {
+        Self {
+            context,
+            movement,
+            airport,
+            begin,
+            end,
+            concurrency: DEFAULT_CHUNK_CONCURRENCY,
+        }
+    }
+
+    /// Sets the maximum number of chunks that are requested at the same time. Requests also
+    /// count towards the limit set with OpenSkyApi::with_max_concurrent_requests().
+    ///
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+
+        self
+    }
+
+    /// Checks the parameters of this request, which send() does before sending it. Returns
+    /// Error::InvalidRequest if the end is not after the beginning. Long intervals are valid,
+    /// as they are split into chunks.
+    ///
+    pub fn validate(&self) -> Result<(), Error> {
+        validate_interval(self.begin, self.end, None)
+    }
+
+    /// Returns the intervals this request is split into
+    pub fn chunks(&self) -> Vec<(u64, u64)> {
+        let mut chunks = Vec::new();
+        let mut begin = self.begin;
+
+        loop {
+            let end = self.end.min(begin.saturating_add(MAX_AIRPORT_INTERVAL));
+            chunks.push((begin, end));
+
+            if end >= self.end {
+                break;
+            }
+
+            begin = end;
+        }
+
+        chunks
+    }
+
+    /// Sends the request. Flights that were returned for more than one chunk, e.g. because
+    /// they span the boundary between two chunks, are only included once, and the flights of
+    /// all chunks are sorted by first_seen.
+    ///
+    /// If any chunk fails, no further chunks are requested and the error is returned.
+    ///
+    pub async fn send(&self) -> Result<Vec<Flight>, Error> {
+        self.validate()?;
+
+        let responses: Vec<Vec<Flight>> = stream::iter(self.chunks())
+            .map(|(begin, end)| self.send_chunk(begin, end))
+            .buffer_unordered(self.concurrency)
+            .try_collect()
+            .await?;
+
+        Ok(dedup_flights(responses.into_iter().flatten().collect()))
+    }
+
+    /// Sends the request like send(), but yields the flights of each chunk as soon as the chunk
+    /// and all chunks before it are complete, so large intervals don't have to be kept in
+    /// memory at once. Flights are yielded in the order of the chunks, and sorted by
+    /// first_seen within each chunk.
+    ///
+    /// A flight which spans the boundary between two chunks is only yielded once, with the
+    /// record of the earlier chunk. If the parameters are invalid (see validate()) or a chunk
+    /// fails, the stream yields that error and then ends.
+    ///
+    pub fn stream(&self) -> impl Stream<Item = Result<Flight, Error>> + '_ {
+        let invalid = self.validate().err();
+        let chunks = match invalid {
+            Some(_) => Vec::new(),
+            None => self.chunks(),
+        };
+
+        let flights = stream::iter(chunks)
+            .map(|(begin, end)| self.send_chunk(begin, end))
+            .buffered(self.concurrency)
+            .scan(Some(Vec::<Flight>::new()), |previous, result| {
+                let items = match (previous.as_mut(), result) {
+                    (None, _) => return future::ready(None),
+                    (Some(previous), Ok(mut flights)) => {
+                        flights.sort_by_key(|flight| flight.first_seen);
+
+                        let items = flights
+                            .iter()
+                            .filter(|flight| {
+                                !previous
+                                    .iter()
+                                    .any(|earlier| earlier.is_same_flight(flight))
+                            })
+                            .cloned()
+                            .map(Ok)
+                            .collect();
+
+                        *previous = flights;
+                        items
+                    }
+                    (Some(_), Err(e)) => {
+                        *previous = None;
+                        vec![Err(e)]
+                    }
+                };
+
+                future::ready(Some(stream::iter(items)))
+            })
+            .flatten();
+
+        stream::iter(invalid.map(Err)).chain(flights)
+    }
+
+    /// Returns the ICAO code of the airport
+    pub fn airport(&self) -> &str {
+        self.airport.as_str()
+    }
+
+    /// Returns the beginning and end of the requested interval
+    pub fn interval(&self) -> (u64, u64) {
+        (self.begin, self.end)
+    }
+
+    /// Sends the request like send(), but keeps the original response body of every chunk
+    /// next to the flights parsed from it. Flights are not deduplicated across chunks.
+    ///
+    pub async fn send_keep_raw(&self) -> Result<Vec<WithRaw<Vec<Flight>>>, Error> {
+        self.validate()?;
+
+        let mut responses = Vec::new();
+
+        for (begin, end) in self.chunks() {
+            let (status, bytes) = self
+                .context
+                .get(EndpointClass::Flights, &self.chunk_url(begin, end))
+                .await?;
+
+            responses.push(WithRaw {
+                parsed: self.context.observe_parse(
+                    EndpointClass::Flights,
+                    parse_airport_flights(status, &bytes),
+                )?,
+                raw: bytes,
+            });
+        }
+
+        Ok(responses)
+    }
+
+    async fn send_chunk(&self, begin: u64, end: u64) -> Result<Vec<Flight>, Error> {
+        let (status, bytes) = self
+            .context
+            .get(EndpointClass::Flights, &self.chunk_url(begin, end))
+            .await?;
+
+        self.context.observe_parse(
+            EndpointClass::Flights,
+            parse_airport_flights(status, &bytes),
+        )
+    }
+
+    /// Renders this request as equivalent curl commands, one line per chunk, for debugging
+    /// and bug reports. The password is masked.
+    ///
+    pub fn to_curl(&self) -> String {
+        self.curl_commands(false)
+    }
+
+    /// Renders this request as equivalent curl commands, including the password
+    pub fn to_curl_with_credentials(&self) -> String {
+        self.curl_commands(true)
+    }
+
+    fn curl_commands(&self, include_credentials: bool) -> String {
+        self.chunks()
+            .into_iter()
+            .map(|(begin, end)| {
+                self.context
+                    .curl(&self.chunk_url(begin, end), include_credentials)
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Returns the URL the chunk from begin to end is sent to
+    pub(crate) fn chunk_url(&self, begin: u64, end: u64) -> String {
+        let endpoint = match self.movement {
+            AirportMovement::Arrival => &self.context.endpoints().flights_arrival,
+            AirportMovement::Departure => &self.context.endpoints().flights_departure,
+        };
+
+        let args = format!("?airport={}&begin={}&end={}", self.airport, begin, end);
+
+        self.context.url(endpoint, &args)
+    }
+}
+
+/// Gets the flights between two airports within a time interval. The departures of the
+/// departure airport are requested using an AirportFlightsRequest, and only those arriving at
+/// the arrival airport are kept.
+///
+#[derive(Debug, Clone)]
+pub struct RouteFlightsRequest {
+    departures: AirportFlightsRequest,
+    arrival: AirportIcao,
+}
+
+impl RouteFlightsRequest {
+    /// Creates a new RouteFlightsRequest for the flights from the departure to the arrival
+    /// airport. The beginning and ending times are in seconds since the Unix Epoch, and refer to
+    /// the departure time.
+    ///
+    pub fn new(
+        context: RequestContext,
+        departure: AirportIcao,
+        arrival: AirportIcao,
+        begin: u64,
+        end: u64,
+    ) -> Self {
+        Self {
+            departures: AirportFlightsRequest::new(
+                context,
+                AirportMovement::Departure,
+                departure,
+                begin,
+                end,
+            ),
+            arrival,
+        }
+    }
+
+    /// Sets the maximum number of chunks that are requested at the same time. See
+    /// AirportFlightsRequest::with_concurrency().
+    ///
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.departures = self.departures.with_concurrency(concurrency);
+
+        self
+    }
+
+    /// Returns the request used to get the departures
+    pub fn departures(&self) -> &AirportFlightsRequest {
+        &self.departures
+    }
+
+    /// Checks the parameters of this request, which send() does before sending it. Returns
+    /// Error::InvalidRequest if the end is not after the beginning.
+    ///
+    pub fn validate(&self) -> Result<(), Error> {
+        self.departures.validate()
+    }
+
+    /// Sends the request, and returns the flights arriving at the arrival airport sorted by
+    /// first_seen, i.e. by departure time
+    ///
+    pub async fn send(&self) -> Result<Vec<Flight>, Error> {
+        self.validate()?;
+
+        let mut flights: Vec<Flight> = self
+            .departures
+            .send()
+            .await?
+            .into_iter()
+            .filter(|flight| flight.arrived_at(self.arrival.as_str()))
+            .collect();
+
+        flights.sort_by(|a, b| {
+            a.first_seen
+                .cmp(&b.first_seen)
+                .then_with(|| a.icao24.cmp(&b.icao24))
+        });
+
+        Ok(flights)
+    }
+}
+
+/// Parses an estimated airport, treating codes which are not ICAO airport codes like a missing
+/// estimate rather than failing the whole response
+///
+fn lenient_airport<'de, D>(deserializer: D) -> Result<Option<AirportIcao>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Ok(Option::<String>::deserialize(deserializer)?.and_then(|code| code.parse().ok()))
+}
+
+fn parse_airport_flights(status: reqwest::StatusCode, bytes: &[u8]) -> Result<Vec<Flight>, Error> {
+    match status {
+        reqwest::StatusCode::OK => Ok(serde_json::from_slice(bytes)?),
+        // OpenSky responds with 404 if there were no flights in the interval
+        reqwest::StatusCode::NOT_FOUND => Ok(Vec::new()),
+        status => Err(Error::Http(status)),
+    }
+}
+
+/// Returns the interval in seconds since the Unix Epoch covering the calendar days from first
+/// to last (inclusive) in the given time zone, e.g. an airport's local time zone. Days on which
+/// daylight saving time starts or ends are 23 or 25 hours long.
+///
+pub fn local_days<Tz: TimeZone>(first: NaiveDate, last: NaiveDate, tz: &Tz) -> (u64, u64) {
+    let begin = local_midnight(first, tz);
+    let end = last
+        .succ_opt()
+        .map(|next| local_midnight(next, tz))
+        .unwrap_or(begin);
+
+    (begin, end.saturating_sub(1).max(begin))
+}
+
+/// Returns the first instant of the given day in the time zone. In the few time zones which
+/// switch to daylight saving time at midnight, the day starts at the end of the skipped hour.
+///
+fn local_midnight<Tz: TimeZone>(date: NaiveDate, tz: &Tz) -> u64 {
+    let midnight = date.and_hms_opt(0, 0, 0).unwrap();
+
+    (0..=3)
+        .find_map(|hours| {
+            tz.from_local_datetime(&(midnight + chrono::Duration::hours(hours)))
+                .earliest()
+        })
+        .map(|start| start.timestamp().max(0) as u64)
+        .unwrap_or_else(|| midnight.and_utc().timestamp().max(0) as u64)
+}
+
+/// Unifies duplicate records of the same flight, which occur when a query interval is split
+/// into overlapping chunks, or when a flight spans the boundary between two chunks.
+///
+/// Two records are considered the same flight if they have the same ICAO24 address and their
+/// [first_seen, last_seen] windows overlap. The merged record spans both windows, takes its
+/// departure estimate from the earliest record and its arrival estimate from the latest one.
+/// The result is sorted by first_seen.
+///
+pub fn dedup_flights(mut flights: Vec<Flight>) -> Vec<Flight> {
+    flights.sort_by(|a, b| {
+        a.icao24
+            .cmp(&b.icao24)
+            .then(a.first_seen.cmp(&b.first_seen))
+    });
+
+    let mut merged: Vec<Flight> = Vec::with_capacity(flights.len());
+
+    for flight in flights {
+        match merged.last_mut() {
+            Some(last) if last.icao24 == flight.icao24 && flight.first_seen <= last.last_seen => {
+                last.merge(flight);
+            }
+            _ => merged.push(flight),
+        }
+    }
+
+    merged.sort_by(|a, b| {
+        a.first_seen
+            .cmp(&b.first_seen)
+            .then_with(|| a.icao24.cmp(&b.icao24))
+    });
+
+    merged
+}
+
+/// Writes the flights as JSON Lines, one JSON object with the snake_case field names of Flight
+/// per line. Calling this function repeatedly on the same writer appends further flights, so
+/// results can be written as they arrive.
+///
+pub fn write_flights_jsonl<W: Write>(mut writer: W, flights: &[Flight]) -> Result<(), Error> {
+    for flight in flights {
+        serde_json::to_writer(&mut writer, flight)?;
+        writer.write_all(b"\n")?;
+    }
+
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// Joins fragments of the same flight using DEFAULT_MAX_FRAGMENT_GAP. See
+/// merge_fragments_with().
+///
+pub fn merge_fragments(flights: Vec<Flight>) -> Vec<Flight> {
+    merge_fragments_with(flights, DEFAULT_MAX_FRAGMENT_GAP)
+}
+
+/// Joins records which OpenSky split from a single flight, e.g. because of a gap in reception,
+/// into one record. Overlapping records are merged like in dedup_flights().
+///
+/// Two consecutive records of the same ICAO24 address are joined if the second one starts at
+/// most max_gap seconds after the first one ends, their callsigns match (ignoring padding and
+/// case, unless one of them is unknown), and they are not separate legs: if the first record
+/// has an arrival airport and the second one a departure airport, the aircraft landed in
+/// between. The result is sorted by first_seen.
+///
+pub fn merge_fragments_with(flights: Vec<Flight>, max_gap: u64) -> Vec<Flight> {
+    let mut flights = dedup_flights(flights);
+    flights.sort_by(|a, b| {
+        a.icao24
+            .cmp(&b.icao24)
+            .then(a.first_seen.cmp(&b.first_seen))
+    });
+
+    let mut merged: Vec<Flight> = Vec::with_capacity(flights.len());
+
+    for flight in flights {
+        match merged.last_mut() {
+            Some(last) if last.is_fragment_of_same_flight(&flight, max_gap) => {
+                last.merge(flight);
+            }
+            _ => merged.push(flight),
+        }
+    }
+
+    merged.sort_by(|a, b| {
+        a.first_seen
+            .cmp(&b.first_seen)
+            .then_with(|| a.icao24.cmp(&b.icao24))
+    });
+
+    merged
+}
+
+impl Flight {
+    /// Returns the number of seconds between the first and last time the aircraft was seen
+    pub fn duration(&self) -> u64 {
+        self.last_seen.saturating_sub(self.first_seen)
+    }
+
+    /// Returns the time between the first and last time the aircraft was seen
+    pub fn chrono_duration(&self) -> chrono::Duration {
+        chrono::Duration::seconds(self.duration() as i64)
+    }
+
+    /// Returns the times the aircraft was first and last seen as a range, e.g. to check
+    /// whether it contains a DateTime
+    ///
+    pub fn datetime_range(&self) -> RangeInclusive<DateTime<Utc>> {
+        self.first_seen_datetime()..=self.last_seen_datetime()
+    }
+
+    /// Returns true if the aircraft was seen at any time within the interval from begin to end
+    /// (inclusive). Both are in seconds since the Unix Epoch.
+    ///
+    pub fn overlaps(&self, begin: u64, end: u64) -> bool {
+        self.first_seen <= end && begin <= self.last_seen
+    }
+
+    /// Returns true if the timestamp in seconds since the Unix Epoch is between the first and
+    /// last time the aircraft was seen (inclusive)
+    ///
+    pub fn contains(&self, timestamp: u64) -> bool {
+        (self.first_seen..=self.last_seen).contains(&timestamp)
+    }
+
+    /// Converts the flight back into the layout of OpenSky's responses, with camelCase field
+    /// names, which can be parsed again using serde_json
+    ///
+    pub fn to_opensky_json(&self) -> Value {
+        json!({
+            "icao24": self.icao24,
+            "firstSeen": self.first_seen,
+            "estDepartureAirport": self.est_departure_airport,
+            "lastSeen": self.last_seen,
+            "estArrivalAirport": self.est_arrival_airport,
+            "callsign": self.callsign,
+            "estDepartureAirportHorizDistance": self.est_departure_airport_horiz_distance,
+            "estDepartureAirportVertDistance": self.est_departure_airport_vert_distance,
+            "estArrivalAirportHorizDistance": self.est_arrival_airport_horiz_distance,
+            "estArrivalAirportVertDistance": self.est_arrival_airport_vert_distance,
+            "departureAirportCandidatesCount": self.departure_airport_candidates_count,
+            "arrivalAirportCandidatesCount": self.arrival_airport_candidates_count,
+        })
+    }
+
+    /// Returns the time the aircraft was first seen
+    pub fn first_seen_datetime(&self) -> DateTime<Utc> {
+        time::datetime(self.first_seen)
+    }
+
+    /// Returns the time the aircraft was last seen
+    pub fn last_seen_datetime(&self) -> DateTime<Utc> {
+        time::datetime(self.last_seen)
+    }
+
+    /// Returns true if both the departure and the arrival airport have been estimated
+    pub fn is_complete(&self) -> bool {
+        self.est_departure_airport.is_some() && self.est_arrival_airport.is_some()
+    }
+
+    /// Returns true if the estimated departure airport is the given ICAO airport code. The
+    /// comparison is case-insensitive.
+    ///
+    pub fn departed_from(&self, airport: &str) -> bool {
+        self.est_departure_airport
+            .is_some_and(|departure| departure == airport)
+    }
+
+    /// Returns true if the estimated arrival airport is the given ICAO airport code. The
+    /// comparison is case-insensitive.
+    ///
+    pub fn arrived_at(&self, airport: &str) -> bool {
+        self.est_arrival_airport
+            .is_some_and(|arrival| arrival == airport)
+    }
+
+    /// Returns true if the callsign starts with the given prefix, e.g. an airline's ICAO code
+    /// like "DLH". The padding OpenSky adds to callsigns is ignored, and the comparison is
+    /// case-insensitive.
+    ///
+    pub fn matches_callsign(&self, prefix: &str) -> bool {
+        let prefix = prefix.trim().to_ascii_uppercase();
+
+        self.callsign
+            .as_deref()
+            .is_some_and(|callsign| callsign.trim().to_ascii_uppercase().starts_with(&prefix))
+    }
+
+    /// Returns true if both are records of the same aircraft whose [first_seen, last_seen]
+    /// windows overlap
+    ///
+    fn is_same_flight(&self, other: &Flight) -> bool {
+        self.icao24 == other.icao24 && self.overlaps(other.first_seen, other.last_seen)
+    }
+
+    /// Returns true if the later record continues this one after a gap of at most max_gap
+    /// seconds. See merge_fragments_with().
+    ///
+    fn is_fragment_of_same_flight(&self, later: &Flight, max_gap: u64) -> bool {
+        let callsigns_match = match (&self.callsign, &later.callsign) {
+            (Some(a), Some(b)) => a.trim().eq_ignore_ascii_case(b.trim()),
+            _ => true,
+        };
+        let landed_between =
+            self.est_arrival_airport.is_some() && later.est_departure_airport.is_some();
+
+        self.icao24 == later.icao24
+            && later.first_seen.saturating_sub(self.last_seen) <= max_gap
+            && callsigns_match
+            && !landed_between
+    }
+
+    /// Merges a later record of the same flight into this one
+    fn merge(&mut self, later: Flight) {
+        if self.callsign.is_none() {
+            self.callsign = later.callsign;
+        }
+
+        if later.last_seen >= self.last_seen {
+            self.last_seen = later.last_seen;
+            self.est_arrival_airport = later
+                .est_arrival_airport
+                .or(self.est_arrival_airport.take());
+            self.est_arrival_airport_horiz_distance = later.est_arrival_airport_horiz_distance;
+            self.est_arrival_airport_vert_distance = later.est_arrival_airport_vert_distance;
+            self.arrival_airport_candidates_count = later.arrival_airport_candidates_count;
+        }
+
+        if self.est_departure_airport.is_none() && later.est_departure_airport.is_some() {
+            self.est_departure_airport = later.est_departure_airport;
+            self.est_departure_airport_horiz_distance = later.est_departure_airport_horiz_distance;
+            self.est_departure_airport_vert_distance = later.est_departure_airport_vert_distance;
+            self.departure_airport_candidates_count = later.departure_airport_candidates_count;
+        }
+    }
+}
@@ -0,0 +1,37 @@
+//! Conversions between this crate's types and the geometry types of the `geo` ecosystem
+//! (`geo_types`, re-exported by `geo`).
+//!
+//! Points use the usual x = longitude, y = latitude order.
+
+use geo_types::{coord, Point, Rect};
+
+use crate::{bounding_box::BoundingBox, states::StateVector, tracks::Waypoint};
+
+impl StateVector {
+    /// Returns the position of the aircraft, or None if it is unknown
+    pub fn to_point(&self) -> Option<Point<f64>> {
+        Some(Point::new(self.longitude?, self.latitude?))
+    }
+}
+
+impl Waypoint {
+    /// Returns the position of the waypoint, or None if it is unknown
+    pub fn to_point(&self) -> Option<Point<f64>> {
+        Some(Point::new(self.longitude?, self.latitude?))
+    }
+}
+
+impl From<BoundingBox> for Rect<f64> {
+    fn from(bbox: BoundingBox) -> Self {
+        Rect::new(
+            coord! { x: bbox.long_min, y: bbox.lat_min },
+            coord! { x: bbox.long_max, y: bbox.lat_max },
+        )
+    }
+}
+
+impl From<Rect<f64>> for BoundingBox {
+    fn from(rect: Rect<f64>) -> Self {
+        BoundingBox::new(rect.min().y, rect.max().y, rect.min().x, rect.max().x)
+    }
+}
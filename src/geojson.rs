@@ -0,0 +1,56 @@
+//! Conversion of States snapshots into GeoJSON, so that they can be shown directly by web
+//! map libraries like Leaflet or Mapbox GL.
+//!
+//! Every aircraft with a known position becomes a Point feature, identified by its icao24 and
+//! carrying the callsign, origin_country, altitude (barometric, in meters), velocity (m/s),
+//! heading (true track in degrees), and on_ground properties. Unknown values are null.
+
+use ::geojson::{feature::Id, Feature, FeatureCollection, Geometry, JsonObject, Value};
+
+use crate::states::{StateVector, States};
+
+impl StateVector {
+    /// Converts this state vector into a GeoJSON Point feature, or returns None if its
+    /// position is unknown
+    ///
+    pub fn to_geojson(&self) -> Option<Feature> {
+        let point = Value::Point(vec![self.longitude?, self.latitude?]);
+
+        let mut properties = JsonObject::new();
+        properties.insert("icao24".into(), self.icao24.clone().into());
+        properties.insert(
+            "callsign".into(),
+            self.callsign.as_deref().map(str::trim).into(),
+        );
+        properties.insert("origin_country".into(), self.origin_country.clone().into());
+        properties.insert("altitude".into(), self.baro_altitude.into());
+        properties.insert("velocity".into(), self.velocity.into());
+        properties.insert("heading".into(), self.true_track.into());
+        properties.insert("on_ground".into(), self.on_ground.into());
+
+        Some(Feature {
+            bbox: None,
+            geometry: Some(Geometry::new(point)),
+            id: Some(Id::String(self.icao24.clone())),
+            properties: Some(properties),
+            foreign_members: None,
+        })
+    }
+}
+
+impl States {
+    /// Converts this snapshot into a GeoJSON FeatureCollection. Aircraft without a position
+    /// are skipped.
+    ///
+    pub fn to_geojson(&self) -> FeatureCollection {
+        FeatureCollection {
+            bbox: None,
+            features: self
+                .states
+                .iter()
+                .filter_map(StateVector::to_geojson)
+                .collect(),
+            foreign_members: None,
+        }
+    }
+}
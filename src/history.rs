@@ -0,0 +1,157 @@
+//! Per-aircraft histories of polled state vectors, e.g. for drawing the recent tracks of live
+//! traffic without requesting them from the /tracks endpoint.
+
+use std::collections::{HashMap, VecDeque};
+
+use crate::{
+    states::{StateVector, States},
+    tracks::{FlightTrack, Waypoint},
+};
+
+/// The default number of state vectors kept per aircraft
+pub const DEFAULT_MAX_LEN: usize = 360;
+
+/// The default age in seconds after which state vectors are evicted
+pub const DEFAULT_MAX_AGE: u64 = 3600;
+
+/// Collects the state vectors of successive snapshots by aircraft. Only state vectors with a
+/// newer last contact than the previous one of the same aircraft are kept, so polling faster
+/// than OpenSky updates does not create duplicates.
+///
+/// Each aircraft keeps at most max_len state vectors, and state vectors whose last contact is
+/// more than max_age seconds older than the latest ingested snapshot are evicted, together
+/// with aircraft which have none left.
+///
+#[derive(Debug, Clone)]
+pub struct StateHistory {
+    max_len: usize,
+    max_age: u64,
+    time: u64,
+    aircraft: HashMap<String, VecDeque<StateVector>>,
+}
+
+impl Default for StateHistory {
+    fn default() -> Self {
+        Self {
+            max_len: DEFAULT_MAX_LEN,
+            max_age: DEFAULT_MAX_AGE,
+            time: 0,
+            aircraft: HashMap::new(),
+        }
+    }
+}
+
+impl StateHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the number of state vectors kept per aircraft, at least one
+    pub fn with_max_len(mut self, max_len: usize) -> Self {
+        self.max_len = max_len.max(1);
+
+        self
+    }
+
+    /// Sets the age in seconds after which state vectors are evicted
+    pub fn with_max_age(mut self, max_age: u64) -> Self {
+        self.max_age = max_age;
+
+        self
+    }
+
+    /// Adds the state vectors of a snapshot, and evicts the ones which are too old
+    pub fn ingest(&mut self, states: &States) {
+        self.time = self.time.max(states.time);
+
+        for state in &states.states {
+            let history = self.aircraft.entry(state.icao24.clone()).or_default();
+
+            if history
+                .back()
+                .is_some_and(|latest| state.last_contact <= latest.last_contact)
+            {
+                continue;
+            }
+
+            history.push_back(state.clone());
+
+            if history.len() > self.max_len {
+                history.pop_front();
+            }
+        }
+
+        self.evict();
+    }
+
+    fn evict(&mut self) {
+        let oldest = self.time.saturating_sub(self.max_age);
+
+        self.aircraft.retain(|_, history| {
+            while history
+                .front()
+                .is_some_and(|state| state.last_contact < oldest)
+            {
+                history.pop_front();
+            }
+
+            !history.is_empty()
+        });
+    }
+
+    /// Returns the number of aircraft with a history
+    pub fn len(&self) -> usize {
+        self.aircraft.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.aircraft.is_empty()
+    }
+
+    /// Returns the ICAO24 addresses of all aircraft with a history, in no particular order
+    pub fn icao24s(&self) -> impl Iterator<Item = &str> {
+        self.aircraft.keys().map(String::as_str)
+    }
+
+    /// Returns the state vectors of the aircraft, oldest first
+    pub fn states(&self, icao24: &str) -> Option<&VecDeque<StateVector>> {
+        self.aircraft.get(icao24)
+    }
+
+    /// Returns the most recent state vector of the aircraft
+    pub fn latest(&self, icao24: &str) -> Option<&StateVector> {
+        self.aircraft.get(icao24)?.back()
+    }
+
+    /// Returns the positions of the aircraft as a FlightTrack with one waypoint per state
+    /// vector with a position, or None if there is no history or no position
+    ///
+    pub fn trajectory(&self, icao24: &str) -> Option<FlightTrack> {
+        let history = self.aircraft.get(icao24)?;
+        let callsign = history
+            .iter()
+            .rev()
+            .find_map(|state| state.callsign.clone());
+
+        let mut track = FlightTrack::new(icao24.to_string(), callsign);
+        track.path = history
+            .iter()
+            .map(Waypoint::from)
+            .filter(|waypoint| waypoint.latitude.is_some() && waypoint.longitude.is_some())
+            .collect();
+
+        track.start_time = track.path.first()?.time;
+        track.end_time = track.path.last()?.time;
+
+        Some(track)
+    }
+
+    /// Removes the history of the aircraft
+    pub fn remove(&mut self, icao24: &str) -> Option<VecDeque<StateVector>> {
+        self.aircraft.remove(icao24)
+    }
+
+    pub fn clear(&mut self) {
+        self.aircraft.clear();
+    }
+}
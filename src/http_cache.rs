@@ -0,0 +1,141 @@
+//! Conditional requests using ETag and Last-Modified validators.
+//!
+//! When a response carries an ETag or Last-Modified header, its body is stored along with the
+//! validators. The next request to the same URL asks the server whether the body changed, and
+//! if the server answers with HTTP 304 (Not Modified), the stored body is parsed again instead
+//! of downloading it. This saves bandwidth, and with OpenSky's credit based rate limits also
+//! credits, when the same data is polled repeatedly.
+//!
+//! Only the most recently used responses are kept, since every historical state vector request
+//! has a URL of its own which is unlikely to be requested again.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use reqwest::header::{
+    HeaderMap, HeaderValue, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED,
+};
+
+use crate::time::Instant;
+
+/// The default number of responses an HttpCache keeps
+pub const DEFAULT_HTTP_CACHE_ENTRIES: usize = 64;
+
+#[derive(Debug, Clone)]
+pub(crate) struct CachedResponse {
+    etag: Option<HeaderValue>,
+    last_modified: Option<HeaderValue>,
+    pub(crate) body: Arc<[u8]>,
+    last_used: Instant,
+}
+
+impl CachedResponse {
+    /// Adds the conditional request headers for this response
+    pub(crate) fn add_validators(
+        &self,
+        mut request: reqwest::RequestBuilder,
+    ) -> reqwest::RequestBuilder {
+        if let Some(etag) = &self.etag {
+            request = request.header(IF_NONE_MATCH, etag.clone());
+        }
+
+        if let Some(last_modified) = &self.last_modified {
+            request = request.header(IF_MODIFIED_SINCE, last_modified.clone());
+        }
+
+        request
+    }
+}
+
+/// Response bodies and their validators by URL, shared by all requests created from the same
+/// OpenSkyApi instance. Once max_entries responses are stored, the least recently used one is
+/// removed to make room for the next.
+///
+#[derive(Debug, Clone)]
+pub struct HttpCache {
+    max_entries: usize,
+    entries: Arc<Mutex<HashMap<String, CachedResponse>>>,
+}
+
+impl Default for HttpCache {
+    fn default() -> Self {
+        Self::with_max_entries(DEFAULT_HTTP_CACHE_ENTRIES)
+    }
+}
+
+impl HttpCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an empty cache which keeps at most max_entries responses
+    pub fn with_max_entries(max_entries: usize) -> Self {
+        Self {
+            max_entries: max_entries.max(1),
+            entries: Arc::default(),
+        }
+    }
+
+    pub fn max_entries(&self) -> usize {
+        self.max_entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+
+    /// Returns the stored response to the URL. The request keeps it until the response
+    /// arrives, so a 304 can be answered even if the entry is evicted in the meantime.
+    ///
+    pub(crate) fn get(&self, url: &str) -> Option<CachedResponse> {
+        self.entries.lock().unwrap().get_mut(url).map(|cached| {
+            cached.last_used = Instant::now();
+            cached.clone()
+        })
+    }
+
+    /// Stores the body of a successful response, if the response has validators, removing the
+    /// least recently used response if the cache is full
+    ///
+    pub(crate) fn store(&self, url: &str, headers: &HeaderMap, body: &[u8]) {
+        let etag = headers.get(ETAG).cloned();
+        let last_modified = headers.get(LAST_MODIFIED).cloned();
+
+        if etag.is_none() && last_modified.is_none() {
+            return;
+        }
+
+        let mut entries = self.entries.lock().unwrap();
+
+        if entries.len() >= self.max_entries && !entries.contains_key(url) {
+            let least_recently_used = entries
+                .iter()
+                .min_by_key(|(_, cached)| cached.last_used)
+                .map(|(url, _)| url.clone());
+
+            if let Some(url) = least_recently_used {
+                entries.remove(&url);
+            }
+        }
+
+        entries.insert(
+            url.to_string(),
+            CachedResponse {
+                etag,
+                last_modified,
+                body: Arc::from(body),
+                last_used: Instant::now(),
+            },
+        );
+    }
+}
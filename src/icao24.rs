@@ -0,0 +1,273 @@
+//! Offline classification of ICAO 24-bit aircraft addresses.
+//!
+//! The ICAO allocates blocks of addresses to states (ICAO Annex 10, Volume III), which in turn
+//! often reserve parts of their blocks for military and other state aircraft. These
+//! reservations are not published by ICAO, so the military ranges here are the ones commonly
+//! used by ADS-B tracking software.
+//! They are a heuristic: a match makes a military operator likely, and a military aircraft
+//! may well use an address outside of them.
+
+/// Address ranges (inclusive) reserved for military or other state aircraft, sorted by start
+pub const MILITARY_RANGES: &[(u32, u32)] = &[
+    (0x01_0070, 0x01_008f), // Egypt
+    (0x0a_4000, 0x0a_4fff), // Algeria
+    (0x33_ff00, 0x33_ffff), // Italy
+    (0x35_0000, 0x37_ffff), // Spain
+    (0x3a_8000, 0x3a_ffff), // France
+    (0x3b_0000, 0x3b_ffff), // France
+    (0x3e_a000, 0x3e_bfff), // Germany
+    (0x3f_4000, 0x3f_bfff), // Germany
+    (0x40_0000, 0x40_003f), // United Kingdom
+    (0x43_c000, 0x43_cfff), // United Kingdom
+    (0x44_4000, 0x44_6fff), // Austria
+    (0x44_f000, 0x44_ffff), // Belgium
+    (0x45_7000, 0x45_7fff), // Bulgaria
+    (0x45_f400, 0x45_f4ff), // Denmark
+    (0x46_8000, 0x46_83ff), // Greece
+    (0x47_3c00, 0x47_3c0f), // Hungary
+    (0x47_8100, 0x47_81ff), // Norway
+    (0x48_0000, 0x48_0fff), // Netherlands
+    (0x48_d800, 0x48_d87f), // Poland
+    (0x49_7c00, 0x49_7cff), // Portugal
+    (0x49_8420, 0x49_842f), // Czech Republic
+    (0x4b_7000, 0x4b_7fff), // Switzerland
+    (0x4b_8200, 0x4b_82ff), // Turkey
+    (0x50_6f00, 0x50_6fff), // Slovenia
+    (0x70_c070, 0x70_c07f), // Oman
+    (0x71_0258, 0x71_028f), // Saudi Arabia
+    (0x71_0380, 0x71_039f), // Saudi Arabia
+    (0x73_8a00, 0x73_8aff), // Israel
+    (0x7c_822e, 0x7c_84ff), // Australia
+    (0x7c_8800, 0x7c_88ff), // Australia
+    (0x7c_9000, 0x7c_bfff), // Australia
+    (0x7d_0000, 0x7f_ffff), // Australia
+    (0x80_0200, 0x80_02ff), // India
+    (0xad_f7c8, 0xaf_ffff), // United States
+    (0xc2_0000, 0xc3_ffff), // Canada
+    (0xe4_0000, 0xe4_1fff), // Brazil
+    (0xe8_0600, 0xe8_06ff), // Chile
+];
+
+/// Address blocks (inclusive) allocated to states by ICAO, with the ISO 3166-1 alpha-2 code of
+/// the state of registry, sorted by start
+///
+pub const COUNTRY_BLOCKS: &[(u32, u32, &str)] = &[
+    (0x00_4000, 0x00_43ff, "ZW"), // Zimbabwe
+    (0x00_6000, 0x00_6fff, "MZ"), // Mozambique
+    (0x00_8000, 0x00_ffff, "ZA"), // South Africa
+    (0x01_0000, 0x01_7fff, "EG"), // Egypt
+    (0x01_8000, 0x01_ffff, "LY"), // Libya
+    (0x02_0000, 0x02_7fff, "MA"), // Morocco
+    (0x02_8000, 0x02_ffff, "TN"), // Tunisia
+    (0x03_0000, 0x03_03ff, "BW"), // Botswana
+    (0x03_2000, 0x03_2fff, "BI"), // Burundi
+    (0x03_4000, 0x03_4fff, "CM"), // Cameroon
+    (0x03_5000, 0x03_53ff, "KM"), // Comoros
+    (0x03_6000, 0x03_6fff, "CG"), // Congo
+    (0x03_8000, 0x03_8fff, "CI"), // Côte d'Ivoire
+    (0x03_e000, 0x03_efff, "GA"), // Gabon
+    (0x04_0000, 0x04_0fff, "ET"), // Ethiopia
+    (0x04_2000, 0x04_2fff, "GQ"), // Equatorial Guinea
+    (0x04_4000, 0x04_4fff, "GH"), // Ghana
+    (0x04_6000, 0x04_6fff, "GN"), // Guinea
+    (0x04_8000, 0x04_83ff, "GW"), // Guinea-Bissau
+    (0x04_a000, 0x04_a3ff, "LS"), // Lesotho
+    (0x04_c000, 0x04_cfff, "KE"), // Kenya
+    (0x05_0000, 0x05_0fff, "LR"), // Liberia
+    (0x05_4000, 0x05_4fff, "MG"), // Madagascar
+    (0x05_8000, 0x05_8fff, "MW"), // Malawi
+    (0x05_a000, 0x05_a3ff, "MV"), // Maldives
+    (0x05_c000, 0x05_cfff, "ML"), // Mali
+    (0x05_e000, 0x05_e3ff, "MR"), // Mauritania
+    (0x06_0000, 0x06_03ff, "MU"), // Mauritius
+    (0x06_2000, 0x06_2fff, "NE"), // Niger
+    (0x06_4000, 0x06_4fff, "NG"), // Nigeria
+    (0x06_8000, 0x06_8fff, "UG"), // Uganda
+    (0x06_a000, 0x06_a3ff, "QA"), // Qatar
+    (0x06_c000, 0x06_cfff, "CF"), // Central African Republic
+    (0x06_e000, 0x06_efff, "RW"), // Rwanda
+    (0x07_0000, 0x07_0fff, "SN"), // Senegal
+    (0x07_4000, 0x07_43ff, "SC"), // Seychelles
+    (0x07_6000, 0x07_63ff, "SL"), // Sierra Leone
+    (0x07_8000, 0x07_8fff, "SO"), // Somalia
+    (0x07_a000, 0x07_a3ff, "SZ"), // Eswatini
+    (0x07_c000, 0x07_cfff, "SD"), // Sudan
+    (0x08_0000, 0x08_0fff, "TZ"), // Tanzania
+    (0x08_4000, 0x08_4fff, "TD"), // Chad
+    (0x08_8000, 0x08_8fff, "TG"), // Togo
+    (0x08_a000, 0x08_afff, "ZM"), // Zambia
+    (0x08_c000, 0x08_cfff, "CD"), // Democratic Republic of the Congo
+    (0x09_0000, 0x09_0fff, "AO"), // Angola
+    (0x09_4000, 0x09_43ff, "BJ"), // Benin
+    (0x09_6000, 0x09_63ff, "CV"), // Cabo Verde
+    (0x09_8000, 0x09_83ff, "DJ"), // Djibouti
+    (0x09_a000, 0x09_afff, "GM"), // Gambia
+    (0x09_c000, 0x09_cfff, "BF"), // Burkina Faso
+    (0x09_e000, 0x09_e3ff, "ST"), // Sao Tome and Principe
+    (0x0a_0000, 0x0a_7fff, "DZ"), // Algeria
+    (0x0a_8000, 0x0a_8fff, "BS"), // Bahamas
+    (0x0a_a000, 0x0a_a3ff, "BB"), // Barbados
+    (0x0a_b000, 0x0a_b3ff, "BZ"), // Belize
+    (0x0a_c000, 0x0a_cfff, "CO"), // Colombia
+    (0x0a_e000, 0x0a_efff, "CR"), // Costa Rica
+    (0x0b_0000, 0x0b_0fff, "CU"), // Cuba
+    (0x0b_2000, 0x0b_2fff, "SV"), // El Salvador
+    (0x0b_4000, 0x0b_4fff, "GT"), // Guatemala
+    (0x0b_6000, 0x0b_6fff, "GY"), // Guyana
+    (0x0b_8000, 0x0b_8fff, "HT"), // Haiti
+    (0x0b_a000, 0x0b_afff, "HN"), // Honduras
+    (0x0b_c000, 0x0b_c3ff, "VC"), // Saint Vincent and the Grenadines
+    (0x0b_e000, 0x0b_efff, "JM"), // Jamaica
+    (0x0c_0000, 0x0c_0fff, "NI"), // Nicaragua
+    (0x0c_2000, 0x0c_2fff, "PA"), // Panama
+    (0x0c_4000, 0x0c_4fff, "DO"), // Dominican Republic
+    (0x0c_6000, 0x0c_6fff, "TT"), // Trinidad and Tobago
+    (0x0c_8000, 0x0c_8fff, "SR"), // Suriname
+    (0x0c_a000, 0x0c_a3ff, "AG"), // Antigua and Barbuda
+    (0x0c_c000, 0x0c_c3ff, "GD"), // Grenada
+    (0x0d_0000, 0x0d_7fff, "MX"), // Mexico
+    (0x0d_8000, 0x0d_ffff, "VE"), // Venezuela
+    (0x10_0000, 0x1f_ffff, "RU"), // Russia
+    (0x20_1000, 0x20_13ff, "NA"), // Namibia
+    (0x20_2000, 0x20_23ff, "ER"), // Eritrea
+    (0x30_0000, 0x33_ffff, "IT"), // Italy
+    (0x34_0000, 0x37_ffff, "ES"), // Spain
+    (0x38_0000, 0x3b_ffff, "FR"), // France
+    (0x3c_0000, 0x3f_ffff, "DE"), // Germany
+    (0x40_0000, 0x43_ffff, "GB"), // United Kingdom
+    (0x44_0000, 0x44_7fff, "AT"), // Austria
+    (0x44_8000, 0x44_ffff, "BE"), // Belgium
+    (0x45_0000, 0x45_7fff, "BG"), // Bulgaria
+    (0x45_8000, 0x45_ffff, "DK"), // Denmark
+    (0x46_0000, 0x46_7fff, "FI"), // Finland
+    (0x46_8000, 0x46_ffff, "GR"), // Greece
+    (0x47_0000, 0x47_7fff, "HU"), // Hungary
+    (0x47_8000, 0x47_ffff, "NO"), // Norway
+    (0x48_0000, 0x48_7fff, "NL"), // Netherlands
+    (0x48_8000, 0x48_ffff, "PL"), // Poland
+    (0x49_0000, 0x49_7fff, "PT"), // Portugal
+    (0x49_8000, 0x49_ffff, "CZ"), // Czech Republic
+    (0x4a_0000, 0x4a_7fff, "RO"), // Romania
+    (0x4a_8000, 0x4a_ffff, "SE"), // Sweden
+    (0x4b_0000, 0x4b_7fff, "CH"), // Switzerland
+    (0x4b_8000, 0x4b_ffff, "TR"), // Turkey
+    (0x4c_0000, 0x4c_7fff, "RS"), // Serbia
+    (0x4c_8000, 0x4c_83ff, "CY"), // Cyprus
+    (0x4c_a000, 0x4c_afff, "IE"), // Ireland
+    (0x4c_c000, 0x4c_cfff, "IS"), // Iceland
+    (0x4d_0000, 0x4d_03ff, "LU"), // Luxembourg
+    (0x4d_2000, 0x4d_23ff, "MT"), // Malta
+    (0x4d_4000, 0x4d_43ff, "MC"), // Monaco
+    (0x50_0000, 0x50_03ff, "SM"), // San Marino
+    (0x50_1000, 0x50_13ff, "AL"), // Albania
+    (0x50_1c00, 0x50_1fff, "HR"), // Croatia
+    (0x50_2c00, 0x50_2fff, "LV"), // Latvia
+    (0x50_3c00, 0x50_3fff, "LT"), // Lithuania
+    (0x50_4c00, 0x50_4fff, "MD"), // Moldova
+    (0x50_5c00, 0x50_5fff, "SK"), // Slovakia
+    (0x50_6c00, 0x50_6fff, "SI"), // Slovenia
+    (0x50_7c00, 0x50_7fff, "UZ"), // Uzbekistan
+    (0x50_8000, 0x50_ffff, "UA"), // Ukraine
+    (0x51_0000, 0x51_03ff, "BY"), // Belarus
+    (0x51_1000, 0x51_13ff, "EE"), // Estonia
+    (0x51_2000, 0x51_23ff, "MK"), // North Macedonia
+    (0x51_3000, 0x51_33ff, "BA"), // Bosnia and Herzegovina
+    (0x51_4000, 0x51_43ff, "GE"), // Georgia
+    (0x51_5000, 0x51_53ff, "TJ"), // Tajikistan
+    (0x51_6000, 0x51_63ff, "ME"), // Montenegro
+    (0x60_0000, 0x60_03ff, "AM"), // Armenia
+    (0x60_0800, 0x60_0bff, "AZ"), // Azerbaijan
+    (0x60_1000, 0x60_13ff, "KG"), // Kyrgyzstan
+    (0x60_1800, 0x60_1bff, "TM"), // Turkmenistan
+    (0x68_0000, 0x68_03ff, "BT"), // Bhutan
+    (0x68_1000, 0x68_13ff, "FM"), // Micronesia
+    (0x68_2000, 0x68_23ff, "MN"), // Mongolia
+    (0x68_3000, 0x68_33ff, "KZ"), // Kazakhstan
+    (0x68_4000, 0x68_43ff, "PW"), // Palau
+    (0x70_0000, 0x70_0fff, "AF"), // Afghanistan
+    (0x70_2000, 0x70_2fff, "BD"), // Bangladesh
+    (0x70_4000, 0x70_4fff, "MM"), // Myanmar
+    (0x70_6000, 0x70_6fff, "KW"), // Kuwait
+    (0x70_8000, 0x70_8fff, "LA"), // Laos
+    (0x70_a000, 0x70_afff, "NP"), // Nepal
+    (0x70_c000, 0x70_c3ff, "OM"), // Oman
+    (0x70_e000, 0x70_efff, "KH"), // Cambodia
+    (0x71_0000, 0x71_7fff, "SA"), // Saudi Arabia
+    (0x71_8000, 0x71_ffff, "KR"), // South Korea
+    (0x72_0000, 0x72_7fff, "KP"), // North Korea
+    (0x72_8000, 0x72_ffff, "IQ"), // Iraq
+    (0x73_0000, 0x73_7fff, "IR"), // Iran
+    (0x73_8000, 0x73_ffff, "IL"), // Israel
+    (0x74_0000, 0x74_7fff, "JO"), // Jordan
+    (0x74_8000, 0x74_ffff, "LB"), // Lebanon
+    (0x75_0000, 0x75_7fff, "MY"), // Malaysia
+    (0x75_8000, 0x75_ffff, "PH"), // Philippines
+    (0x76_0000, 0x76_7fff, "PK"), // Pakistan
+    (0x76_8000, 0x76_ffff, "SG"), // Singapore
+    (0x77_0000, 0x77_7fff, "LK"), // Sri Lanka
+    (0x77_8000, 0x77_ffff, "SY"), // Syria
+    (0x78_0000, 0x7b_ffff, "CN"), // China
+    (0x7c_0000, 0x7f_ffff, "AU"), // Australia
+    (0x80_0000, 0x83_ffff, "IN"), // India
+    (0x84_0000, 0x87_ffff, "JP"), // Japan
+    (0x88_0000, 0x88_7fff, "TH"), // Thailand
+    (0x88_8000, 0x88_ffff, "VN"), // Viet Nam
+    (0x89_0000, 0x89_0fff, "YE"), // Yemen
+    (0x89_4000, 0x89_4fff, "BH"), // Bahrain
+    (0x89_5000, 0x89_53ff, "BN"), // Brunei
+    (0x89_6000, 0x89_6fff, "AE"), // United Arab Emirates
+    (0x89_7000, 0x89_73ff, "SB"), // Solomon Islands
+    (0x89_8000, 0x89_8fff, "PG"), // Papua New Guinea
+    (0x89_9000, 0x89_93ff, "TW"), // Taiwan
+    (0x8a_0000, 0x8a_7fff, "ID"), // Indonesia
+    (0x90_0000, 0x90_03ff, "MH"), // Marshall Islands
+    (0x90_1000, 0x90_13ff, "CK"), // Cook Islands
+    (0x90_2000, 0x90_23ff, "WS"), // Samoa
+    (0xa0_0000, 0xaf_ffff, "US"), // United States
+    (0xc0_0000, 0xc3_ffff, "CA"), // Canada
+    (0xc8_0000, 0xc8_7fff, "NZ"), // New Zealand
+    (0xc8_8000, 0xc8_8fff, "FJ"), // Fiji
+    (0xc8_a000, 0xc8_a3ff, "NR"), // Nauru
+    (0xc8_c000, 0xc8_c3ff, "LC"), // Saint Lucia
+    (0xc8_d000, 0xc8_d3ff, "TO"), // Tonga
+    (0xc8_e000, 0xc8_e3ff, "KI"), // Kiribati
+    (0xc9_0000, 0xc9_03ff, "VU"), // Vanuatu
+    (0xe0_0000, 0xe3_ffff, "AR"), // Argentina
+    (0xe4_0000, 0xe7_ffff, "BR"), // Brazil
+    (0xe8_0000, 0xe8_0fff, "CL"), // Chile
+    (0xe8_4000, 0xe8_4fff, "EC"), // Ecuador
+    (0xe8_8000, 0xe8_8fff, "PY"), // Paraguay
+    (0xe8_c000, 0xe8_cfff, "PE"), // Peru
+    (0xe9_0000, 0xe9_0fff, "UY"), // Uruguay
+    (0xe9_4000, 0xe9_4fff, "BO"), // Bolivia
+];
+
+/// Parses an ICAO24 address given as hex string, e.g. "3c6444", ignoring surrounding
+/// whitespace and case
+///
+pub fn parse(icao24: &str) -> Option<u32> {
+    let icao24 = icao24.trim();
+
+    if icao24.is_empty() || icao24.len() > 6 {
+        return None;
+    }
+
+    u32::from_str_radix(icao24, 16).ok()
+}
+
+/// Returns true if the address lies in one of the MILITARY_RANGES
+pub fn is_likely_military(address: u32) -> bool {
+    let index = MILITARY_RANGES.partition_point(|&(start, _)| start <= address);
+
+    index > 0 && address <= MILITARY_RANGES[index - 1].1
+}
+
+/// Returns the ISO 3166-1 alpha-2 code of the state the address block is allocated to, e.g.
+/// "DE" for 0x3c6444, or None if the address is not in an allocated block
+///
+pub fn country_code(address: u32) -> Option<&'static str> {
+    let index = COUNTRY_BLOCKS.partition_point(|&(start, _, _)| start <= address);
+    let (_, end, code) = COUNTRY_BLOCKS[index.checked_sub(1)?];
+
+    (address <= end).then_some(code)
+}
@@ -0,0 +1,113 @@
+//! Blending between two snapshots, so that map frontends can animate aircraft smoothly between
+//! polls which are usually 5-10 seconds apart.
+
+use std::collections::HashMap;
+
+use crate::states::{StateVector, States};
+
+/// Linear interpolation between two values, or the nearer one if either is unknown
+fn lerp(a: Option<f32>, b: Option<f32>, t: f64) -> Option<f32> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some((a as f64 + (b as f64 - a as f64) * t) as f32),
+        _ if t < 0.5 => a,
+        _ => b,
+    }
+}
+
+/// Returns the shortest signed difference from angle a to angle b in degrees, in the range
+/// [-180, 180)
+///
+fn angle_difference(a: f64, b: f64) -> f64 {
+    (b - a + 180.0).rem_euclid(360.0) - 180.0
+}
+
+impl StateVector {
+    /// Returns the state of the aircraft a fraction t (between 0 and 1) of the way from this
+    /// state vector to a later one of the same aircraft.
+    ///
+    /// The position, altitudes, speeds, and times are blended linearly, with the position and
+    /// the true track taking the shorter way around. Values which are unknown in either state
+    /// vector, as well as all other fields, are taken from the one nearer to t.
+    ///
+    pub fn interpolate(&self, other: &StateVector, t: f64) -> StateVector {
+        let t = t.clamp(0.0, 1.0);
+        let mut state = if t < 0.5 { self.clone() } else { other.clone() };
+
+        let blend = |a: u64, b: u64| (a as f64 + (b as f64 - a as f64) * t).round() as u64;
+        state.last_contact = blend(self.last_contact, other.last_contact);
+        state.time_position = match (self.time_position, other.time_position) {
+            (Some(a), Some(b)) => Some(blend(a, b)),
+            _ => state.time_position,
+        };
+
+        if let (Some(lat_a), Some(lon_a), Some(lat_b), Some(lon_b)) = (
+            self.latitude,
+            self.longitude,
+            other.latitude,
+            other.longitude,
+        ) {
+            let longitude = lon_a + angle_difference(lon_a, lon_b) * t;
+
+            state.latitude = Some(lat_a + (lat_b - lat_a) * t);
+            state.longitude = Some((longitude + 180.0).rem_euclid(360.0) - 180.0);
+        }
+
+        state.baro_altitude = lerp(self.baro_altitude, other.baro_altitude, t);
+        state.geo_altitude = lerp(self.geo_altitude, other.geo_altitude, t);
+        state.velocity = lerp(self.velocity, other.velocity, t);
+        state.vertical_rate = lerp(self.vertical_rate, other.vertical_rate, t);
+
+        if let (Some(a), Some(b)) = (self.true_track, other.true_track) {
+            let (a, b) = (a as f64, b as f64);
+            state.true_track = Some((a + angle_difference(a, b) * t).rem_euclid(360.0) as f32);
+        }
+
+        state
+    }
+}
+
+impl States {
+    /// Returns the snapshot a fraction t (between 0 and 1) of the way from this snapshot to a
+    /// later one, interpolating every aircraft which is in both using
+    /// StateVector::interpolate(). Aircraft which are in only one of the snapshots are taken
+    /// unchanged from it while it is the nearer one, so removed aircraft disappear and added
+    /// aircraft appear halfway.
+    ///
+    pub fn interpolate_with(&self, next: &States, t: f64) -> States {
+        let t = t.clamp(0.0, 1.0);
+        let next_by_icao24: HashMap<&str, &StateVector> = next
+            .states
+            .iter()
+            .map(|state| (state.icao24.as_str(), state))
+            .collect();
+        let current_by_icao24: HashMap<&str, &StateVector> = self
+            .states
+            .iter()
+            .map(|state| (state.icao24.as_str(), state))
+            .collect();
+
+        let mut states: Vec<StateVector> = self
+            .states
+            .iter()
+            .filter_map(|state| match next_by_icao24.get(state.icao24.as_str()) {
+                Some(next) => Some(state.interpolate(next, t)),
+                None if t < 0.5 => Some(state.clone()),
+                None => None,
+            })
+            .collect();
+
+        if t >= 0.5 {
+            states.extend(
+                next.states
+                    .iter()
+                    .filter(|state| !current_by_icao24.contains_key(state.icao24.as_str()))
+                    .cloned(),
+            );
+        }
+
+        States {
+            time: (self.time as f64 + (next.time as f64 - self.time as f64) * t).round() as u64,
+            states,
+        }
+    }
+}
@@ -1,41 +1,518 @@
-use std::sync::Arc;
-
+#[cfg(feature = "aircraft-db")]
+pub mod aircraft_db;
+pub mod airport;
+pub mod airspace;
+pub mod analysis;
+pub mod auth;
+#[cfg(feature = "blocking")]
+pub mod blocking;
 pub mod bounding_box;
+#[cfg(feature = "analytics")]
+pub mod clustering;
+pub mod congestion;
+pub mod context;
+pub mod credentials;
+#[cfg(feature = "csv")]
+pub mod csv;
+pub mod dedup;
+pub mod diff;
+pub mod downsample;
+pub mod endpoints;
 pub mod errors;
+pub mod filter;
 pub mod flights;
+#[cfg(feature = "geo")]
+pub mod geo;
+#[cfg(feature = "geojson")]
+pub mod geojson;
+pub mod history;
+pub mod http_cache;
+pub mod icao24;
+pub mod interpolate;
+pub mod metadata;
+pub mod metrics;
+pub mod middleware;
+pub mod mvt;
+#[cfg(feature = "parquet")]
+pub mod parquet;
+pub mod policy;
+pub mod poll;
+#[cfg(feature = "pseudonymize")]
+pub mod pseudonymize;
+pub mod quality;
+#[cfg(feature = "sqlite")]
+pub mod recorder;
+pub mod response;
+#[cfg(feature = "sim")]
+pub mod sim;
+pub mod squawk;
 pub mod states;
+pub mod states_cache;
+mod time;
+#[cfg(feature = "timescale")]
+pub mod timescale;
+pub mod tracks;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod transport;
+pub mod units;
+pub mod validation;
+pub mod vrs;
+
+use std::{ops::Range, time::Duration};
 
-use flights::FlightsRequestBuilder;
-use states::StateRequestBuilder;
+use airport::AirportIcao;
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use congestion::CongestionRequest;
+use context::RequestContext;
+use endpoints::Endpoints;
+use flights::{
+    AirportFlightsRequest, AirportMovement, Flight, FlightsRequestBuilder, RouteFlightsRequest,
+};
+use metadata::{EnrichRequest, MetadataBatchRequest, MetadataCache, MetadataRequest};
+use states::{OwnStatesRequestBuilder, StateRequestBuilder};
+use tracks::{TrackBatchRequest, TrackRequest};
 
 pub struct OpenSkyApi {
-    login: Option<Arc<(String, String)>>,
+    context: RequestContext,
+    metadata_cache: MetadataCache,
 }
 
 impl OpenSkyApi {
     /// Creates a new anonymous OpenSkyApi instance
     pub fn new() -> Self {
-        Self { login: None }
+        Self {
+            context: RequestContext::new(None),
+            metadata_cache: MetadataCache::new(),
+        }
     }
 
     /// Creates a new OpenSkyApi instance with the provided username and password
     pub fn with_login(username: String, password: String) -> Self {
         Self {
-            login: Some(Arc::new((username, password))),
+            context: RequestContext::new(Some((username, password))),
+            metadata_cache: MetadataCache::new(),
+        }
+    }
+
+    /// Creates a new OpenSkyApi instance which spreads its requests over the accounts of the
+    /// pool, e.g. to use the credit budgets of several accounts
+    ///
+    pub fn with_credential_pool(pool: credentials::CredentialPool) -> Self {
+        Self {
+            context: RequestContext::with_pool(pool),
+            metadata_cache: MetadataCache::new(),
         }
     }
 
+    /// Creates a new OpenSkyApi instance which authenticates using the OAuth2 client
+    /// credentials flow. A bearer token is obtained from OpenSky's authentication server and
+    /// attached to every request. The token is shared by all requests created from this
+    /// instance, and refreshed when it is about to expire.
+    ///
+    pub fn with_oauth2(client_id: String, client_secret: String) -> Self {
+        Self::with_oauth2_credentials(auth::OAuth2Credentials::new(client_id, client_secret))
+    }
+
+    /// Creates a new OpenSkyApi instance which authenticates using the given OAuth2
+    /// credentials, e.g. ones with a custom token URL
+    ///
+    pub fn with_oauth2_credentials(credentials: auth::OAuth2Credentials) -> Self {
+        Self {
+            context: RequestContext::with_oauth2(credentials),
+            metadata_cache: MetadataCache::new(),
+        }
+    }
+
+    /// Discards the cached OAuth2 access token and obtains a new one right away. Tokens are
+    /// cached and refreshed automatically shortly before they expire, so this is only needed
+    /// if a token was revoked. Does nothing if this instance does not use OAuth2.
+    ///
+    pub async fn force_refresh(&self) -> Result<(), errors::Error> {
+        self.context.force_refresh().await
+    }
+
+    /// Overrides the paths of the API endpoints used by all requests created from this
+    /// OpenSkyApi instance
+    ///
+    pub fn with_endpoints(mut self, endpoints: Endpoints) -> Self {
+        self.context.set_endpoints(endpoints);
+
+        self
+    }
+
+    /// Sends all requests created from this OpenSkyApi instance to the given base URL instead
+    /// of https://opensky-network.org, e.g. a mirror, a gateway, or a local mock server. The
+    /// endpoint paths are appended to the base URL.
+    ///
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.context.set_base_url(base_url);
+
+        self
+    }
+
+    /// Identifies all requests created from this OpenSkyApi instance after this call with the
+    /// given User-Agent, e.g. "my-flight-tracker/1.2", instead of "opensky_api.rs/<version>".
+    /// Fails if the User-Agent contains characters which are not allowed in headers.
+    ///
+    pub fn with_user_agent(mut self, user_agent: &str) -> Result<Self, errors::Error> {
+        let user_agent = reqwest::header::HeaderValue::from_str(user_agent)
+            .map_err(|_| errors::Error::InvalidHeader("User-Agent"))?;
+
+        self.context.set_user_agent(user_agent);
+
+        Ok(self)
+    }
+
+    /// Sends all requests created from this OpenSkyApi instance, including OAuth2 token
+    /// requests, using the given client. Every OpenSkyApi instance keeps a single client, whose
+    /// connection pool is reused by all of its requests, so this is only needed to share the
+    /// pool with other code or to configure the client beyond what this crate offers.
+    ///
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.context.set_client(client);
+
+        self
+    }
+
+    /// Routes all requests created from this OpenSkyApi instance, including OAuth2 token
+    /// requests, through the given HTTP(S) proxy, e.g. "http://proxy.example.com:8080".
+    /// Credentials are only sent to the proxy if they are provided.
+    ///
+    /// Without a proxy configured here, the HTTP_PROXY and HTTPS_PROXY environment variables
+    /// are used.
+    ///
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_proxy(
+        mut self,
+        proxy_url: &str,
+        credentials: Option<(&str, &str)>,
+    ) -> Result<Self, errors::Error> {
+        let mut proxy = reqwest::Proxy::all(proxy_url)?;
+
+        if let Some((username, password)) = credentials {
+            proxy = proxy.basic_auth(username, password);
+        }
+
+        self.context
+            .set_client(context::client_builder().proxy(proxy).build()?);
+
+        Ok(self)
+    }
+
+    /// Sends requests again without credentials if the credentials are rejected (HTTP 401 or
+    /// 403), e.g. after the password was changed, instead of failing. Anonymous requests only
+    /// get data at a coarser time resolution, so every downgrade is logged as a warning,
+    /// counted in RequestContext::anonymous_fallbacks(), and reported to metrics observers.
+    ///
+    pub fn with_anonymous_fallback(mut self) -> Self {
+        self.context.set_anonymous_fallback(true);
+
+        self
+    }
+
+    /// Sends conditional requests (If-None-Match and If-Modified-Since) for URLs which were
+    /// requested before, and reuses the previous response if the server answers that it has
+    /// not been modified. Applies to all requests created from this OpenSkyApi instance after
+    /// this call. At most http_cache::DEFAULT_HTTP_CACHE_ENTRIES responses are kept.
+    ///
+    pub fn with_http_cache(mut self) -> Self {
+        self.context.set_http_cache(http_cache::HttpCache::new());
+
+        self
+    }
+
+    /// Sends conditional requests like with_http_cache(), keeping at most max_entries responses
+    pub fn with_http_cache_entries(mut self, max_entries: usize) -> Self {
+        self.context
+            .set_http_cache(http_cache::HttpCache::with_max_entries(max_entries));
+
+        self
+    }
+
+    /// Answers state vector requests which are identical to one sent less than ttl ago from
+    /// memory instead of sending them again. OpenSky only updates state vectors every 10
+    /// seconds (see states_cache::DEFAULT_STATES_TTL), so shorter polling intervals mostly
+    /// return the same data. Applies to all requests created from this OpenSkyApi instance
+    /// after this call.
+    ///
+    pub fn with_states_cache(mut self, ttl: Duration) -> Self {
+        self.context
+            .set_states_cache(states_cache::StatesCache::new(ttl));
+
+        self
+    }
+
+    /// Sends all requests created from this OpenSkyApi instance after this call using the
+    /// given transport instead of HTTP, e.g. a transport::CannedTransport in tests
+    ///
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_transport<T: transport::Transport + 'static>(mut self, transport: T) -> Self {
+        self.context.set_transport(transport);
+
+        self
+    }
+
+    /// Reports measurements of every request created from this OpenSkyApi instance after this
+    /// call to the given observer, e.g. a metrics::MetricsCounter
+    ///
+    pub fn with_metrics<O: metrics::MetricsObserver + 'static>(mut self, observer: O) -> Self {
+        self.context.add_observer(observer);
+
+        self
+    }
+
+    /// Passes every request created from this OpenSkyApi instance after this call, and its
+    /// response, through the given middleware. Middleware is called in the order it was added.
+    ///
+    pub fn with_middleware<M: middleware::Middleware + 'static>(mut self, middleware: M) -> Self {
+        self.context.add_middleware(middleware);
+
+        self
+    }
+
+    /// Limits the number of HTTP requests in flight at the same time, across all requests,
+    /// streams, and batch helpers created from this OpenSkyApi instance after this call.
+    /// Requests beyond the limit wait until another request has finished.
+    ///
+    pub fn with_max_concurrent_requests(mut self, max: usize) -> Self {
+        self.context.set_max_concurrent_requests(max);
+
+        self
+    }
+
+    /// Sets the retry and timeout policy of a class of endpoints, for all requests created
+    /// from this OpenSkyApi instance after this call
+    ///
+    pub fn with_policy(
+        mut self,
+        class: policy::EndpointClass,
+        policy: policy::RequestPolicy,
+    ) -> Self {
+        self.context.set_policy(class, policy);
+
+        self
+    }
+
+    /// Turns this instance into a blocking client, whose requests have a synchronous send().
+    /// The blocking client must not be used from within an async runtime.
+    ///
+    #[cfg(feature = "blocking")]
+    pub fn blocking(self) -> blocking::OpenSkyApi {
+        blocking::OpenSkyApi::new(self)
+    }
+
+    /// Returns the context shared by the requests created from this OpenSkyApi instance
+    pub fn context(&self) -> &RequestContext {
+        &self.context
+    }
+
     /// Creates a new StateRequestBuilder which can be used to create StateRequests
     pub fn get_states(&self) -> StateRequestBuilder {
-        StateRequestBuilder::new(self.login.clone())
+        StateRequestBuilder::new(self.context.clone())
+    }
+
+    /// Creates a new StateRequestBuilder for the most recent state vectors. This is the same as
+    /// get_states() without at_time(), which makes the intent explicit.
+    ///
+    pub fn get_states_now(&self) -> StateRequestBuilder {
+        self.get_states()
+    }
+
+    /// Creates a new OwnStatesRequestBuilder for the state vectors received by your own
+    /// sensors. This requires a login which owns the sensors.
+    ///
+    pub fn get_own_states(&self) -> OwnStatesRequestBuilder {
+        OwnStatesRequestBuilder::new(self.context.clone())
     }
 
     /// Creates a new FlightsRequestBuilder using the given time interval. The beginning
     /// and ending times are numbers that represent times in seconds since the Unix Epoch.
     ///
-    /// The interval must not span greater than 2 hours, otherwise the request will fail.
+    /// The interval must not span greater than 2 hours, or 30 days for the flights of a single
+    /// aircraft (see FlightsRequestBuilder::by_aircraft()), otherwise the request will fail.
     ///
     pub fn get_flights(&self, begin: u64, end: u64) -> FlightsRequestBuilder {
-        FlightsRequestBuilder::new(self.login.clone(), begin, end)
+        FlightsRequestBuilder::new(self.context.clone(), begin, end)
+    }
+
+    /// Creates a new FlightsRequestBuilder for the flights within the given time range, like
+    /// get_flights(). The range excludes its end, so a flight first seen exactly at range.end
+    /// is not included.
+    ///
+    pub fn get_flights_in(&self, range: Range<DateTime<Utc>>) -> FlightsRequestBuilder {
+        self.get_flights(time::seconds(range.start), time::inclusive_end(range.end))
+    }
+
+    /// Creates a new AirportFlightsRequest for the flights arriving at the airport with the
+    /// given ICAO code within the time interval. The beginning and ending times
+    /// are in seconds since the Unix Epoch.
+    ///
+    pub fn get_arrivals(
+        &self,
+        airport: AirportIcao,
+        begin: u64,
+        end: u64,
+    ) -> AirportFlightsRequest {
+        self.airport_flights(AirportMovement::Arrival, airport, begin, end)
+    }
+
+    /// Creates a new AirportFlightsRequest for the flights departing from the airport with the
+    /// given ICAO code within the time interval. The beginning and ending times
+    /// are in seconds since the Unix Epoch.
+    ///
+    pub fn get_departures(
+        &self,
+        airport: AirportIcao,
+        begin: u64,
+        end: u64,
+    ) -> AirportFlightsRequest {
+        self.airport_flights(AirportMovement::Departure, airport, begin, end)
+    }
+
+    /// Creates a new RouteFlightsRequest for the flights from the departure to the arrival
+    /// airport which departed within the time interval. The beginning and ending times are in
+    /// seconds since the Unix Epoch.
+    ///
+    pub fn get_route_flights(
+        &self,
+        departure: AirportIcao,
+        arrival: AirportIcao,
+        begin: u64,
+        end: u64,
+    ) -> RouteFlightsRequest {
+        RouteFlightsRequest::new(self.context.clone(), departure, arrival, begin, end)
+    }
+
+    /// Creates a new AirportFlightsRequest for the flights arriving at the airport on the given
+    /// calendar day in the airport's time zone, e.g. chrono_tz::America::New_York for KJFK
+    ///
+    pub fn get_arrivals_on<Tz: TimeZone>(
+        &self,
+        airport: AirportIcao,
+        date: NaiveDate,
+        tz: &Tz,
+    ) -> AirportFlightsRequest {
+        let (begin, end) = flights::local_days(date, date, tz);
+
+        self.get_arrivals(airport, begin, end)
+    }
+
+    /// Creates a new AirportFlightsRequest for the flights departing from the airport on the
+    /// given calendar day in the airport's time zone, e.g. chrono_tz::America::New_York for KJFK
+    ///
+    pub fn get_departures_on<Tz: TimeZone>(
+        &self,
+        airport: AirportIcao,
+        date: NaiveDate,
+        tz: &Tz,
+    ) -> AirportFlightsRequest {
+        let (begin, end) = flights::local_days(date, date, tz);
+
+        self.get_departures(airport, begin, end)
+    }
+
+    /// Creates a new CongestionRequest, which computes hourly movement statistics from the
+    /// arrivals and departures of the airport within the time interval
+    ///
+    pub fn get_congestion(&self, airport: AirportIcao, begin: u64, end: u64) -> CongestionRequest {
+        CongestionRequest::new(
+            self.get_arrivals(airport, begin, end),
+            self.get_departures(airport, begin, end),
+        )
+    }
+
+    /// Creates a new CongestionRequest covering the calendar days from first to last
+    /// (inclusive) in the airport's time zone, so that the hours of the report are local hours
+    ///
+    pub fn get_congestion_on_days<Tz: TimeZone>(
+        &self,
+        airport: AirportIcao,
+        first: NaiveDate,
+        last: NaiveDate,
+        tz: &Tz,
+    ) -> CongestionRequest {
+        let (begin, end) = flights::local_days(first, last, tz);
+
+        self.get_congestion(airport, begin, end)
+    }
+
+    fn airport_flights(
+        &self,
+        movement: AirportMovement,
+        airport: AirportIcao,
+        begin: u64,
+        end: u64,
+    ) -> AirportFlightsRequest {
+        AirportFlightsRequest::new(self.context.clone(), movement, airport, begin, end)
+    }
+
+    /// Creates a new MetadataRequest which looks up the aircraft with the given ICAO24 address
+    /// in OpenSky's aircraft database. Results are cached in this OpenSkyApi instance.
+    ///
+    pub fn get_aircraft_metadata(&self, icao24: String) -> MetadataRequest {
+        MetadataRequest::new(self.context.clone(), self.metadata_cache.clone(), icao24)
+    }
+
+    /// Creates a new MetadataBatchRequest which looks up all of the given aircraft, using the
+    /// same cache as get_aircraft_metadata()
+    ///
+    pub fn get_aircraft_metadata_many<I>(&self, icao24s: I) -> MetadataBatchRequest
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        MetadataBatchRequest::new(
+            self.context.clone(),
+            self.metadata_cache.clone(),
+            icao24s.into_iter().map(Into::into).collect(),
+        )
+    }
+
+    /// Creates a new TrackRequest for the track of the aircraft with the ICAO24 address at the
+    /// given time in seconds since the Unix Epoch
+    ///
+    pub fn get_track(&self, icao24: String, time: u64) -> TrackRequest {
+        TrackRequest::new(self.context.clone(), icao24, time)
+    }
+
+    /// Creates a new TrackRequest for the track of a flight, e.g. one returned by
+    /// get_flights(), using its ICAO24 address and time window
+    ///
+    pub fn get_track_for_flight(&self, flight: &Flight) -> TrackRequest {
+        TrackRequest::for_flight(self.context.clone(), flight)
+    }
+
+    /// Creates a new TrackBatchRequest for the tracks of many flights
+    pub fn get_tracks_for_flights(&self, flights: &[Flight]) -> TrackBatchRequest {
+        TrackBatchRequest::new(self.context.clone(), flights)
+    }
+
+    /// Creates a new EnrichRequest which joins flights, state vectors, or tracks with the
+    /// metadata of their aircraft (registration, model, operator, ...), using the same cache as
+    /// get_aircraft_metadata()
+    ///
+    pub fn enrich<T: metadata::Aircraft>(&self, records: Vec<T>) -> EnrichRequest<T> {
+        EnrichRequest::new(self.context.clone(), self.metadata_cache.clone(), records)
+    }
+
+    /// Uses the offline aircraft database for aircraft metadata lookups, only falling back to
+    /// the API for aircraft missing from the database
+    ///
+    #[cfg(feature = "aircraft-db")]
+    pub fn with_aircraft_database(mut self, database: aircraft_db::AircraftDatabase) -> Self {
+        self.metadata_cache = self.metadata_cache.with_database(database);
+
+        self
+    }
+
+    /// Returns the cache used for aircraft metadata lookups
+    pub fn metadata_cache(&self) -> &MetadataCache {
+        &self.metadata_cache
+    }
+}
+
+impl Default for OpenSkyApi {
+    fn default() -> Self {
+        Self::new()
     }
 }
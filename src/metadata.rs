@@ -0,0 +1,334 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use futures_util::{stream, StreamExt};
+use serde::Deserialize;
+
+#[cfg(feature = "aircraft-db")]
+use crate::aircraft_db::AircraftDatabase;
+use crate::{
+    context::RequestContext, errors::Error, flights::Flight, policy::EndpointClass,
+    states::StateVector, tracks::FlightTrack,
+};
+
+/// The default number of metadata requests a MetadataBatchRequest sends at the same time
+pub const DEFAULT_METADATA_CONCURRENCY: usize = 8;
+
+/// Information about an airframe from OpenSky's aircraft database. Most fields are empty for
+/// aircraft that are not (or not fully) registered in the database.
+///
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+pub struct AircraftMetadata {
+    pub icao24: String,
+    #[serde(default)]
+    pub registration: Option<String>,
+    #[serde(default, rename(deserialize = "manufacturerName"))]
+    pub manufacturer_name: Option<String>,
+    #[serde(default, rename(deserialize = "manufacturerIcao"))]
+    pub manufacturer_icao: Option<String>,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub typecode: Option<String>,
+    #[serde(default, rename(deserialize = "serialNumber"))]
+    pub serial_number: Option<String>,
+    #[serde(default, rename(deserialize = "icaoAircraftClass"))]
+    pub icao_aircraft_class: Option<String>,
+    #[serde(default)]
+    pub operator: Option<String>,
+    #[serde(default, rename(deserialize = "operatorCallsign"))]
+    pub operator_callsign: Option<String>,
+    #[serde(default, rename(deserialize = "operatorIcao"))]
+    pub operator_icao: Option<String>,
+    #[serde(default, rename(deserialize = "operatorIata"))]
+    pub operator_iata: Option<String>,
+    #[serde(default)]
+    pub owner: Option<String>,
+    #[serde(default)]
+    pub country: Option<String>,
+}
+
+/// A cache of aircraft metadata shared by all requests created from the same OpenSkyApi
+/// instance. Aircraft that are unknown to OpenSky are cached as well, so they are not looked
+/// up again.
+///
+#[derive(Debug, Clone, Default)]
+pub struct MetadataCache {
+    entries: Arc<Mutex<HashMap<String, Option<AircraftMetadata>>>>,
+    #[cfg(feature = "aircraft-db")]
+    database: Option<AircraftDatabase>,
+}
+
+impl MetadataCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Falls back to the offline aircraft database for aircraft which are not cached, so that
+    /// only aircraft missing from the database are looked up using the API.
+    ///
+    #[cfg(feature = "aircraft-db")]
+    pub fn with_database(mut self, database: AircraftDatabase) -> Self {
+        self.database = Some(database);
+
+        self
+    }
+
+    /// Returns the cached lookup result for the aircraft, which is Some(None) if the aircraft
+    /// is known to not be in OpenSky's database.
+    ///
+    pub fn get(&self, icao24: &str) -> Option<Option<AircraftMetadata>> {
+        let cached = self
+            .entries
+            .lock()
+            .unwrap()
+            .get(&normalize(icao24))
+            .cloned();
+
+        #[cfg(feature = "aircraft-db")]
+        let cached = cached.or_else(|| {
+            self.database
+                .as_ref()
+                .and_then(|database| database.get(icao24))
+                .map(Some)
+        });
+
+        cached
+    }
+
+    pub fn insert(&self, icao24: &str, metadata: Option<AircraftMetadata>) {
+        self.entries
+            .lock()
+            .unwrap()
+            .insert(normalize(icao24), metadata);
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+}
+
+fn normalize(icao24: &str) -> String {
+    icao24.trim().to_lowercase()
+}
+
+#[derive(Debug, Clone)]
+pub struct MetadataRequest {
+    context: RequestContext,
+    cache: MetadataCache,
+    icao24: String,
+}
+
+impl MetadataRequest {
+    pub fn new(context: RequestContext, cache: MetadataCache, icao24: String) -> Self {
+        Self {
+            context,
+            cache,
+            icao24: normalize(&icao24),
+        }
+    }
+
+    /// Renders the API request of this lookup as an equivalent curl command, for debugging
+    /// and bug reports. The password is masked.
+    ///
+    pub fn to_curl(&self) -> String {
+        self.context.curl(&self.url(), false)
+    }
+
+    /// Renders the API request of this lookup as an equivalent curl command, including the
+    /// password
+    ///
+    pub fn to_curl_with_credentials(&self) -> String {
+        self.context.curl(&self.url(), true)
+    }
+
+    fn url(&self) -> String {
+        self.context
+            .url(&self.context.endpoints().metadata_aircraft, &self.icao24)
+    }
+
+    /// Looks up the aircraft, first in the cache and then using the API. Returns None if the
+    /// aircraft is not in OpenSky's database.
+    ///
+    pub async fn send(&self) -> Result<Option<AircraftMetadata>, Error> {
+        if let Some(cached) = self.cache.get(&self.icao24) {
+            return Ok(cached);
+        }
+
+        let (status, bytes) = self
+            .context
+            .get(EndpointClass::Metadata, &self.url())
+            .await?;
+
+        let metadata = match status {
+            reqwest::StatusCode::OK => Some(self.context.observe_parse(
+                EndpointClass::Metadata,
+                serde_json::from_slice(&bytes).map_err(Error::from),
+            )?),
+            reqwest::StatusCode::NOT_FOUND => None,
+            status => return Err(Error::Http(status)),
+        };
+
+        self.cache.insert(&self.icao24, metadata.clone());
+
+        Ok(metadata)
+    }
+}
+
+/// Looks up the metadata of many aircraft at once. Duplicate addresses are only looked up
+/// once, cached aircraft are not looked up at all, and the remaining lookups are sent with a
+/// bounded number of requests in flight.
+///
+#[derive(Debug, Clone)]
+pub struct MetadataBatchRequest {
+    context: RequestContext,
+    cache: MetadataCache,
+    icao24s: Vec<String>,
+    concurrency: usize,
+}
+
+impl MetadataBatchRequest {
+    pub fn new(context: RequestContext, cache: MetadataCache, icao24s: Vec<String>) -> Self {
+        let mut icao24s: Vec<String> = icao24s.iter().map(|icao24| normalize(icao24)).collect();
+        icao24s.sort();
+        icao24s.dedup();
+
+        Self {
+            context,
+            cache,
+            icao24s,
+            concurrency: DEFAULT_METADATA_CONCURRENCY,
+        }
+    }
+
+    /// Sets the maximum number of metadata requests that are sent at the same time
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+
+        self
+    }
+
+    /// Looks up all aircraft and returns their metadata keyed by the (lowercase) ICAO24
+    /// address. Aircraft that are not in OpenSky's database are not included in the map.
+    ///
+    /// If any lookup fails, the first error is returned. Lookups that succeeded before are
+    /// still cached.
+    ///
+    pub async fn send(&self) -> Result<HashMap<String, AircraftMetadata>, Error> {
+        let results: Vec<(String, Result<Option<AircraftMetadata>, Error>)> =
+            stream::iter(self.icao24s.iter().cloned())
+                .map(|icao24| async move {
+                    let result = MetadataRequest::new(
+                        self.context.clone(),
+                        self.cache.clone(),
+                        icao24.clone(),
+                    )
+                    .send()
+                    .await;
+
+                    (icao24, result)
+                })
+                .buffer_unordered(self.concurrency)
+                .collect()
+                .await;
+
+        let mut metadata = HashMap::new();
+
+        for (icao24, result) in results {
+            if let Some(aircraft) = result? {
+                metadata.insert(icao24, aircraft);
+            }
+        }
+
+        Ok(metadata)
+    }
+}
+
+/// A record which belongs to a single aircraft, identified by its ICAO24 address
+pub trait Aircraft {
+    fn icao24(&self) -> &str;
+}
+
+impl Aircraft for Flight {
+    fn icao24(&self) -> &str {
+        &self.icao24
+    }
+}
+
+impl Aircraft for StateVector {
+    fn icao24(&self) -> &str {
+        &self.icao24
+    }
+}
+
+impl Aircraft for FlightTrack {
+    fn icao24(&self) -> &str {
+        &self.icao24
+    }
+}
+
+/// A flight, state vector, or other record together with the metadata of its aircraft, which
+/// is None if the aircraft is not in OpenSky's database
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct WithMetadata<T> {
+    pub record: T,
+    pub metadata: Option<AircraftMetadata>,
+}
+
+/// Looks up the metadata of the aircraft of many records, and returns every record together
+/// with it. The lookups are sent using a MetadataBatchRequest, so every aircraft is looked up
+/// only once and cached aircraft are not looked up at all.
+///
+#[derive(Debug, Clone)]
+pub struct EnrichRequest<T> {
+    batch: MetadataBatchRequest,
+    records: Vec<T>,
+}
+
+impl<T: Aircraft> EnrichRequest<T> {
+    pub fn new(context: RequestContext, cache: MetadataCache, records: Vec<T>) -> Self {
+        let icao24s = records
+            .iter()
+            .map(|record| record.icao24().to_string())
+            .collect();
+
+        Self {
+            batch: MetadataBatchRequest::new(context, cache, icao24s),
+            records,
+        }
+    }
+
+    /// Sets the maximum number of metadata requests that are sent at the same time
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.batch = self.batch.with_concurrency(concurrency);
+
+        self
+    }
+
+    /// Looks up all aircraft and returns the records in their original order, each with the
+    /// metadata of its aircraft. If any lookup fails, the first error is returned.
+    ///
+    pub async fn send(self) -> Result<Vec<WithMetadata<T>>, Error> {
+        let metadata = self.batch.send().await?;
+
+        Ok(self
+            .records
+            .into_iter()
+            .map(|record| WithMetadata {
+                metadata: metadata.get(&normalize(record.icao24())).cloned(),
+                record,
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,201 @@
+//! Observability hooks for the requests sent to the API.
+//!
+//! A MetricsObserver attached to an OpenSkyApi instance is told about every HTTP response,
+//! every request that failed without a response, and every response that could not be parsed,
+//! along with the class of the endpoint. MetricsCounter is a simple observer which keeps
+//! totals in memory, e.g. for a status page.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use crate::{errors::Error, policy::EndpointClass};
+
+/// Receives measurements of the requests sent to the API. All methods do nothing by default,
+/// so implementations only need to override the ones they use.
+///
+pub trait MetricsObserver: Send + Sync {
+    /// Called for every HTTP response, including responses to attempts which are retried.
+    /// size is the length of the response body in bytes, and latency includes the time spent
+    /// waiting for the concurrency limit.
+    ///
+    fn on_response(
+        &self,
+        _class: EndpointClass,
+        _status: reqwest::StatusCode,
+        _size: usize,
+        _latency: Duration,
+    ) {
+    }
+
+    /// Called for every attempt which failed without a response, e.g. after a timeout
+    fn on_request_error(&self, _class: EndpointClass, _error: &Error, _latency: Duration) {}
+
+    /// Called when a successful response could not be parsed
+    fn on_parse_failure(&self, _class: EndpointClass, _error: &Error) {}
+
+    /// Called when the credentials were rejected with the given status, and the request is
+    /// sent again anonymously
+    ///
+    fn on_anonymous_fallback(&self, _class: EndpointClass, _status: reqwest::StatusCode) {}
+}
+
+/// The MetricsObservers of an OpenSkyApi instance, which are called in the order they were
+/// added
+///
+#[derive(Clone, Default)]
+pub struct Observers {
+    observers: Vec<Arc<dyn MetricsObserver>>,
+}
+
+impl fmt::Debug for Observers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Observers")
+            .field("observers", &self.observers.len())
+            .finish()
+    }
+}
+
+impl Observers {
+    pub(crate) fn push<O: MetricsObserver + 'static>(&mut self, observer: O) {
+        self.observers.push(Arc::new(observer));
+    }
+
+    pub fn len(&self) -> usize {
+        self.observers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.observers.is_empty()
+    }
+
+    pub(crate) fn record_attempt(
+        &self,
+        class: EndpointClass,
+        result: &Result<(reqwest::StatusCode, Option<Duration>, Vec<u8>), Error>,
+        latency: Duration,
+    ) {
+        for observer in &self.observers {
+            match result {
+                Ok((status, _, bytes)) => {
+                    observer.on_response(class, *status, bytes.len(), latency)
+                }
+                Err(error) => observer.on_request_error(class, error, latency),
+            }
+        }
+    }
+
+    pub(crate) fn record_anonymous_fallback(
+        &self,
+        class: EndpointClass,
+        status: reqwest::StatusCode,
+    ) {
+        for observer in &self.observers {
+            observer.on_anonymous_fallback(class, status);
+        }
+    }
+
+    /// Reports the error if parsing failed, and passes the result on
+    pub(crate) fn record_parse<T>(
+        &self,
+        class: EndpointClass,
+        result: Result<T, Error>,
+    ) -> Result<T, Error> {
+        if let Err(error @ Error::InvalidJson(_)) = &result {
+            for observer in &self.observers {
+                observer.on_parse_failure(class, error);
+            }
+        }
+
+        result
+    }
+}
+
+/// Totals of the requests sent to one class of endpoints
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EndpointMetrics {
+    /// The number of HTTP responses
+    pub responses: u64,
+    /// The number of attempts which failed without a response
+    pub request_errors: u64,
+    /// The number of responses which could not be parsed
+    pub parse_failures: u64,
+    /// The total size of all response bodies in bytes
+    pub bytes: u64,
+    /// The total latency of all attempts, with and without a response
+    pub total_latency: Duration,
+    /// The number of responses by HTTP status code
+    pub statuses: BTreeMap<u16, u64>,
+}
+
+impl EndpointMetrics {
+    /// Returns the average latency of all attempts, or None if nothing was sent
+    pub fn average_latency(&self) -> Option<Duration> {
+        let attempts = self.responses + self.request_errors;
+
+        (attempts > 0).then(|| self.total_latency / attempts as u32)
+    }
+}
+
+/// A MetricsObserver which adds up the measurements per class of endpoints. Clones share the
+/// same totals, so a clone can be attached to an OpenSkyApi instance and the original read.
+///
+#[derive(Debug, Clone, Default)]
+pub struct MetricsCounter {
+    totals: Arc<Mutex<HashMap<EndpointClass, EndpointMetrics>>>,
+}
+
+impl MetricsCounter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the totals of the class of endpoints
+    pub fn get(&self, class: EndpointClass) -> EndpointMetrics {
+        self.totals
+            .lock()
+            .unwrap()
+            .get(&class)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn reset(&self) {
+        self.totals.lock().unwrap().clear();
+    }
+
+    fn update(&self, class: EndpointClass, f: impl FnOnce(&mut EndpointMetrics)) {
+        f(self.totals.lock().unwrap().entry(class).or_default());
+    }
+}
+
+impl MetricsObserver for MetricsCounter {
+    fn on_response(
+        &self,
+        class: EndpointClass,
+        status: reqwest::StatusCode,
+        size: usize,
+        latency: Duration,
+    ) {
+        self.update(class, |metrics| {
+            metrics.responses += 1;
+            metrics.bytes += size as u64;
+            metrics.total_latency += latency;
+            *metrics.statuses.entry(status.as_u16()).or_default() += 1;
+        });
+    }
+
+    fn on_request_error(&self, class: EndpointClass, _error: &Error, latency: Duration) {
+        self.update(class, |metrics| {
+            metrics.request_errors += 1;
+            metrics.total_latency += latency;
+        });
+    }
+
+    fn on_parse_failure(&self, class: EndpointClass, _error: &Error) {
+        self.update(class, |metrics| metrics.parse_failures += 1);
+    }
+}
@@ -0,0 +1,85 @@
+//! Hooks which see every API request and response.
+//!
+//! Middleware attached to an OpenSkyApi instance is called for the requests of all endpoints,
+//! so it is a uniform place to add custom headers, write audit logs, or collect metrics. The
+//! requests which obtain OAuth2 access tokens are not passed through the middleware.
+
+use std::{fmt, sync::Arc};
+
+/// Inspects or modifies outgoing requests and incoming responses. Both methods do nothing by
+/// default, so implementations only need to override the ones they use.
+///
+pub trait Middleware: Send + Sync {
+    /// Called before a request is sent, after the authentication headers have been added
+    fn on_request(&self, _request: &mut reqwest::Request) {}
+
+    /// Called after the response headers have been received, before the body is read. This is
+    /// called for every attempt, including attempts which are retried afterwards.
+    ///
+    fn on_response(&self, _response: &mut reqwest::Response) {}
+}
+
+/// Adds a header to every request
+#[derive(Debug, Clone)]
+pub struct DefaultHeader {
+    name: reqwest::header::HeaderName,
+    value: reqwest::header::HeaderValue,
+}
+
+impl DefaultHeader {
+    pub fn new(name: reqwest::header::HeaderName, value: reqwest::header::HeaderValue) -> Self {
+        Self { name, value }
+    }
+}
+
+impl Middleware for DefaultHeader {
+    fn on_request(&self, request: &mut reqwest::Request) {
+        request
+            .headers_mut()
+            .insert(self.name.clone(), self.value.clone());
+    }
+}
+
+/// A list of Middleware which is called in the order it was added
+#[derive(Clone, Default)]
+pub struct MiddlewareStack {
+    layers: Vec<Arc<dyn Middleware>>,
+}
+
+impl fmt::Debug for MiddlewareStack {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MiddlewareStack")
+            .field("layers", &self.layers.len())
+            .finish()
+    }
+}
+
+impl MiddlewareStack {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push<M: Middleware + 'static>(&mut self, middleware: M) {
+        self.layers.push(Arc::new(middleware));
+    }
+
+    pub fn len(&self) -> usize {
+        self.layers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.layers.is_empty()
+    }
+
+    pub(crate) fn on_request(&self, request: &mut reqwest::Request) {
+        for layer in &self.layers {
+            layer.on_request(request);
+        }
+    }
+
+    pub(crate) fn on_response(&self, response: &mut reqwest::Response) {
+        for layer in &self.layers {
+            layer.on_response(response);
+        }
+    }
+}
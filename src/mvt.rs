@@ -0,0 +1,307 @@
+//! Renders States snapshots into Mapbox Vector Tiles, so that a tile server can serve live
+//! traffic as a map layer without sending every client the full snapshot.
+//!
+//! Each tile contains a single layer named "aircraft" with one point feature per aircraft.
+//! The features carry the icao24, callsign, altitude, velocity, heading (true_track), and
+//! on_ground attributes. Tiles are addressed using the usual Web Mercator z/x/y scheme.
+
+use std::{collections::HashMap, f64::consts::PI};
+
+use crate::states::{StateVector, States};
+
+/// The name of the layer containing the aircraft
+pub const LAYER_NAME: &str = "aircraft";
+
+/// The default number of units along each side of a tile
+pub const DEFAULT_EXTENT: u32 = 4096;
+
+/// The highest supported zoom level, at which tile coordinates still fit into a u32. Higher
+/// zoom levels are clamped to it.
+///
+pub const MAX_ZOOM: u8 = 32;
+
+/// The Web Mercator projection is only defined up to this latitude
+const MAX_LATITUDE: f64 = 85.051_128_779_806_59;
+
+/// The address of a single Web Mercator tile
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TileId {
+    pub z: u8,
+    pub x: u32,
+    pub y: u32,
+}
+
+impl TileId {
+    /// Creates the address of a tile. Zoom levels above MAX_ZOOM are clamped to it.
+    pub fn new(z: u8, x: u32, y: u32) -> Self {
+        Self {
+            z: z.min(MAX_ZOOM),
+            x,
+            y,
+        }
+    }
+
+    /// Returns the tile at the given zoom level which contains the coordinate. Zoom levels
+    /// above MAX_ZOOM are clamped to it.
+    ///
+    pub fn containing(z: u8, latitude: f64, longitude: f64) -> Self {
+        let z = z.min(MAX_ZOOM);
+        let (x, y) = project(z, latitude, longitude);
+        let max = (1u64 << z) as f64 - 1.0;
+
+        Self {
+            z,
+            x: x.floor().clamp(0.0, max) as u32,
+            y: y.floor().clamp(0.0, max) as u32,
+        }
+    }
+}
+
+/// Projects a coordinate to fractional tile coordinates at the given zoom level
+fn project(z: u8, latitude: f64, longitude: f64) -> (f64, f64) {
+    let n = (1u64 << z.min(MAX_ZOOM)) as f64;
+    let lat = latitude.clamp(-MAX_LATITUDE, MAX_LATITUDE).to_radians();
+
+    let x = (longitude + 180.0) / 360.0 * n;
+    let y = (1.0 - (lat.tan() + 1.0 / lat.cos()).ln() / PI) / 2.0 * n;
+
+    (x, y)
+}
+
+impl States {
+    /// Encodes the aircraft inside the given tile as a Mapbox Vector Tile using the default
+    /// extent of 4096. Aircraft without a position are skipped. The returned bytes are not
+    /// compressed.
+    ///
+    pub fn to_mvt(&self, tile: TileId) -> Vec<u8> {
+        self.to_mvt_with_extent(tile, DEFAULT_EXTENT)
+    }
+
+    /// Like to_mvt(), but with a custom tile extent
+    pub fn to_mvt_with_extent(&self, tile: TileId, extent: u32) -> Vec<u8> {
+        let mut layer = LayerBuilder::new(extent);
+
+        for state in &self.states {
+            let (Some(latitude), Some(longitude)) = (state.latitude, state.longitude) else {
+                continue;
+            };
+
+            let (x, y) = project(tile.z, latitude, longitude);
+            let x = ((x - tile.x as f64) * extent as f64).floor();
+            let y = ((y - tile.y as f64) * extent as f64).floor();
+
+            if x < 0.0 || y < 0.0 || x >= extent as f64 || y >= extent as f64 {
+                continue;
+            }
+
+            layer.add_point(state, x as i32, y as i32);
+        }
+
+        let mut tile = Vec::new();
+
+        if !layer.features.is_empty() {
+            write_bytes(&mut tile, 3, &layer.finish());
+        }
+
+        tile
+    }
+
+    /// Encodes every non-empty tile at the given zoom level. This is useful for pre-rendering
+    /// a snapshot once instead of encoding it again for every tile request. Zoom levels above
+    /// MAX_ZOOM are clamped to it.
+    ///
+    pub fn to_mvt_tiles(&self, z: u8) -> HashMap<TileId, Vec<u8>> {
+        let mut tiles: HashMap<TileId, States> = HashMap::new();
+
+        for state in &self.states {
+            let (Some(latitude), Some(longitude)) = (state.latitude, state.longitude) else {
+                continue;
+            };
+
+            let id = TileId::containing(z, latitude, longitude);
+
+            tiles
+                .entry(id)
+                .or_insert_with(|| States {
+                    time: self.time,
+                    states: Vec::new(),
+                })
+                .states
+                .push(state.clone());
+        }
+
+        tiles
+            .into_iter()
+            .map(|(id, states)| (id, states.to_mvt(id)))
+            .collect()
+    }
+}
+
+/// A property value, as defined by the vector tile specification
+enum Value {
+    String(String),
+    Double(f64),
+    Bool(bool),
+}
+
+impl Value {
+    fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+
+        match self {
+            Value::String(s) => write_bytes(&mut buf, 1, s.as_bytes()),
+            Value::Double(d) => {
+                write_key(&mut buf, 3, 1);
+                buf.extend_from_slice(&d.to_le_bytes());
+            }
+            Value::Bool(b) => {
+                write_key(&mut buf, 7, 0);
+                write_varint(&mut buf, *b as u64);
+            }
+        }
+
+        buf
+    }
+}
+
+struct LayerBuilder {
+    extent: u32,
+    keys: Vec<&'static str>,
+    /// Encoded values, in the order they are referenced by the feature tags
+    values: Vec<Vec<u8>>,
+    value_indices: HashMap<Vec<u8>, u32>,
+    features: Vec<Vec<u8>>,
+}
+
+impl LayerBuilder {
+    fn new(extent: u32) -> Self {
+        Self {
+            extent,
+            keys: Vec::new(),
+            values: Vec::new(),
+            value_indices: HashMap::new(),
+            features: Vec::new(),
+        }
+    }
+
+    /// Appends the key and value indices of a property to the tags of a feature
+    fn tag(&mut self, tags: &mut Vec<u32>, key: &'static str, value: Value) {
+        let key_index = match self.keys.iter().position(|k| *k == key) {
+            Some(index) => index as u32,
+            None => {
+                self.keys.push(key);
+                self.keys.len() as u32 - 1
+            }
+        };
+
+        let encoded = value.encode();
+        let value_index = match self.value_indices.get(&encoded) {
+            Some(index) => *index,
+            None => {
+                let index = self.values.len() as u32;
+                self.values.push(encoded.clone());
+                self.value_indices.insert(encoded, index);
+                index
+            }
+        };
+
+        tags.push(key_index);
+        tags.push(value_index);
+    }
+
+    fn add_point(&mut self, state: &StateVector, x: i32, y: i32) {
+        let mut tags = Vec::new();
+
+        self.tag(&mut tags, "icao24", Value::String(state.icao24.clone()));
+
+        if let Some(callsign) = state.callsign.as_deref().map(str::trim) {
+            if !callsign.is_empty() {
+                self.tag(&mut tags, "callsign", Value::String(callsign.to_string()));
+            }
+        }
+
+        if let Some(altitude) = state.geo_altitude.or(state.baro_altitude) {
+            self.tag(&mut tags, "altitude", Value::Double(altitude as f64));
+        }
+
+        if let Some(velocity) = state.velocity {
+            self.tag(&mut tags, "velocity", Value::Double(velocity as f64));
+        }
+
+        if let Some(heading) = state.true_track {
+            self.tag(&mut tags, "heading", Value::Double(heading as f64));
+        }
+
+        self.tag(&mut tags, "on_ground", Value::Bool(state.on_ground));
+
+        let mut feature = Vec::new();
+
+        write_packed(&mut feature, 2, &tags);
+        // GeomType POINT
+        write_key(&mut feature, 3, 0);
+        write_varint(&mut feature, 1);
+        // A single MoveTo command followed by the zigzag encoded position
+        write_packed(&mut feature, 4, &[(1 << 3) | 1, zigzag(x), zigzag(y)]);
+
+        self.features.push(feature);
+    }
+
+    fn finish(self) -> Vec<u8> {
+        let mut layer = Vec::new();
+
+        // Version
+        write_key(&mut layer, 15, 0);
+        write_varint(&mut layer, 2);
+        write_bytes(&mut layer, 1, LAYER_NAME.as_bytes());
+
+        for feature in &self.features {
+            write_bytes(&mut layer, 2, feature);
+        }
+
+        for key in &self.keys {
+            write_bytes(&mut layer, 3, key.as_bytes());
+        }
+
+        for value in &self.values {
+            write_bytes(&mut layer, 4, value);
+        }
+
+        write_key(&mut layer, 5, 0);
+        write_varint(&mut layer, self.extent as u64);
+
+        layer
+    }
+}
+
+fn zigzag(n: i32) -> u32 {
+    ((n << 1) ^ (n >> 31)) as u32
+}
+
+fn write_varint(buf: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buf.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+
+    buf.push(value as u8);
+}
+
+fn write_key(buf: &mut Vec<u8>, field: u32, wire_type: u32) {
+    write_varint(buf, ((field << 3) | wire_type) as u64);
+}
+
+fn write_bytes(buf: &mut Vec<u8>, field: u32, bytes: &[u8]) {
+    write_key(buf, field, 2);
+    write_varint(buf, bytes.len() as u64);
+    buf.extend_from_slice(bytes);
+}
+
+fn write_packed(buf: &mut Vec<u8>, field: u32, values: &[u32]) {
+    let mut packed = Vec::new();
+
+    for value in values {
+        write_varint(&mut packed, *value as u64);
+    }
+
+    write_bytes(buf, field, &packed);
+}
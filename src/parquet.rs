@@ -0,0 +1,272 @@
+//! Parquet export of state vectors and flights, for long-term archival of polled data.
+//!
+//! Each call writes a complete Parquet file with a single row group, compressed with Snappy.
+//! The schemas are given by STATES_SCHEMA and FLIGHTS_SCHEMA. Their columns are named after
+//! the fields of StateVector and Flight, times are seconds since the Unix Epoch, altitudes
+//! are in meters, and speeds are in m/s, exactly as returned by the API.
+
+use std::{io::Write, sync::Arc};
+
+use ::parquet::{
+    basic::Compression,
+    data_type::{
+        BoolType, ByteArray, ByteArrayType, DataType, DoubleType, FloatType, Int32Type, Int64Type,
+    },
+    errors::ParquetError,
+    file::{
+        properties::WriterProperties,
+        writer::{SerializedFileWriter, SerializedRowGroupWriter},
+    },
+    schema::parser::parse_message_type,
+};
+
+use crate::{airport::AirportIcao, errors::Error, flights::Flight, states::StateVector};
+
+/// The Parquet schema of files written by write_states()
+pub const STATES_SCHEMA: &str = "
+message state_vector {
+    required binary icao24 (STRING);
+    optional binary callsign (STRING);
+    required binary origin_country (STRING);
+    optional int64 time_position;
+    required int64 last_contact;
+    optional double longitude;
+    optional double latitude;
+    optional float baro_altitude;
+    required boolean on_ground;
+    optional float velocity;
+    optional float true_track;
+    optional float vertical_rate;
+    optional group sensors (LIST) {
+        repeated group list {
+            required int64 element;
+        }
+    }
+    optional float geo_altitude;
+    optional binary squawk (STRING);
+    required boolean spi;
+    required int32 position_source;
+    optional int32 category;
+}
+";
+
+/// The Parquet schema of files written by write_flights()
+pub const FLIGHTS_SCHEMA: &str = "
+message flight {
+    required binary icao24 (STRING);
+    required int64 first_seen;
+    optional binary est_departure_airport (STRING);
+    required int64 last_seen;
+    optional binary est_arrival_airport (STRING);
+    optional binary callsign (STRING);
+    optional int64 est_departure_airport_horiz_distance;
+    optional int64 est_departure_airport_vert_distance;
+    optional int64 est_arrival_airport_horiz_distance;
+    optional int64 est_arrival_airport_vert_distance;
+    required int32 departure_airport_candidates_count;
+    required int32 arrival_airport_candidates_count;
+}
+";
+
+/// Writes the state vectors as a Parquet file following STATES_SCHEMA
+pub fn write_states<W: Write + Send>(writer: W, states: &[StateVector]) -> Result<W, Error> {
+    write_file(writer, STATES_SCHEMA, |columns| {
+        columns.strings(states.iter().map(|state| Some(state.icao24.as_str())), true)?;
+        columns.strings(states.iter().map(|state| state.callsign.as_deref()), false)?;
+        columns.strings(
+            states
+                .iter()
+                .map(|state| Some(state.origin_country.as_str())),
+            true,
+        )?;
+        columns
+            .optional::<Int64Type>(states.iter().map(|state| state.time_position.map(to_i64)))?;
+        columns.required::<Int64Type>(states.iter().map(|state| to_i64(state.last_contact)))?;
+        columns.optional::<DoubleType>(states.iter().map(|state| state.longitude))?;
+        columns.optional::<DoubleType>(states.iter().map(|state| state.latitude))?;
+        columns.optional::<FloatType>(states.iter().map(|state| state.baro_altitude))?;
+        columns.required::<BoolType>(states.iter().map(|state| state.on_ground))?;
+        columns.optional::<FloatType>(states.iter().map(|state| state.velocity))?;
+        columns.optional::<FloatType>(states.iter().map(|state| state.true_track))?;
+        columns.optional::<FloatType>(states.iter().map(|state| state.vertical_rate))?;
+        columns.sensors(states.iter().map(|state| state.sensors.as_deref()))?;
+        columns.optional::<FloatType>(states.iter().map(|state| state.geo_altitude))?;
+        let squawks: Vec<_> = states
+            .iter()
+            .map(|state| state.squawk.map(|squawk| squawk.to_string()))
+            .collect();
+        columns.strings(squawks.iter().map(Option::as_deref), false)?;
+        columns.required::<BoolType>(states.iter().map(|state| state.spi))?;
+        columns
+            .required::<Int32Type>(states.iter().map(|state| i32::from(state.position_source)))?;
+        columns.optional::<Int32Type>(
+            states
+                .iter()
+                .map(|state| state.category.map(|category| category as i32)),
+        )
+    })
+}
+
+/// Writes the flights as a Parquet file following FLIGHTS_SCHEMA
+pub fn write_flights<W: Write + Send>(writer: W, flights: &[Flight]) -> Result<W, Error> {
+    write_file(writer, FLIGHTS_SCHEMA, |columns| {
+        columns.strings(
+            flights.iter().map(|flight| Some(flight.icao24.as_str())),
+            true,
+        )?;
+        columns.required::<Int64Type>(flights.iter().map(|flight| to_i64(flight.first_seen)))?;
+        columns.strings(
+            flights.iter().map(|flight| {
+                flight
+                    .est_departure_airport
+                    .as_ref()
+                    .map(AirportIcao::as_str)
+            }),
+            false,
+        )?;
+        columns.required::<Int64Type>(flights.iter().map(|flight| to_i64(flight.last_seen)))?;
+        columns.strings(
+            flights
+                .iter()
+                .map(|flight| flight.est_arrival_airport.as_ref().map(AirportIcao::as_str)),
+            false,
+        )?;
+        columns.strings(
+            flights.iter().map(|flight| flight.callsign.as_deref()),
+            false,
+        )?;
+        for distance in [
+            |flight: &Flight| flight.est_departure_airport_horiz_distance,
+            |flight: &Flight| flight.est_departure_airport_vert_distance,
+            |flight: &Flight| flight.est_arrival_airport_horiz_distance,
+            |flight: &Flight| flight.est_arrival_airport_vert_distance,
+        ] {
+            columns.optional::<Int64Type>(
+                flights.iter().map(|flight| distance(flight).map(i64::from)),
+            )?;
+        }
+        columns.required::<Int32Type>(
+            flights
+                .iter()
+                .map(|flight| i32::from(flight.departure_airport_candidates_count)),
+        )?;
+        columns.required::<Int32Type>(
+            flights
+                .iter()
+                .map(|flight| i32::from(flight.arrival_airport_candidates_count)),
+        )
+    })
+}
+
+fn to_i64(seconds: u64) -> i64 {
+    i64::try_from(seconds).unwrap_or(i64::MAX)
+}
+
+fn write_file<W: Write + Send>(
+    writer: W,
+    schema: &str,
+    write_columns: impl FnOnce(&mut Columns<'_, '_, W>) -> Result<(), Error>,
+) -> Result<W, Error> {
+    let schema = Arc::new(parse_message_type(schema)?);
+    let properties = Arc::new(
+        WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .build(),
+    );
+
+    let mut file = SerializedFileWriter::new(writer, schema, properties)?;
+    let mut row_group = file.next_row_group()?;
+    write_columns(&mut Columns {
+        row_group: &mut row_group,
+    })?;
+    row_group.close()?;
+
+    Ok(file.into_inner()?)
+}
+
+/// Writes the columns of a row group in schema order
+struct Columns<'a, 'b, W: Write + Send> {
+    row_group: &'a mut SerializedRowGroupWriter<'b, W>,
+}
+
+impl<W: Write + Send> Columns<'_, '_, W> {
+    fn write<T: DataType>(
+        &mut self,
+        values: &[T::T],
+        definition_levels: Option<&[i16]>,
+        repetition_levels: Option<&[i16]>,
+    ) -> Result<(), Error> {
+        let mut column = self.row_group.next_column()?.ok_or_else(|| {
+            ParquetError::General(String::from("more columns written than in the schema"))
+        })?;
+        column
+            .typed::<T>()
+            .write_batch(values, definition_levels, repetition_levels)?;
+        column.close()?;
+
+        Ok(())
+    }
+
+    fn required<T: DataType>(&mut self, values: impl Iterator<Item = T::T>) -> Result<(), Error> {
+        let values: Vec<_> = values.collect();
+
+        self.write::<T>(&values, None, None)
+    }
+
+    fn optional<T: DataType>(
+        &mut self,
+        values: impl Iterator<Item = Option<T::T>>,
+    ) -> Result<(), Error> {
+        let mut definition_levels = Vec::new();
+        let mut present = Vec::new();
+
+        for value in values {
+            definition_levels.push(value.is_some() as i16);
+            present.extend(value);
+        }
+
+        self.write::<T>(&present, Some(&definition_levels), None)
+    }
+
+    fn strings<'s>(
+        &mut self,
+        values: impl Iterator<Item = Option<&'s str>>,
+        required: bool,
+    ) -> Result<(), Error> {
+        let values = values.map(|value| value.map(ByteArray::from));
+
+        if required {
+            self.required::<ByteArrayType>(values.flatten())
+        } else {
+            self.optional::<ByteArrayType>(values)
+        }
+    }
+
+    /// Writes an optional list of sensor serials. The definition level is 0 for a missing
+    /// list, 1 for an empty list, and 2 for an element. The repetition level is 1 for every
+    /// element but the first of a list.
+    ///
+    fn sensors<'s>(&mut self, lists: impl Iterator<Item = Option<&'s [u64]>>) -> Result<(), Error> {
+        let mut definition_levels = Vec::new();
+        let mut repetition_levels = Vec::new();
+        let mut serials = Vec::new();
+
+        for list in lists {
+            match list {
+                None | Some([]) => {
+                    definition_levels.push(list.is_some() as i16);
+                    repetition_levels.push(0);
+                }
+                Some(list) => {
+                    for (index, serial) in list.iter().enumerate() {
+                        definition_levels.push(2);
+                        repetition_levels.push((index > 0) as i16);
+                        serials.push(to_i64(*serial));
+                    }
+                }
+            }
+        }
+
+        self.write::<Int64Type>(&serials, Some(&definition_levels), Some(&repetition_levels))
+    }
+}
@@ -0,0 +1,186 @@
+//! Retry and timeout policies, which can differ between the classes of endpoints. A live
+//! states poll should fail fast so the next poll is not delayed, while a scan over historical
+//! flights can wait longer and retry more often.
+
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    time::Duration,
+};
+
+/// The classes of endpoints that can have their own RequestPolicy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EndpointClass {
+    States,
+    Flights,
+    Tracks,
+    Metadata,
+}
+
+/// How long a request may take, and how often it is retried if it fails. Requests are retried
+/// after timeouts, connection errors, and server errors (HTTP 5xx), which OpenSky returns
+/// frequently but only for a short time.
+///
+/// The delay before a retry grows exponentially: the first retry waits retry_delay, and every
+/// following retry waits backoff_factor times longer than the one before, up to
+/// max_retry_delay. With jitter, every delay is shortened by a random amount of up to half of
+/// it, so that many clients failing at the same time do not retry at the same time.
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RequestPolicy {
+    /// The time after which a single attempt is aborted, None to wait indefinitely
+    pub timeout: Option<Duration>,
+    /// The number of retries after the first attempt
+    pub max_retries: u32,
+    /// The time to wait before the first retry
+    pub retry_delay: Duration,
+    /// The factor the delay grows by with every retry
+    pub backoff_factor: f64,
+    /// The longest time to wait before a retry
+    pub max_retry_delay: Duration,
+    /// Whether delays are shortened by a random amount
+    pub jitter: bool,
+    /// The total time to wait for rate limits (HTTP 429) to pass before returning
+    /// Error::RateLimited, None to return it right away
+    ///
+    pub rate_limit_budget: Option<Duration>,
+}
+
+impl Default for RequestPolicy {
+    /// No timeout, and up to 3 retries after 0.5, 1, and 2 seconds (minus jitter)
+    fn default() -> Self {
+        Self {
+            timeout: None,
+            max_retries: 3,
+            retry_delay: Duration::from_millis(500),
+            backoff_factor: 2.0,
+            max_retry_delay: Duration::from_secs(30),
+            jitter: true,
+            rate_limit_budget: None,
+        }
+    }
+}
+
+impl RequestPolicy {
+    /// Creates a new RequestPolicy with the default retries and no timeout
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Disables retries, so that every failure is returned right away
+    pub fn without_retries(mut self) -> Self {
+        self.max_retries = 0;
+
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+
+        self
+    }
+
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+
+        self
+    }
+
+    /// Sets the time to wait before the first retry
+    pub fn with_retry_delay(mut self, retry_delay: Duration) -> Self {
+        self.retry_delay = retry_delay;
+
+        self
+    }
+
+    /// Sets the factor the delay grows by with every retry, 1.0 for a constant delay
+    pub fn with_backoff_factor(mut self, backoff_factor: f64) -> Self {
+        self.backoff_factor = backoff_factor.max(1.0);
+
+        self
+    }
+
+    /// Sets the longest time to wait before a retry
+    pub fn with_max_retry_delay(mut self, max_retry_delay: Duration) -> Self {
+        self.max_retry_delay = max_retry_delay;
+
+        self
+    }
+
+    /// Enables or disables shortening delays by a random amount
+    pub fn with_jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+
+        self
+    }
+
+    /// Waits for rate limits to pass and retries, as long as the total time waited stays
+    /// within the budget. The wait time is taken from the Retry-After header of the response,
+    /// or from the backoff delay if the server did not send one. Retries after rate limits do
+    /// not count towards max_retries.
+    ///
+    pub fn with_rate_limit_budget(mut self, budget: Duration) -> Self {
+        self.rate_limit_budget = Some(budget);
+
+        self
+    }
+
+    /// Returns the delay before the given retry, starting at 1 for the first retry, without
+    /// jitter
+    ///
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = self
+            .backoff_factor
+            .powi(retry.saturating_sub(1).min(i32::MAX as u32) as i32);
+
+        self.retry_delay
+            .mul_f64(factor.min(u32::MAX as f64))
+            .min(self.max_retry_delay)
+    }
+
+    /// Returns the delay before the given retry, starting at 1 for the first retry
+    pub fn delay(&self, retry: u32) -> Duration {
+        let delay = self.backoff(retry);
+
+        if !self.jitter || delay.is_zero() {
+            return delay;
+        }
+
+        // Every RandomState is seeded randomly, which is good enough for spreading out retries
+        let random = RandomState::new().build_hasher().finish();
+        let half = delay / 2;
+
+        half + Duration::from_nanos(random % (half.as_nanos() as u64 + 1))
+    }
+}
+
+/// The RequestPolicy of every EndpointClass
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Policies {
+    pub states: RequestPolicy,
+    pub flights: RequestPolicy,
+    pub tracks: RequestPolicy,
+    pub metadata: RequestPolicy,
+}
+
+impl Policies {
+    /// Returns the policy of the given class of endpoints
+    pub fn get(&self, class: EndpointClass) -> &RequestPolicy {
+        match class {
+            EndpointClass::States => &self.states,
+            EndpointClass::Flights => &self.flights,
+            EndpointClass::Tracks => &self.tracks,
+            EndpointClass::Metadata => &self.metadata,
+        }
+    }
+
+    /// Sets the policy of the given class of endpoints
+    pub fn set(&mut self, class: EndpointClass, policy: RequestPolicy) {
+        match class {
+            EndpointClass::States => self.states = policy,
+            EndpointClass::Flights => self.flights = policy,
+            EndpointClass::Tracks => self.tracks = policy,
+            EndpointClass::Metadata => self.metadata = policy,
+        }
+    }
+}
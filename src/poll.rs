@@ -0,0 +1,101 @@
+//! Scheduling of repeated requests.
+//!
+//! When many collectors poll the API at the same interval they tend to synchronize, which
+//! produces bursts of requests against the API (and against the user's own rate limit). A
+//! PollSchedule can spread polls out with random jitter, and can align polls to the wall
+//! clock, e.g. at :00, :10, :20 seconds, to match the resolution of OpenSky's data.
+
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    time::Duration,
+};
+
+use crate::time::{SystemTime, UNIX_EPOCH};
+
+/// Determines when the next poll of a polling loop should happen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PollSchedule {
+    interval: Duration,
+    jitter: Duration,
+    aligned: bool,
+    offset: Duration,
+}
+
+impl PollSchedule {
+    /// Creates a new PollSchedule which polls once per interval, without jitter or alignment
+    pub fn every(interval: Duration) -> Self {
+        Self {
+            interval,
+            jitter: Duration::ZERO,
+            aligned: false,
+            offset: Duration::ZERO,
+        }
+    }
+
+    /// Adds a random delay between zero and the given jitter to every poll
+    pub fn with_jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter;
+
+        self
+    }
+
+    /// Aligns polls to wall-clock multiples of the interval since the Unix Epoch. With an
+    /// interval of 10 seconds polls happen at :00, :10, :20 seconds and so on.
+    ///
+    pub fn aligned(mut self) -> Self {
+        self.aligned = true;
+
+        self
+    }
+
+    /// Shifts aligned polls by the given offset, e.g. an offset of 2 seconds with an interval
+    /// of 10 seconds polls at :02, :12, :22 seconds. This implies aligned().
+    ///
+    pub fn with_offset(mut self, offset: Duration) -> Self {
+        self.aligned = true;
+        self.offset = offset;
+
+        self
+    }
+
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Returns how long to wait from the given time until the next poll should happen
+    pub fn delay_from(&self, now: SystemTime) -> Duration {
+        let base = if self.aligned && !self.interval.is_zero() {
+            let since_epoch = now.duration_since(UNIX_EPOCH).unwrap_or_default();
+            let interval = self.interval.as_nanos();
+            let phase =
+                (since_epoch.as_nanos() + interval - self.offset.as_nanos() % interval) % interval;
+
+            if phase == 0 {
+                self.interval
+            } else {
+                Duration::from_nanos((interval - phase) as u64)
+            }
+        } else {
+            self.interval
+        };
+
+        base + self.random_jitter()
+    }
+
+    /// Returns how long to wait from now until the next poll should happen
+    pub fn next_delay(&self) -> Duration {
+        self.delay_from(SystemTime::now())
+    }
+
+    fn random_jitter(&self) -> Duration {
+        if self.jitter.is_zero() {
+            return Duration::ZERO;
+        }
+
+        // Every RandomState is seeded randomly, which is good enough for spreading out polls
+        let random = RandomState::new().build_hasher().finish();
+
+        Duration::from_nanos(random % (self.jitter.as_nanos() as u64 + 1))
+    }
+}
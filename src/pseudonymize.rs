@@ -0,0 +1,140 @@
+//! Pseudonymization of recorded data, for publishing derived datasets without identifying
+//! individual aircraft.
+//!
+//! ICAO24 addresses and callsigns are replaced by a keyed HMAC-SHA256 of their value. The same
+//! key always produces the same pseudonym, so aircraft can still be followed across a dataset,
+//! but without the key the pseudonyms can not be reversed by hashing every possible address.
+//! Use a fresh random key per dataset (or session) unless datasets need to be joined.
+
+use hmac::{Hmac, KeyInit, Mac};
+use sha2::Sha256;
+
+use crate::states::{StateVector, States};
+
+/// The number of hex characters in a pseudonym
+pub const PSEUDONYM_LEN: usize = 16;
+
+/// Selects which fields are removed entirely when pseudonymizing state vectors
+#[derive(Debug, Clone, Copy)]
+pub struct Redactions {
+    /// Removes the transponder code, which can identify a flight
+    pub squawk: bool,
+    /// Removes the serial numbers of the receivers which saw the aircraft
+    pub sensors: bool,
+    /// Replaces the country of registration with an empty string
+    pub origin_country: bool,
+    /// Removes the callsign instead of pseudonymizing it
+    pub callsign: bool,
+}
+
+impl Default for Redactions {
+    fn default() -> Self {
+        Self {
+            squawk: true,
+            sensors: true,
+            origin_country: false,
+            callsign: false,
+        }
+    }
+}
+
+/// Consistently pseudonymizes ICAO24 addresses and callsigns using a secret key
+#[derive(Clone)]
+pub struct Pseudonymizer {
+    mac: Hmac<Sha256>,
+    redactions: Redactions,
+}
+
+impl Pseudonymizer {
+    /// Creates a new Pseudonymizer using the given secret key and the default redactions
+    pub fn new(key: &[u8]) -> Self {
+        Self {
+            mac: <Hmac<Sha256> as KeyInit>::new_from_slice(key)
+                .expect("HMAC accepts keys of any length"),
+            redactions: Redactions::default(),
+        }
+    }
+
+    /// Creates a new Pseudonymizer using a random key, which makes the pseudonyms stable only
+    /// for as long as this Pseudonymizer is used.
+    ///
+    pub fn random() -> Result<Self, getrandom::Error> {
+        let mut key = [0; 32];
+        getrandom::getrandom(&mut key)?;
+
+        Ok(Self::new(&key))
+    }
+
+    /// Sets which fields are removed from pseudonymized state vectors
+    pub fn with_redactions(mut self, redactions: Redactions) -> Self {
+        self.redactions = redactions;
+
+        self
+    }
+
+    fn pseudonym(&self, domain: &[u8], value: &str) -> String {
+        let mut mac = self.mac.clone();
+        mac.update(domain);
+        mac.update(b":");
+        mac.update(value.as_bytes());
+
+        mac.finalize()
+            .into_bytes()
+            .iter()
+            .take(PSEUDONYM_LEN / 2)
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
+    /// Returns the pseudonym of an ICAO24 address. Addresses are compared case-insensitively.
+    pub fn icao24(&self, icao24: &str) -> String {
+        self.pseudonym(b"icao24", &icao24.trim().to_lowercase())
+    }
+
+    /// Returns the pseudonym of a callsign. Surrounding whitespace is ignored, as OpenSky pads
+    /// callsigns to 8 characters.
+    ///
+    pub fn callsign(&self, callsign: &str) -> String {
+        self.pseudonym(b"callsign", &callsign.trim().to_uppercase())
+    }
+
+    /// Returns a copy of the state vector with its identifiers pseudonymized and the
+    /// configured fields redacted
+    ///
+    pub fn state_vector(&self, state: &StateVector) -> StateVector {
+        let mut state = state.clone();
+
+        state.icao24 = self.icao24(&state.icao24);
+        state.callsign = if self.redactions.callsign {
+            None
+        } else {
+            state.callsign.map(|callsign| self.callsign(&callsign))
+        };
+
+        if self.redactions.squawk {
+            state.squawk = None;
+        }
+
+        if self.redactions.sensors {
+            state.sensors = None;
+        }
+
+        if self.redactions.origin_country {
+            state.origin_country = String::new();
+        }
+
+        state
+    }
+
+    /// Pseudonymizes every state vector of the snapshot
+    pub fn states(&self, states: &States) -> States {
+        States {
+            time: states.time,
+            states: states
+                .states
+                .iter()
+                .map(|state| self.state_vector(state))
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,57 @@
+//! Freshness and confidence of state vectors, so that stale or unreliable targets can be
+//! dropped or drawn less prominently.
+
+use crate::states::StateVector;
+
+/// The position age in seconds at which the freshness part of the quality score reaches zero
+pub const STALE_POSITION_AGE: u64 = 60;
+
+impl StateVector {
+    /// Returns the number of seconds between the last position update and now, or None if
+    /// there is no position
+    ///
+    pub fn position_age(&self, now: u64) -> Option<u64> {
+        self.time_position.map(|time| now.saturating_sub(time))
+    }
+
+    /// Returns a score between 0 and 1 of how much the state vector can be trusted at the
+    /// given time, usually the time of its snapshot. The score multiplies three factors:
+    ///
+    /// - Freshness, falling linearly from 1 for a current position to 0 for a position which is
+    ///   STALE_POSITION_AGE seconds old. Without a position the score is 0.
+    /// - The position source: 1 for ADS-B, 0.9 for ASTERIX, 0.8 for MLAT, 0.7 for FLARM, and
+    ///   0.5 for unknown sources.
+    /// - Completeness, from 0.5 to 1 depending on how many of the callsign, barometric
+    ///   altitude, velocity, true track, and vertical rate are known.
+    ///
+    pub fn quality(&self, now: u64) -> f64 {
+        let Some(age) = self.position_age(now) else {
+            return 0.0;
+        };
+        if self.latitude.is_none() || self.longitude.is_none() {
+            return 0.0;
+        }
+
+        let freshness = 1.0 - (age as f64 / STALE_POSITION_AGE as f64).min(1.0);
+
+        let source = match self.position_source {
+            0 => 1.0,
+            1 => 0.9,
+            2 => 0.8,
+            3 => 0.7,
+            _ => 0.5,
+        };
+
+        let known = [
+            self.callsign.is_some(),
+            self.baro_altitude.is_some(),
+            self.velocity.is_some(),
+            self.true_track.is_some(),
+            self.vertical_rate.is_some(),
+        ];
+        let completeness =
+            0.5 + 0.5 * known.iter().filter(|&&known| known).count() as f64 / known.len() as f64;
+
+        freshness * source * completeness
+    }
+}
@@ -0,0 +1,252 @@
+//! Records States snapshots into a SQLite database and queries them back.
+//!
+//! State vectors are stored normalized per aircraft: static information about each aircraft
+//! lives in the `aircraft` table, while every received state vector is a row in `states`
+//! keyed by the snapshot time. The `states` table is indexed on (icao24, time) for history
+//! queries and on (time, latitude, longitude) for spatial queries at a point in time.
+
+use std::path::Path;
+
+use rusqlite::{params, Connection, Row};
+
+use crate::{
+    bounding_box::BoundingBox,
+    errors::Error,
+    states::{StateVector, States},
+};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS snapshots (
+    time INTEGER PRIMARY KEY
+);
+
+CREATE TABLE IF NOT EXISTS aircraft (
+    icao24 TEXT PRIMARY KEY,
+    origin_country TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS states (
+    time INTEGER NOT NULL REFERENCES snapshots (time),
+    icao24 TEXT NOT NULL REFERENCES aircraft (icao24),
+    callsign TEXT,
+    time_position INTEGER,
+    last_contact INTEGER NOT NULL,
+    longitude REAL,
+    latitude REAL,
+    baro_altitude REAL,
+    on_ground INTEGER NOT NULL,
+    velocity REAL,
+    true_track REAL,
+    vertical_rate REAL,
+    sensors TEXT,
+    geo_altitude REAL,
+    squawk TEXT,
+    spi INTEGER NOT NULL,
+    position_source INTEGER NOT NULL,
+    category INTEGER,
+    PRIMARY KEY (time, icao24)
+);
+
+CREATE INDEX IF NOT EXISTS states_icao24_time ON states (icao24, time);
+CREATE INDEX IF NOT EXISTS states_time_position ON states (time, latitude, longitude);
+";
+
+const SELECT_STATES: &str = "
+SELECT s.icao24, s.callsign, a.origin_country, s.time_position, s.last_contact, s.longitude,
+    s.latitude, s.baro_altitude, s.on_ground, s.velocity, s.true_track, s.vertical_rate,
+    s.sensors, s.geo_altitude, s.squawk, s.spi, s.position_source, s.category, s.time
+FROM states s JOIN aircraft a ON a.icao24 = s.icao24
+";
+
+/// A session recorder which stores States snapshots in a SQLite database
+pub struct SqliteRecorder {
+    conn: Connection,
+}
+
+impl SqliteRecorder {
+    /// Opens (or creates) the SQLite database at the given path
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Self::with_connection(Connection::open(path)?)
+    }
+
+    /// Creates a recorder backed by a temporary in-memory database
+    pub fn in_memory() -> Result<Self, Error> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(conn: Connection) -> Result<Self, Error> {
+        conn.execute_batch(SCHEMA)?;
+
+        Ok(Self { conn })
+    }
+
+    /// Stores every state vector of the snapshot. Recording the same snapshot time twice
+    /// replaces the previously recorded state vectors of that time.
+    ///
+    pub fn record(&mut self, states: &States) -> Result<(), Error> {
+        let tx = self.conn.transaction()?;
+
+        tx.execute(
+            "INSERT OR IGNORE INTO snapshots (time) VALUES (?1)",
+            params![states.time as i64],
+        )?;
+        tx.execute(
+            "DELETE FROM states WHERE time = ?1",
+            params![states.time as i64],
+        )?;
+
+        {
+            let mut aircraft = tx.prepare_cached(
+                "INSERT INTO aircraft (icao24, origin_country) VALUES (?1, ?2)
+                ON CONFLICT (icao24) DO UPDATE SET origin_country = excluded.origin_country",
+            )?;
+            let mut state = tx.prepare_cached(
+                "INSERT OR REPLACE INTO states VALUES
+                (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18)",
+            )?;
+
+            for sv in &states.states {
+                aircraft.execute(params![sv.icao24, sv.origin_country])?;
+
+                let sensors = sv.sensors.as_ref().map(serde_json::to_string).transpose()?;
+
+                state.execute(params![
+                    states.time as i64,
+                    sv.icao24,
+                    sv.callsign,
+                    sv.time_position.map(|time| time as i64),
+                    sv.last_contact as i64,
+                    sv.longitude,
+                    sv.latitude,
+                    sv.baro_altitude,
+                    sv.on_ground,
+                    sv.velocity,
+                    sv.true_track,
+                    sv.vertical_rate,
+                    sensors,
+                    sv.geo_altitude,
+                    sv.squawk.map(|squawk| squawk.to_string()),
+                    sv.spi,
+                    sv.position_source,
+                    sv.category,
+                ])?;
+            }
+        }
+
+        tx.commit()?;
+
+        Ok(())
+    }
+
+    /// Returns the recorded snapshot times within the given interval (inclusive) in order
+    pub fn snapshot_times(&self, begin: u64, end: u64) -> Result<Vec<u64>, Error> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT time FROM snapshots WHERE time BETWEEN ?1 AND ?2 ORDER BY time",
+        )?;
+
+        let times = stmt
+            .query_map(params![begin as i64, end as i64], |row| {
+                row.get::<_, i64>(0).map(|time| time as u64)
+            })?
+            .collect::<Result<_, _>>()?;
+
+        Ok(times)
+    }
+
+    /// Returns the recorded state history of one aircraft between the two times (inclusive)
+    /// as pairs of snapshot time and state vector, ordered by time.
+    ///
+    pub fn history(
+        &self,
+        icao24: &str,
+        begin: u64,
+        end: u64,
+    ) -> Result<Vec<(u64, StateVector)>, Error> {
+        let mut stmt = self.conn.prepare_cached(&format!(
+            "{} WHERE s.icao24 = ?1 AND s.time BETWEEN ?2 AND ?3 ORDER BY s.time",
+            SELECT_STATES
+        ))?;
+
+        let history = stmt
+            .query_map(params![icao24, begin as i64, end as i64], read_row)?
+            .collect::<Result<_, _>>()?;
+
+        Ok(history)
+    }
+
+    /// Returns all aircraft inside the bounding box as they were recorded in the latest
+    /// snapshot at or before the given time. Returns None if nothing was recorded before that
+    /// time.
+    ///
+    pub fn in_bbox_at(&self, bbox: BoundingBox, time: u64) -> Result<Option<States>, Error> {
+        let snapshot: Option<i64> = self.conn.query_row(
+            "SELECT MAX(time) FROM snapshots WHERE time <= ?1",
+            params![time as i64],
+            |row| row.get(0),
+        )?;
+
+        let Some(snapshot) = snapshot else {
+            return Ok(None);
+        };
+
+        let mut stmt = self.conn.prepare_cached(&format!(
+            "{} WHERE s.time = ?1 AND s.latitude BETWEEN ?2 AND ?3 AND s.longitude BETWEEN ?4 AND ?5
+            ORDER BY s.icao24",
+            SELECT_STATES
+        ))?;
+
+        let states = stmt
+            .query_map(
+                params![
+                    snapshot,
+                    bbox.lat_min,
+                    bbox.lat_max,
+                    bbox.long_min,
+                    bbox.long_max
+                ],
+                read_row,
+            )?
+            .map(|row| row.map(|(_, state)| state))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Some(States {
+            time: snapshot as u64,
+            states,
+        }))
+    }
+}
+
+fn read_row(row: &Row) -> rusqlite::Result<(u64, StateVector)> {
+    let sensors: Option<String> = row.get(12)?;
+    let sensors = sensors
+        .map(|sensors| serde_json::from_str(&sensors))
+        .transpose()
+        .map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(12, rusqlite::types::Type::Text, Box::new(e))
+        })?;
+
+    let state = StateVector {
+        icao24: row.get(0)?,
+        callsign: row.get(1)?,
+        origin_country: row.get(2)?,
+        time_position: row.get::<_, Option<i64>>(3)?.map(|time| time as u64),
+        last_contact: row.get::<_, i64>(4)? as u64,
+        longitude: row.get(5)?,
+        latitude: row.get(6)?,
+        baro_altitude: row.get(7)?,
+        on_ground: row.get(8)?,
+        velocity: row.get(9)?,
+        true_track: row.get(10)?,
+        vertical_rate: row.get(11)?,
+        sensors,
+        geo_altitude: row.get(13)?,
+        squawk: row
+            .get::<_, Option<String>>(14)?
+            .and_then(|squawk| squawk.parse().ok()),
+        spi: row.get(15)?,
+        position_source: row.get(16)?,
+        category: row.get(17)?,
+    };
+
+    Ok((row.get::<_, i64>(18)? as u64, state))
+}
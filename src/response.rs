@@ -0,0 +1,21 @@
+/// A parsed response together with the original response body, for archiving exact upstream
+/// payloads or debugging parsing discrepancies
+///
+#[derive(Debug, Clone)]
+pub struct WithRaw<T> {
+    pub parsed: T,
+    /// The response body exactly as it was received
+    pub raw: Vec<u8>,
+}
+
+impl<T> WithRaw<T> {
+    /// Returns the response body as a string, replacing invalid UTF-8 sequences
+    pub fn raw_str(&self) -> std::borrow::Cow<'_, str> {
+        String::from_utf8_lossy(&self.raw)
+    }
+
+    /// Discards the response body and returns the parsed response
+    pub fn into_parsed(self) -> T {
+        self.parsed
+    }
+}
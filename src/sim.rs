@@ -0,0 +1,436 @@
+//! Converts live state vectors into UDP traffic packets understood by flight simulators, so
+//! that real-world traffic from OpenSky can be shown inside a FlightGear or X-Plane session.
+//!
+//! OpenSky only updates positions every 5-10 seconds, while simulators expect several updates
+//! per second. Every packet is therefore generated from a position that is dead-reckoned
+//! forwards from the last reported position, using the reported ground speed, track and
+//! vertical rate.
+
+use std::{
+    collections::HashMap,
+    f64::consts::PI,
+    io,
+    net::{SocketAddr, ToSocketAddrs, UdpSocket},
+};
+
+use crate::{
+    bounding_box::EARTH_RADIUS_KM,
+    states::{StateVector, States},
+    units,
+};
+
+/// Positions are never extrapolated further than this many seconds past the last reported
+/// position. Beyond that the guess is worse than simply holding the aircraft in place.
+pub const MAX_EXTRAPOLATION_SECS: f64 = 20.0;
+
+/// X-Plane only accepts VEHX packets for aircraft indices 1 to 19 (0 is the user's aircraft)
+pub const XPLANE_MAX_AIRCRAFT: usize = 19;
+
+// WGS-84 ellipsoid, used for the earth-centered coordinates FlightGear expects
+const WGS84_A: f64 = 6_378_137.0;
+const WGS84_E2: f64 = 6.694_379_990_14e-3;
+
+const FG_MAGIC: u32 = 0x4647_4653;
+const FG_PROTOCOL_VERSION: u32 = 0x0001_0001;
+const FG_POS_DATA_ID: u32 = 7;
+const FG_HEADER_LEN: usize = 32;
+const FG_POS_MSG_LEN: usize = 200;
+
+/// A position that has been dead-reckoned forwards from a StateVector
+#[derive(Debug, Clone, Copy)]
+pub struct SimPosition {
+    pub latitude: f64,
+    pub longitude: f64,
+    /// Altitude in meters, geometric altitude is preferred over barometric altitude
+    pub altitude: f64,
+    /// True track in decimal degrees clockwise from north
+    pub true_track: f64,
+    /// Ground speed in m/s
+    pub velocity: f64,
+    /// Vertical rate in m/s
+    pub vertical_rate: f64,
+    /// Pitch angle in degrees, derived from the flight path angle
+    pub pitch: f64,
+    pub on_ground: bool,
+}
+
+/// Dead-reckons the position of the aircraft at the given time, which is specified in
+/// (possibly fractional) seconds since the Unix Epoch.
+///
+/// Returns None if the state vector has no position. Aircraft on the ground are not moved
+/// vertically, and the extrapolation horizon is limited to MAX_EXTRAPOLATION_SECS.
+///
+pub fn extrapolate(state: &StateVector, time: f64) -> Option<SimPosition> {
+    let latitude = state.latitude?;
+    let longitude = state.longitude?;
+    let altitude = state
+        .geo_altitude
+        .or(state.baro_altitude)
+        .map(|altitude| altitude as f64)
+        .unwrap_or(0.0);
+
+    let true_track = state.true_track.unwrap_or(0.0) as f64;
+    let velocity = state.velocity.unwrap_or(0.0) as f64;
+    let vertical_rate = if state.on_ground {
+        0.0
+    } else {
+        state.vertical_rate.unwrap_or(0.0) as f64
+    };
+
+    let reported_at = state.time_position.unwrap_or(state.last_contact) as f64;
+    let dt = (time - reported_at).clamp(0.0, MAX_EXTRAPOLATION_SECS);
+
+    let (latitude, longitude) = destination(latitude, longitude, true_track, velocity * dt);
+
+    let pitch = if velocity > 1.0 {
+        vertical_rate.atan2(velocity).to_degrees()
+    } else {
+        0.0
+    };
+
+    Some(SimPosition {
+        latitude,
+        longitude,
+        altitude: (altitude + vertical_rate * dt).max(0.0),
+        true_track,
+        velocity,
+        vertical_rate,
+        pitch,
+        on_ground: state.on_ground,
+    })
+}
+
+/// Moves a point the given distance in meters along a great circle with the given bearing
+fn destination(latitude: f64, longitude: f64, bearing: f64, distance: f64) -> (f64, f64) {
+    if distance == 0.0 {
+        return (latitude, longitude);
+    }
+
+    let delta = distance / (EARTH_RADIUS_KM * 1000.0);
+    let theta = bearing.to_radians();
+    let phi1 = latitude.to_radians();
+    let lambda1 = longitude.to_radians();
+
+    let phi2 = (phi1.sin() * delta.cos() + phi1.cos() * delta.sin() * theta.cos()).asin();
+    let lambda2 = lambda1
+        + (theta.sin() * delta.sin() * phi1.cos()).atan2(delta.cos() - phi1.sin() * phi2.sin());
+
+    let longitude = (lambda2.to_degrees() + 540.0) % 360.0 - 180.0;
+
+    (phi2.to_degrees(), longitude)
+}
+
+/// Encodes an X-Plane "VEHX" packet, which places the aircraft with the given index (1 to 19)
+/// at the provided position. The packet should be sent to X-Plane's UDP port (49000 by default).
+///
+pub fn xplane_vehx(index: i32, position: &SimPosition) -> Vec<u8> {
+    let mut packet = Vec::with_capacity(45);
+
+    packet.extend_from_slice(b"VEHX\0");
+    packet.extend_from_slice(&index.to_le_bytes());
+    packet.extend_from_slice(&position.latitude.to_le_bytes());
+    packet.extend_from_slice(&position.longitude.to_le_bytes());
+    packet.extend_from_slice(&position.altitude.to_le_bytes());
+    packet.extend_from_slice(&(position.true_track as f32).to_le_bytes());
+    packet.extend_from_slice(&(position.pitch as f32).to_le_bytes());
+    packet.extend_from_slice(&0f32.to_le_bytes());
+
+    packet
+}
+
+/// Encodes a ForeFlight-style "XTRAFFIC" sentence, which is understood by most X-Plane
+/// traffic plugins and EFB applications. The sim_name identifies the sender.
+///
+/// Returns None if the state vector's ICAO24 address is not valid hexadecimal.
+///
+pub fn xtraffic(sim_name: &str, state: &StateVector, position: &SimPosition) -> Option<String> {
+    let address = u32::from_str_radix(state.icao24.trim(), 16).ok()?;
+    let callsign = state.callsign.as_deref().unwrap_or("").trim();
+
+    Some(format!(
+        "XTRAFFIC{},{},{:.6},{:.6},{:.1},{:.1},{},{:.1},{:.1},{}",
+        sim_name,
+        address,
+        position.latitude,
+        position.longitude,
+        units::meters_to_feet(position.altitude),
+        units::mps_to_fpm(position.vertical_rate),
+        if position.on_ground { 0 } else { 1 },
+        position.true_track,
+        units::mps_to_knots(position.velocity),
+        callsign
+    ))
+}
+
+/// Encodes a FlightGear multiplayer position message. The callsign is truncated to 7
+/// characters and the model to 95, which is the most the protocol allows. The time is the
+/// simulation time of the position in seconds.
+///
+pub fn flightgear_position(
+    callsign: &str,
+    model: &str,
+    position: &SimPosition,
+    time: f64,
+) -> Vec<u8> {
+    let mut packet = Vec::with_capacity(FG_HEADER_LEN + FG_POS_MSG_LEN);
+
+    // Header
+    put_u32(&mut packet, FG_MAGIC);
+    put_u32(&mut packet, FG_PROTOCOL_VERSION);
+    put_u32(&mut packet, FG_POS_DATA_ID);
+    put_u32(&mut packet, (FG_HEADER_LEN + FG_POS_MSG_LEN) as u32);
+    // Requested range in nautical miles, and reply port, neither of which are used by us
+    put_u32(&mut packet, 100);
+    put_u32(&mut packet, 0);
+    put_str(&mut packet, callsign, 8);
+
+    // Position message
+    put_str(&mut packet, model, 96);
+    put_f64(&mut packet, time);
+    // Lag
+    put_f64(&mut packet, 0.1);
+
+    let lat = position.latitude.to_radians();
+    let lon = position.longitude.to_radians();
+
+    for coordinate in geodetic_to_ecef(lat, lon, position.altitude) {
+        put_f64(&mut packet, coordinate);
+    }
+
+    let orientation = Quat::from_lon_lat(lon, lat).mul(&Quat::from_yaw_pitch_roll(
+        position.true_track.to_radians(),
+        position.pitch.to_radians(),
+        0.0,
+    ));
+
+    for component in orientation.angle_axis() {
+        put_f32(&mut packet, component as f32);
+    }
+
+    // Linear velocity in the body frame, x forwards and z down
+    let speed = (position.velocity.powi(2) + position.vertical_rate.powi(2)).sqrt();
+    put_f32(&mut packet, speed as f32);
+    put_f32(&mut packet, 0.0);
+    put_f32(&mut packet, 0.0);
+
+    // Angular velocity, linear acceleration, angular acceleration, and padding
+    for _ in 0..10 {
+        put_u32(&mut packet, 0);
+    }
+
+    packet
+}
+
+fn put_u32(packet: &mut Vec<u8>, value: u32) {
+    packet.extend_from_slice(&value.to_be_bytes());
+}
+
+fn put_f32(packet: &mut Vec<u8>, value: f32) {
+    packet.extend_from_slice(&value.to_be_bytes());
+}
+
+fn put_f64(packet: &mut Vec<u8>, value: f64) {
+    packet.extend_from_slice(&value.to_be_bytes());
+}
+
+/// Writes a fixed size, NUL terminated string
+fn put_str(packet: &mut Vec<u8>, value: &str, len: usize) {
+    let bytes = value.as_bytes();
+    let n = bytes.len().min(len - 1);
+
+    packet.extend_from_slice(&bytes[..n]);
+    packet.resize(packet.len() + len - n, 0);
+}
+
+fn geodetic_to_ecef(lat: f64, lon: f64, altitude: f64) -> [f64; 3] {
+    let n = WGS84_A / (1.0 - WGS84_E2 * lat.sin().powi(2)).sqrt();
+
+    [
+        (n + altitude) * lat.cos() * lon.cos(),
+        (n + altitude) * lat.cos() * lon.sin(),
+        (n * (1.0 - WGS84_E2) + altitude) * lat.sin(),
+    ]
+}
+
+/// The minimal quaternion math needed to express an orientation the way FlightGear does
+struct Quat {
+    w: f64,
+    x: f64,
+    y: f64,
+    z: f64,
+}
+
+impl Quat {
+    /// Rotation from the earth-centered frame to the local horizontal frame
+    fn from_lon_lat(lon: f64, lat: f64) -> Self {
+        let zd2 = 0.5 * lon;
+        let yd2 = -0.25 * PI - 0.5 * lat;
+
+        Self {
+            w: zd2.cos() * yd2.cos(),
+            x: -zd2.sin() * yd2.sin(),
+            y: zd2.cos() * yd2.sin(),
+            z: zd2.sin() * yd2.cos(),
+        }
+    }
+
+    fn from_yaw_pitch_roll(yaw: f64, pitch: f64, roll: f64) -> Self {
+        let (szd2, czd2) = (0.5 * yaw).sin_cos();
+        let (syd2, cyd2) = (0.5 * pitch).sin_cos();
+        let (sxd2, cxd2) = (0.5 * roll).sin_cos();
+
+        Self {
+            w: cxd2 * czd2 * cyd2 + sxd2 * szd2 * syd2,
+            x: sxd2 * czd2 * cyd2 - cxd2 * szd2 * syd2,
+            y: cxd2 * czd2 * syd2 + sxd2 * szd2 * cyd2,
+            z: cxd2 * szd2 * cyd2 - sxd2 * czd2 * syd2,
+        }
+    }
+
+    fn mul(&self, other: &Self) -> Self {
+        Self {
+            w: self.w * other.w - self.x * other.x - self.y * other.y - self.z * other.z,
+            x: self.w * other.x + self.x * other.w + self.y * other.z - self.z * other.y,
+            y: self.w * other.y - self.x * other.z + self.y * other.w + self.z * other.x,
+            z: self.w * other.z + self.x * other.y - self.y * other.x + self.z * other.w,
+        }
+    }
+
+    /// Returns the rotation axis scaled by the rotation angle in radians
+    fn angle_axis(&self) -> [f64; 3] {
+        let norm = (self.w.powi(2) + self.x.powi(2) + self.y.powi(2) + self.z.powi(2)).sqrt();
+
+        if norm <= f64::EPSILON {
+            return [0.0; 3];
+        }
+
+        let angle = (self.w / norm).clamp(-1.0, 1.0).acos();
+        let sin_angle = angle.sin();
+
+        if sin_angle.abs() <= f64::EPSILON {
+            return [0.0; 3];
+        }
+
+        let scale = 2.0 * angle / (norm * sin_angle);
+
+        [self.x * scale, self.y * scale, self.z * scale]
+    }
+}
+
+/// The packet format a SimTrafficSender emits
+#[derive(Debug, Clone)]
+pub enum SimFormat {
+    /// X-Plane VEHX packets. Only the first XPLANE_MAX_AIRCRAFT aircraft are sent.
+    XPlane,
+    /// XTRAFFIC sentences, tagged with the provided simulator name
+    XTraffic(String),
+    /// FlightGear multiplayer position messages, all using the provided aircraft model path
+    /// (e.g. "Aircraft/A320-family/Models/A320-231.xml")
+    FlightGear(String),
+}
+
+/// Sends the aircraft of the latest States snapshot to a simulator over UDP. The snapshot
+/// should be updated whenever new data is received from the API, while send() should be
+/// called at the rate the simulator should receive updates (e.g. several times per second).
+///
+pub struct SimTrafficSender {
+    socket: UdpSocket,
+    target: SocketAddr,
+    format: SimFormat,
+    states: Vec<StateVector>,
+    slots: HashMap<String, i32>,
+}
+
+impl SimTrafficSender {
+    /// Creates a new SimTrafficSender which sends packets in the given format to the target
+    /// address, for example "127.0.0.1:49000" for X-Plane or "127.0.0.1:5000" for FlightGear.
+    ///
+    pub fn new<A: ToSocketAddrs>(target: A, format: SimFormat) -> io::Result<Self> {
+        let target = target
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no address to send to"))?;
+
+        let bind = if target.is_ipv4() {
+            "0.0.0.0:0"
+        } else {
+            "[::]:0"
+        };
+
+        Ok(Self {
+            socket: UdpSocket::bind(bind)?,
+            target,
+            format,
+            states: Vec::new(),
+            slots: HashMap::new(),
+        })
+    }
+
+    /// Replaces the current traffic with the state vectors of the provided snapshot.
+    /// Aircraft keep their X-Plane index for as long as they remain in the snapshots.
+    ///
+    pub fn update(&mut self, states: &States) {
+        self.states = states
+            .states
+            .iter()
+            .filter(|state| state.latitude.is_some() && state.longitude.is_some())
+            .cloned()
+            .collect();
+
+        if let SimFormat::XPlane = self.format {
+            let current: Vec<&str> = self.states.iter().map(|s| s.icao24.as_str()).collect();
+            self.slots
+                .retain(|icao24, _| current.contains(&icao24.as_str()));
+
+            for state in &self.states {
+                if self.slots.contains_key(&state.icao24) {
+                    continue;
+                }
+
+                let free = (1..=XPLANE_MAX_AIRCRAFT as i32)
+                    .find(|slot| !self.slots.values().any(|used| used == slot));
+
+                match free {
+                    Some(slot) => {
+                        self.slots.insert(state.icao24.clone(), slot);
+                    }
+                    None => break,
+                }
+            }
+        }
+    }
+
+    /// Sends one packet per aircraft, extrapolated to the given time in seconds since the
+    /// Unix Epoch. Returns the number of packets sent.
+    ///
+    pub fn send(&self, time: f64) -> io::Result<usize> {
+        let mut sent = 0;
+
+        for state in &self.states {
+            let Some(position) = extrapolate(state, time) else {
+                continue;
+            };
+
+            let packet = match &self.format {
+                SimFormat::XPlane => match self.slots.get(&state.icao24) {
+                    Some(slot) => xplane_vehx(*slot, &position),
+                    None => continue,
+                },
+                SimFormat::XTraffic(sim_name) => match xtraffic(sim_name, state, &position) {
+                    Some(sentence) => sentence.into_bytes(),
+                    None => continue,
+                },
+                SimFormat::FlightGear(model) => {
+                    let callsign = state.callsign.as_deref().unwrap_or(&state.icao24).trim();
+
+                    flightgear_position(callsign, model, &position, time)
+                }
+            };
+
+            self.socket.send_to(&packet, self.target)?;
+            sent += 1;
+        }
+
+        Ok(sent)
+    }
+}
@@ -0,0 +1,79 @@
+//! Transponder (Mode A) codes.
+
+use std::{fmt, str::FromStr};
+
+use crate::errors::Error;
+
+/// A 4-digit octal transponder code, as set by the pilot, e.g. 7000 or 2453
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Squawk {
+    /// The four octal digits, read as a decimal number (so 7700 is stored as 7700)
+    code: u16,
+}
+
+impl Squawk {
+    /// Unlawful interference (hijacking)
+    pub const HIJACK: Squawk = Squawk { code: 7500 };
+    /// Radio communication failure
+    pub const RADIO_FAILURE: Squawk = Squawk { code: 7600 };
+    /// General emergency
+    pub const EMERGENCY: Squawk = Squawk { code: 7700 };
+
+    /// Creates a Squawk from its four digits read as a decimal number, e.g. 7700. Returns None
+    /// if the number has more than four digits or any digit is 8 or 9.
+    ///
+    pub fn new(code: u16) -> Option<Self> {
+        let valid = code < 10000
+            && [code, code / 10, code / 100, code / 1000]
+                .iter()
+                .all(|digits| digits % 10 < 8);
+
+        valid.then_some(Self { code })
+    }
+
+    /// Returns the four digits read as a decimal number, e.g. 7700
+    pub fn code(&self) -> u16 {
+        self.code
+    }
+
+    /// Returns true for 7500, unlawful interference (hijacking)
+    pub fn is_hijack(&self) -> bool {
+        *self == Self::HIJACK
+    }
+
+    /// Returns true for 7600, radio communication failure
+    pub fn is_radio_failure(&self) -> bool {
+        *self == Self::RADIO_FAILURE
+    }
+
+    /// Returns true for 7700, general emergency
+    pub fn is_emergency(&self) -> bool {
+        *self == Self::EMERGENCY
+    }
+
+    /// Returns true for any of the three emergency codes 7500, 7600, and 7700
+    pub fn is_any_emergency(&self) -> bool {
+        self.is_hijack() || self.is_radio_failure() || self.is_emergency()
+    }
+}
+
+impl FromStr for Squawk {
+    type Err = Error;
+
+    /// Parses exactly four octal digits, e.g. "0400"
+    fn from_str(code: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::InvalidSquawk(code.to_string());
+
+        if code.len() != 4 || !code.bytes().all(|digit| (b'0'..=b'7').contains(&digit)) {
+            return Err(invalid());
+        }
+
+        code.parse().ok().and_then(Self::new).ok_or_else(invalid)
+    }
+}
+
+impl fmt::Display for Squawk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}", self.code)
+    }
+}
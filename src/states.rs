@@ -1,26 +1,64 @@
-use std::sync::Arc;
+use std::{collections::HashMap, fmt, ops::Deref, time::Duration};
 
+use chrono::{DateTime, Utc};
+use futures_util::{future, stream, Stream, StreamExt};
 use log::{debug, info, warn};
-use serde::{Deserialize, Deserializer};
-use serde_json::{from_value, Value};
+use serde::{
+    de::{self, IgnoredAny, SeqAccess, Visitor},
+    Deserialize,
+};
+use serde_json::{json, Value};
 
-use crate::{bounding_box::BoundingBox, errors::Error};
+use crate::{
+    bounding_box::{great_circle_km, initial_bearing, BoundingBox},
+    context::RequestContext,
+    dedup::PositionDedup,
+    errors::Error,
+    icao24,
+    policy::EndpointClass,
+    poll::PollSchedule,
+    response::WithRaw,
+    squawk::Squawk,
+    time, units,
+    validation::{ValidationReport, Validators},
+};
 
-#[derive(Debug, Deserialize)]
+/// The time resolution in seconds of state vectors requested without a login
+pub const ANONYMOUS_RESOLUTION: u64 = 10;
+
+/// The time resolution in seconds of state vectors requested with a login
+pub const AUTHENTICATED_RESOLUTION: u64 = 5;
+
+/// Rounds a timestamp down to the given time resolution, like OpenSky does with the time
+/// parameter of state vector requests
+///
+pub fn snap_time(timestamp: u64, resolution: u64) -> u64 {
+    if resolution == 0 {
+        return timestamp;
+    }
+
+    timestamp - timestamp % resolution
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
 pub struct States {
     pub time: u64,
     pub states: Vec<StateVector>,
 }
 
-#[derive(Debug)]
+/// The state of a single aircraft. State vectors are compared field by field, with floats
+/// following IEEE 754, so they implement neither Eq nor Hash. The icao24 together with the
+/// last_contact identifies a state vector, e.g. as a key in maps.
+///
+#[derive(Debug, Clone, PartialEq)]
 pub struct StateVector {
     pub icao24: String,
     pub callsign: Option<String>,
     pub origin_country: String,
     pub time_position: Option<u64>,
     pub last_contact: u64,
-    pub longitude: Option<f32>,
-    pub latitude: Option<f32>,
+    pub longitude: Option<f64>,
+    pub latitude: Option<f64>,
     pub baro_altitude: Option<f32>,
     pub on_ground: bool,
     pub velocity: Option<f32>,
@@ -28,7 +66,7 @@ pub struct StateVector {
     pub vertical_rate: Option<f32>,
     pub sensors: Option<Vec<u64>>,
     pub geo_altitude: Option<f32>,
-    pub squawk: Option<String>,
+    pub squawk: Option<Squawk>,
     pub spi: bool,
     pub position_source: u8,
     /// There is an undocumented extra field in StateVectors, for now it will be read, and just
@@ -36,65 +74,542 @@ pub struct StateVector {
     pub category: Option<u32>,
 }
 
+impl States {
+    /// Returns the number of state vectors in this snapshot
+    pub fn len(&self) -> usize {
+        self.states.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.states.is_empty()
+    }
+
+    /// Removes every state vector whose last contact is more than max_age seconds older than
+    /// the time of this snapshot
+    ///
+    pub fn retain_recent(&mut self, max_age: u64) {
+        let time = self.time;
+
+        self.states.retain(|state| state.age_at(time) <= max_age);
+    }
+
+    /// Returns a copy of this snapshot without surface vehicles and obstacles. Only extended
+    /// requests return the categories needed to tell them apart.
+    ///
+    pub fn aircraft_only(&self) -> States {
+        States {
+            time: self.time,
+            states: self
+                .states
+                .iter()
+                .filter(|state| state.is_aircraft())
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// Combines several snapshots, e.g. the responses for neighbouring bounding boxes, into one.
+    /// Aircraft which are in more than one snapshot are only included once, with their most
+    /// recent state vector. The state vectors are sorted by icao24, and the time is the latest
+    /// time of all snapshots.
+    ///
+    pub fn merge(snapshots: impl IntoIterator<Item = States>) -> States {
+        let mut time = 0;
+        let mut merged: HashMap<String, StateVector> = HashMap::new();
+
+        for snapshot in snapshots {
+            time = time.max(snapshot.time);
+
+            for state in snapshot.states {
+                match merged.get(&state.icao24) {
+                    Some(existing) if existing.last_contact >= state.last_contact => {}
+                    _ => {
+                        merged.insert(state.icao24.clone(), state);
+                    }
+                }
+            }
+        }
+
+        let mut states: Vec<StateVector> = merged.into_values().collect();
+        states.sort_by(|a, b| a.icao24.cmp(&b.icao24));
+
+        States { time, states }
+    }
+
+    /// Sorts the state vectors by their great-circle distance from the given point, nearest
+    /// first. State vectors without a position are moved to the end.
+    ///
+    pub fn sort_by_distance_to(&mut self, latitude: f64, longitude: f64) {
+        self.states.sort_by(|a, b| {
+            let distance = |state: &StateVector| {
+                state
+                    .distance_to(latitude, longitude)
+                    .unwrap_or(f64::INFINITY)
+            };
+
+            distance(a).total_cmp(&distance(b))
+        });
+    }
+
+    /// Removes every state vector which is not within radius_km kilometers (great-circle
+    /// distance) of the given point, including those without a position
+    ///
+    pub fn retain_within_radius(&mut self, latitude: f64, longitude: f64, radius_km: f64) {
+        self.states.retain(|state| {
+            state
+                .distance_to(latitude, longitude)
+                .is_some_and(|distance| distance <= radius_km)
+        });
+    }
+}
+
+impl Deref for States {
+    type Target = [StateVector];
+
+    fn deref(&self) -> &Self::Target {
+        &self.states
+    }
+}
+
+impl IntoIterator for States {
+    type Item = StateVector;
+    type IntoIter = std::vec::IntoIter<StateVector>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.states.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a States {
+    type Item = &'a StateVector;
+    type IntoIter = std::slice::Iter<'a, StateVector>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.states.iter()
+    }
+}
+
+impl StateVector {
+    /// Returns the number of seconds between the last contact with the aircraft and the given
+    /// time, usually the time of the snapshot this StateVector belongs to
+    ///
+    pub fn age_at(&self, snapshot_time: u64) -> u64 {
+        snapshot_time.saturating_sub(self.last_contact)
+    }
+
+    /// Returns the time of the last update from the transponder
+    pub fn last_contact_datetime(&self) -> DateTime<Utc> {
+        time::datetime(self.last_contact)
+    }
+
+    /// Returns the time of the last position update, or None if no position has been received
+    /// in the past 15 seconds
+    ///
+    pub fn time_position_datetime(&self) -> Option<DateTime<Utc>> {
+        self.time_position.map(time::datetime)
+    }
+
+    /// Returns the great-circle distance in kilometers between the position of the aircraft
+    /// and the given point, or None if the position is unknown
+    ///
+    pub fn distance_to(&self, latitude: f64, longitude: f64) -> Option<f64> {
+        Some(great_circle_km(
+            self.latitude?,
+            self.longitude?,
+            latitude,
+            longitude,
+        ))
+    }
+
+    /// Returns true if the ICAO24 address lies in a block commonly reserved for military or
+    /// other state aircraft. See the icao24 module for the limits of this heuristic.
+    ///
+    pub fn is_likely_military(&self) -> bool {
+        icao24::parse(&self.icao24).is_some_and(icao24::is_likely_military)
+    }
+
+    /// Returns the ISO 3166-1 alpha-2 code of the state of registry, derived from the block the
+    /// ICAO24 address was allocated from. Unlike origin_country this is a stable code, e.g.
+    /// "US" rather than "United States", but it is None for unallocated addresses.
+    ///
+    pub fn registration_country_code(&self) -> Option<&'static str> {
+        icao24::parse(&self.icao24).and_then(icao24::country_code)
+    }
+
+    /// Returns true if the category marks a surface emergency or service vehicle
+    pub fn is_surface_vehicle(&self) -> bool {
+        matches!(self.category, Some(16 | 17))
+    }
+
+    /// Returns true if the category marks a point, cluster, or line obstacle, which includes
+    /// tethered balloons
+    ///
+    pub fn is_obstacle(&self) -> bool {
+        matches!(self.category, Some(18..=20))
+    }
+
+    /// Returns true unless the category marks a surface vehicle or an obstacle. State vectors
+    /// without a category, e.g. from requests which were not extended, count as aircraft.
+    ///
+    pub fn is_aircraft(&self) -> bool {
+        !self.is_surface_vehicle() && !self.is_obstacle()
+    }
+
+    /// Returns the initial great-circle bearing from the aircraft to the given point in degrees
+    /// clockwise from north, or None if the position is unknown
+    ///
+    pub fn bearing_to(&self, latitude: f64, longitude: f64) -> Option<f64> {
+        Some(initial_bearing(
+            self.latitude?,
+            self.longitude?,
+            latitude,
+            longitude,
+        ))
+    }
+
+    /// Returns the initial great-circle bearing from the given point to the aircraft in degrees
+    /// clockwise from north, e.g. where to look from an observer's location, or None if the
+    /// position is unknown
+    ///
+    pub fn bearing_from(&self, latitude: f64, longitude: f64) -> Option<f64> {
+        Some(initial_bearing(
+            latitude,
+            longitude,
+            self.latitude?,
+            self.longitude?,
+        ))
+    }
+
+    /// Returns the geometric minus the barometric altitude in meters, or None if either is
+    /// unknown
+    ///
+    pub fn altitude_difference(&self) -> Option<f32> {
+        Some(self.geo_altitude? - self.baro_altitude?)
+    }
+
+    /// Returns the ground speed in knots
+    pub fn velocity_knots(&self) -> Option<f32> {
+        Some(units::mps_to_knots(self.velocity? as f64) as f32)
+    }
+
+    /// Returns the barometric altitude in feet
+    pub fn baro_altitude_ft(&self) -> Option<f32> {
+        Some(units::meters_to_feet(self.baro_altitude? as f64) as f32)
+    }
+
+    /// Returns the geometric altitude in feet
+    pub fn geo_altitude_ft(&self) -> Option<f32> {
+        Some(units::meters_to_feet(self.geo_altitude? as f64) as f32)
+    }
+
+    /// Returns the vertical rate in feet per minute, positive when climbing
+    pub fn vertical_rate_fpm(&self) -> Option<f32> {
+        Some(units::mps_to_fpm(self.vertical_rate? as f64) as f32)
+    }
+
+    /// Returns the north and east components of the ground speed in m/s, or None if the
+    /// velocity or true track is unknown
+    ///
+    pub fn ground_velocity(&self) -> Option<(f64, f64)> {
+        let velocity = self.velocity? as f64;
+        let track = (self.true_track? as f64).to_radians();
+
+        // The true track is measured clockwise from north
+        Some((velocity * track.cos(), velocity * track.sin()))
+    }
+
+    /// Returns the velocity of the aircraft in a local north-east-up frame in m/s, or None if
+    /// the velocity or true track is unknown. An unknown vertical rate is treated as level
+    /// flight.
+    ///
+    pub fn velocity_vector(&self) -> Option<VelocityVector> {
+        let (north, east) = self.ground_velocity()?;
+
+        Some(VelocityVector {
+            north,
+            east,
+            up: self.vertical_rate.unwrap_or(0.0) as f64,
+        })
+    }
+}
+
+/// The velocity of an aircraft in m/s, split into components along the local north, east, and
+/// up directions
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VelocityVector {
+    pub north: f64,
+    pub east: f64,
+    pub up: f64,
+}
+
+impl VelocityVector {
+    /// Returns the horizontal speed in m/s
+    pub fn ground_speed(&self) -> f64 {
+        self.north.hypot(self.east)
+    }
+
+    /// Returns the speed along all three axes in m/s
+    pub fn speed(&self) -> f64 {
+        (self.north.powi(2) + self.east.powi(2) + self.up.powi(2)).sqrt()
+    }
+
+    /// Returns the direction of horizontal movement in degrees clockwise from north, in the
+    /// range [0, 360)
+    ///
+    pub fn track(&self) -> f64 {
+        self.east.atan2(self.north).to_degrees().rem_euclid(360.0)
+    }
+}
+
+/// The states returned by a RadiusStateRequest, ordered by distance from the center
+#[derive(Debug, Clone, PartialEq)]
+pub struct NearbyStates {
+    pub time: u64,
+    pub states: Vec<NearbyState>,
+}
+
+/// A state vector together with its distance from the center of a RadiusStateRequest
+#[derive(Debug, Clone, PartialEq)]
+pub struct NearbyState {
+    /// Great-circle distance from the center in kilometers
+    pub distance: f64,
+    pub state: StateVector,
+}
+
+/// The names of the fields of a state vector, in the order of the API's arrays
+const FIELDS: [&str; 18] = [
+    "icao24",
+    "callsign",
+    "origin_country",
+    "time_position",
+    "last_contact",
+    "longitude",
+    "latitude",
+    "baro_altitude",
+    "on_ground",
+    "velocity",
+    "true_track",
+    "vertical_rate",
+    "sensors",
+    "geo_altitude",
+    "squawk",
+    "spi",
+    "position_source",
+    "category",
+];
+
 impl<'de> Deserialize<'de> for StateVector {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: serde::Deserializer<'de>,
     {
-        let values: Vec<Value> = Deserialize::deserialize(deserializer)?;
+        deserializer.deserialize_seq(StateVectorVisitor)
+    }
+}
 
-        if values.len() != 18 && values.len() != 17 {
-            warn!("expected 18 elements, got {}", values.len());
-            return Err(serde::de::Error::invalid_length(
-                values.len(),
-                &"expected 18 elements",
-            ));
+/// Parses the elements of a state vector array in place, without building a Value tree first
+struct StateVectorVisitor;
+
+impl<'de> Visitor<'de> for StateVectorVisitor {
+    type Value = StateVector;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a state vector array of 17 or 18 elements")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let state = StateVector {
+            icao24: field(&mut seq, 0)?,
+            callsign: field(&mut seq, 1)?,
+            origin_country: field(&mut seq, 2)?,
+            time_position: field(&mut seq, 3)?,
+            last_contact: field(&mut seq, 4)?,
+            longitude: field(&mut seq, 5)?,
+            latitude: field(&mut seq, 6)?,
+            baro_altitude: field(&mut seq, 7)?,
+            on_ground: field(&mut seq, 8)?,
+            velocity: field(&mut seq, 9)?,
+            true_track: field(&mut seq, 10)?,
+            vertical_rate: field(&mut seq, 11)?,
+            sensors: field(&mut seq, 12)?,
+            geo_altitude: field(&mut seq, 13)?,
+            // Receivers occasionally decode garbage, which is not worth failing the snapshot for
+            squawk: field::<Option<String>, _>(&mut seq, 14)?
+                .and_then(|squawk| squawk.parse().ok()),
+            spi: field(&mut seq, 15)?,
+            position_source: field(&mut seq, 16)?,
+            // The category is only sent for extended requests
+            category: next(&mut seq, 17)?.flatten(),
+        };
+
+        if seq.next_element::<IgnoredAny>()?.is_some() {
+            warn!("expected 17 or 18 elements, got more");
+            return Err(de::Error::invalid_length(19, &self));
         }
 
-        Ok(StateVector {
-            icao24: from_value(values[0].clone()).map_err(serde::de::Error::custom)?,
-            callsign: from_value(values[1].clone()).map_err(serde::de::Error::custom)?,
-            origin_country: from_value(values[2].clone()).map_err(serde::de::Error::custom)?,
-            time_position: from_value(values[3].clone()).map_err(serde::de::Error::custom)?,
-            last_contact: from_value(values[4].clone()).map_err(serde::de::Error::custom)?,
-            longitude: from_value(values[5].clone()).map_err(serde::de::Error::custom)?,
-            latitude: from_value(values[6].clone()).map_err(serde::de::Error::custom)?,
-            baro_altitude: from_value(values[7].clone()).map_err(serde::de::Error::custom)?,
-            on_ground: from_value(values[8].clone()).map_err(serde::de::Error::custom)?,
-            velocity: from_value(values[9].clone()).map_err(serde::de::Error::custom)?,
-            true_track: from_value(values[10].clone()).map_err(serde::de::Error::custom)?,
-            vertical_rate: from_value(values[11].clone()).map_err(serde::de::Error::custom)?,
-            sensors: from_value(values[12].clone()).map_err(serde::de::Error::custom)?,
-            geo_altitude: from_value(values[13].clone()).map_err(serde::de::Error::custom)?,
-            squawk: from_value(values[14].clone()).map_err(serde::de::Error::custom)?,
-            spi: from_value(values[15].clone()).map_err(serde::de::Error::custom)?,
-            position_source: from_value(values[16].clone()).map_err(serde::de::Error::custom)?,
-            category: if values.len() == 18 {
-                from_value(values[17].clone()).map_err(serde::de::Error::custom)?
-            } else {
-                None
-            },
+        Ok(state)
+    }
+}
+
+/// Deserializes the next element of a state vector array, if there is one. The error names
+/// the offending field and index.
+///
+fn next<'de, T, A>(seq: &mut A, index: usize) -> Result<Option<T>, A::Error>
+where
+    T: Deserialize<'de>,
+    A: SeqAccess<'de>,
+{
+    seq.next_element().map_err(|error| {
+        de::Error::custom(format_args!(
+            "invalid {} at index {index}: {error}",
+            FIELDS[index]
+        ))
+    })
+}
+
+/// Deserializes the next element of a state vector array, which must exist
+fn field<'de, T, A>(seq: &mut A, index: usize) -> Result<T, A::Error>
+where
+    T: Deserialize<'de>,
+    A: SeqAccess<'de>,
+{
+    next(seq, index)?.ok_or_else(|| {
+        warn!("expected 17 or 18 elements, got {index}");
+        de::Error::invalid_length(index, &StateVectorVisitor)
+    })
+}
+
+impl StateVector {
+    /// Converts the state vector back into the array layout of OpenSky's responses. The
+    /// category is only included if it is known, giving 18 instead of 17 elements like the
+    /// responses to extended requests.
+    ///
+    pub fn to_opensky_array(&self) -> Value {
+        let mut array = vec![
+            json!(self.icao24),
+            json!(self.callsign),
+            json!(self.origin_country),
+            json!(self.time_position),
+            json!(self.last_contact),
+            json!(self.longitude),
+            json!(self.latitude),
+            json!(self.baro_altitude),
+            json!(self.on_ground),
+            json!(self.velocity),
+            json!(self.true_track),
+            json!(self.vertical_rate),
+            json!(self.sensors),
+            json!(self.geo_altitude),
+            json!(self.squawk.map(|squawk| squawk.to_string())),
+            json!(self.spi),
+            json!(self.position_source),
+        ];
+
+        if let Some(category) = self.category {
+            array.push(json!(category));
+        }
+
+        Value::Array(array)
+    }
+}
+
+impl States {
+    /// Converts the snapshot back into the layout of OpenSky's /states responses, which can be
+    /// parsed again using serde_json
+    ///
+    pub fn to_opensky_json(&self) -> Value {
+        json!({
+            "time": self.time,
+            "states": self.states.iter().map(StateVector::to_opensky_array).collect::<Vec<_>>(),
         })
     }
 }
 
 #[derive(Debug, Clone)]
 pub struct StateRequest {
-    login: Option<Arc<(String, String)>>,
+    context: RequestContext,
     bbox: Option<BoundingBox>,
     time: Option<u64>,
     icao24_addresses: Vec<String>,
     serials: Vec<u64>,
+    own: bool,
+    extended: bool,
+    validators: Option<Validators>,
 }
 
 impl StateRequest {
+    /// Sends the request. If Validators were added to the request, invalid state vectors are
+    /// removed from the response.
+    ///
     pub async fn send(&self) -> Result<States, Error> {
-        let login_part = if let Some(login) = &self.login {
-            format!("{}:{}@", login.0, login.1)
-        } else {
-            String::new()
-        };
+        let (states, report) = self.send_with_report().await?;
+
+        if !report.is_empty() {
+            warn!("removed {} invalid state vectors", report.len());
+        }
+
+        Ok(states)
+    }
+
+    /// Sends the request, and also returns the state vectors that were removed because they
+    /// failed validation
+    ///
+    pub async fn send_with_report(&self) -> Result<(States, ValidationReport), Error> {
+        self.validate()?;
+
+        let (status, bytes) = self.context.get(EndpointClass::States, &self.url()).await?;
+
+        self.context
+            .observe_parse(EndpointClass::States, self.parse(status, &bytes))
+    }
+
+    /// Sends the request like send(), and keeps the original response body next to the
+    /// parsed States
+    ///
+    pub async fn send_keep_raw(&self) -> Result<WithRaw<States>, Error> {
+        self.validate()?;
+
+        let (status, bytes) = self.context.get(EndpointClass::States, &self.url()).await?;
+        let (states, report) = self
+            .context
+            .observe_parse(EndpointClass::States, self.parse(status, &bytes))?;
+
+        if !report.is_empty() {
+            warn!("removed {} invalid state vectors", report.len());
+        }
+
+        Ok(WithRaw {
+            parsed: states,
+            raw: bytes,
+        })
+    }
+
+    /// Renders this request as an equivalent curl command, for debugging and bug reports. The
+    /// password is masked.
+    ///
+    pub fn to_curl(&self) -> String {
+        self.context.curl(&self.url(), false)
+    }
+
+    /// Renders this request as an equivalent curl command, including the password
+    pub fn to_curl_with_credentials(&self) -> String {
+        self.context.curl(&self.url(), true)
+    }
+
+    /// Checks the parameters which the server would reject with an unhelpful error
+    fn validate(&self) -> Result<(), Error> {
+        match &self.bbox {
+            Some(bbox) => bbox.validate(),
+            None => Ok(()),
+        }
+    }
 
+    /// Returns the URL this request is sent to
+    pub(crate) fn url(&self) -> String {
         let mut args = String::new();
 
         if let Some(time) = self.time {
@@ -125,7 +640,7 @@ impl StateRequest {
                 args.push('&');
             }
 
-            if let Some(first) = self.icao24_addresses.get(0) {
+            if let Some(first) = self.icao24_addresses.first() {
                 args.push_str(&format!("icao24={}", first));
             }
 
@@ -134,15 +649,18 @@ impl StateRequest {
             }
         }
 
+        if self.extended {
+            args.push(if args.is_empty() { '?' } else { '&' });
+            args.push_str("extended=1");
+        }
+
         // If serial numbers are provided determines which endpoint we use
-        let endpoint = if !self.serials.is_empty() {
-            if args.is_empty() {
-                args.push('?');
-            } else {
-                args.push('&');
+        let endpoint = if self.own || !self.serials.is_empty() {
+            if !self.serials.is_empty() {
+                args.push(if args.is_empty() { '?' } else { '&' });
             }
 
-            if let Some(first) = self.serials.get(0) {
+            if let Some(first) = self.serials.first() {
                 args.push_str(&format!("serials={}", first));
             }
 
@@ -150,28 +668,24 @@ impl StateRequest {
                 args.push_str(&format!("&serials={}", serial));
             }
 
-            "own"
+            &self.context.endpoints().states_own
         } else {
-            "all"
+            &self.context.endpoints().states_all
         };
 
-        let url = format!(
-            "https://{}opensky-network.org/api/states/{}{}",
-            login_part, endpoint, args
-        );
-
-        let res = reqwest::get(url).await?;
+        self.context.url(endpoint, &args)
+    }
 
-        match res.status() {
+    fn parse(
+        &self,
+        status: reqwest::StatusCode,
+        bytes: &[u8],
+    ) -> Result<(States, ValidationReport), Error> {
+        match status {
             reqwest::StatusCode::OK => {
-                let bytes = res.bytes().await?.to_vec();
-
-                let time = match self.time {
-                    Some(time) => time,
-                    None => 0,
-                };
-                info!("received: {:#?}", String::from_utf8_lossy(&bytes));
-                let states: States = match serde_json::from_slice(&bytes) {
+                let time = self.time.unwrap_or_default();
+                info!("received: {:#?}", String::from_utf8_lossy(bytes));
+                let mut states: States = match serde_json::from_slice(bytes) {
                     Ok(result) => result,
                     Err(err) => {
                         warn!("JSON Error: {}", err);
@@ -188,7 +702,12 @@ impl StateRequest {
 
                 debug!("ShortInnerOpenSkyStates: \n{:#?}", states);
 
-                Ok(states)
+                let report = match &self.validators {
+                    Some(validators) => validators.apply(&mut states),
+                    None => ValidationReport::default(),
+                };
+
+                Ok((states, report))
             }
             status => Err(Error::Http(status)),
         }
@@ -200,14 +719,17 @@ pub struct StateRequestBuilder {
 }
 
 impl StateRequestBuilder {
-    pub fn new(login: Option<Arc<(String, String)>>) -> Self {
+    pub fn new(context: RequestContext) -> Self {
         Self {
             inner: StateRequest {
-                login,
+                context,
                 bbox: None,
                 time: None,
                 icao24_addresses: Vec::new(),
                 serials: Vec::new(),
+                own: false,
+                extended: false,
+                validators: None,
             },
         }
     }
@@ -215,8 +737,10 @@ impl StateRequestBuilder {
     /// Adds the provided bounding box to the request. This will only get states that are within
     /// that bounding box. This will overwrite any previously specified bounding box.
     ///
-    pub fn with_bbox(mut self, bbox: BoundingBox) -> Self {
-        self.inner.bbox = Some(bbox);
+    /// With the geo feature, a geo::Rect (x = longitude, y = latitude) is accepted as well.
+    ///
+    pub fn with_bbox(mut self, bbox: impl Into<BoundingBox>) -> Self {
+        self.inner.bbox = Some(bbox.into());
 
         self
     }
@@ -232,6 +756,23 @@ impl StateRequestBuilder {
         self
     }
 
+    /// Specifies the time at which to get the data like at_time(), using a DateTime instead of
+    /// seconds since the Unix Epoch
+    ///
+    pub fn at_datetime(self, datetime: DateTime<Utc>) -> Self {
+        self.at_time(time::seconds(datetime))
+    }
+
+    /// Like at_time(), but rounds the timestamp down to the time resolution of the login used
+    /// (see RequestContext::time_resolution()). The time of the returned States then matches
+    /// the requested time, instead of OpenSky silently returning an earlier one.
+    ///
+    pub fn at_snapped_time(self, timestamp: u64) -> Self {
+        let resolution = self.inner.context.time_resolution();
+
+        self.at_time(snap_time(timestamp, resolution))
+    }
+
     /// Adds an ICAO24 transponder address represented by a hex string (e.g. abc9f3) to filter the
     /// request by. Calling this function multiple times will append more addresses which will be
     /// included in the returned data.
@@ -251,12 +792,88 @@ impl StateRequestBuilder {
     /// Calling this function multiple times will append more serial numbers of receiviers which
     /// provide the returned data.
     ///
+    #[deprecated(
+        note = "this switches the request to the endpoint of your own sensors, use OpenSkyApi::get_own_states() instead"
+    )]
     pub fn with_serial(mut self, serial: u64) -> Self {
         self.inner.serials.push(serial);
 
         self
     }
 
+    /// Requests the extended state vectors, which include the aircraft category. Without
+    /// this, the category of every state vector is None.
+    ///
+    pub fn extended(mut self) -> Self {
+        self.inner.extended = true;
+
+        self
+    }
+
+    /// Removes state vectors that fail any of the given validation rules from the response.
+    /// Validators::default_rules() provides a reasonable default rule set.
+    ///
+    pub fn with_validators(mut self, validators: Validators) -> Self {
+        self.inner.validators = Some(validators);
+
+        self
+    }
+
+    /// Splits the bounding box of the request (or the whole world, if there is none) into tiles
+    /// spanning at most max_tile_degrees of latitude and longitude, which are requested
+    /// concurrently. Large boxes are slow to answer, and smaller boxes cost fewer credits, so
+    /// this should be called after all other builder methods.
+    ///
+    /// Returns Error::InvalidRequest if max_tile_degrees is not a positive number, or if it is
+    /// so small that the box would be split into more than bounding_box::MAX_TILES tiles.
+    ///
+    pub fn tiled(self, max_tile_degrees: f64) -> Result<TiledStateRequest, Error> {
+        let bbox = self
+            .inner
+            .bbox
+            .unwrap_or(BoundingBox::new(-90.0, 90.0, -180.0, 180.0));
+
+        Ok(TiledStateRequest {
+            tiles: bbox.tiles(max_tile_degrees)?,
+            inner: self.inner,
+            concurrency: DEFAULT_TILE_CONCURRENCY,
+        })
+    }
+
+    /// Limits the request to the bounding box enclosing the circle of radius_km kilometers
+    /// around the given point. The response still contains the aircraft in the corners of the
+    /// box, which States::retain_within_radius() removes; within_radius() does both.
+    ///
+    pub fn around(mut self, latitude: f64, longitude: f64, radius_km: f64) -> Self {
+        self.inner.bbox = Some(BoundingBox::enclosing_circle(
+            latitude, longitude, radius_km,
+        ));
+
+        self
+    }
+
+    /// Limits the request to aircraft within radius_km kilometers (great-circle distance) of
+    /// the given point, and returns their distances from it. This replaces any previously
+    /// specified bounding box, so it should be called after all other builder methods.
+    ///
+    pub fn within_radius(
+        mut self,
+        latitude: f64,
+        longitude: f64,
+        radius_km: f64,
+    ) -> RadiusStateRequest {
+        self.inner.bbox = Some(BoundingBox::enclosing_circle(
+            latitude, longitude, radius_km,
+        ));
+
+        RadiusStateRequest {
+            inner: self.inner,
+            latitude,
+            longitude,
+            radius_km,
+        }
+    }
+
     /// Consumes this StateRequestBuilder and returns a new StateRequest. If this
     /// StateRequestBuilder could be used again effectively, then the finish() method should
     /// be called instead because that will allow this to be reused.
@@ -277,6 +894,233 @@ impl StateRequestBuilder {
     pub async fn send(self) -> Result<States, Error> {
         self.inner.send().await
     }
+
+    /// Consumes this StateRequestBuilder and sends the request to the API, keeping the
+    /// original response body next to the parsed States
+    ///
+    pub async fn send_keep_raw(self) -> Result<WithRaw<States>, Error> {
+        self.inner.send_keep_raw().await
+    }
+
+    /// Consumes this StateRequestBuilder and returns a stream which sends the request right
+    /// away and then once per interval, always for the most recent states. See poll_with().
+    ///
+    pub fn poll_every(self, interval: Duration) -> impl Stream<Item = Result<States, Error>> {
+        self.poll_with(PollSchedule::every(interval))
+    }
+
+    /// Consumes this StateRequestBuilder and returns a stream which sends the request right
+    /// away and then according to the schedule, always for the most recent states (any time
+    /// set with at_time() is ignored).
+    ///
+    /// Errors are yielded like states, and polling continues after them. After consecutive
+    /// errors, the next poll is delayed by the backoff of the RequestPolicy of the states
+    /// endpoints if that is longer than the schedule's delay. Polling stops when the stream is
+    /// dropped.
+    ///
+    pub fn poll_with(self, schedule: PollSchedule) -> impl Stream<Item = Result<States, Error>> {
+        let mut request = self.inner;
+        request.time = None;
+
+        stream::unfold(
+            (request, schedule, true, 0),
+            |(request, schedule, first, errors)| async move {
+                if !first {
+                    let mut delay = schedule.next_delay();
+
+                    if errors > 0 {
+                        let policy = request.context.policies().get(EndpointClass::States);
+                        delay = delay.max(policy.delay(errors));
+                    }
+
+                    crate::time::sleep(delay).await;
+                }
+
+                let result = request.send().await;
+                let errors = if result.is_ok() { 0 } else { errors + 1 };
+
+                Some((result, (request, schedule, false, errors)))
+            },
+        )
+    }
+
+    /// Like poll_with(), but every snapshot only contains the aircraft whose position changed
+    /// since the previous successful poll. See PositionDedup.
+    ///
+    pub fn poll_updates(self, schedule: PollSchedule) -> impl Stream<Item = Result<States, Error>> {
+        self.poll_with(schedule)
+            .scan(PositionDedup::new(), |dedup, result| {
+                future::ready(Some(result.map(|states| dedup.updated(states))))
+            })
+    }
+}
+
+/// The default number of tiles a TiledStateRequest requests at the same time
+pub const DEFAULT_TILE_CONCURRENCY: usize = 4;
+
+/// A StateRequest for a large area, which is split into tiles that are requested separately.
+/// The responses are merged into a single States, in which every aircraft appears once.
+///
+#[derive(Debug, Clone)]
+pub struct TiledStateRequest {
+    inner: StateRequest,
+    tiles: Vec<BoundingBox>,
+    concurrency: usize,
+}
+
+impl TiledStateRequest {
+    /// Sets the maximum number of tiles that are requested at the same time. Requests also
+    /// count towards the limit set with OpenSkyApi::with_max_concurrent_requests().
+    ///
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+
+        self
+    }
+
+    /// Returns the tiles which are requested
+    pub fn tiles(&self) -> &[BoundingBox] {
+        &self.tiles
+    }
+
+    /// Sends the requests of all tiles, and merges their responses. Aircraft on the edge
+    /// between two tiles are only included once, with their most recent state vector. The time
+    /// of the merged States is the latest time of all responses.
+    ///
+    /// If any tile fails, the first error is returned.
+    ///
+    pub async fn send(&self) -> Result<States, Error> {
+        let responses: Vec<Result<States, Error>> = stream::iter(self.tiles.iter().copied())
+            .map(|tile| {
+                let mut request = self.inner.clone();
+                request.bbox = Some(tile);
+
+                async move { request.send().await }
+            })
+            .buffer_unordered(self.concurrency)
+            .collect()
+            .await;
+
+        Ok(States::merge(
+            responses.into_iter().collect::<Result<Vec<_>, _>>()?,
+        ))
+    }
+}
+
+/// Builds a StateRequest for the state vectors received by your own sensors, which uses the
+/// /states/own endpoint. Unlike requests for all state vectors, these requests are not rate
+/// limited, and they cannot be limited to a bounding box.
+///
+pub struct OwnStatesRequestBuilder {
+    inner: StateRequest,
+}
+
+impl OwnStatesRequestBuilder {
+    pub fn new(context: RequestContext) -> Self {
+        let mut inner = StateRequestBuilder::new(context).inner;
+        inner.own = true;
+
+        Self { inner }
+    }
+
+    /// Specifies the time at which to get the data, in seconds since the Unix Epoch
+    pub fn at_time(mut self, timestamp: u64) -> Self {
+        self.inner.time = Some(timestamp);
+
+        self
+    }
+
+    /// Specifies the time at which to get the data like at_time(), using a DateTime instead of
+    /// seconds since the Unix Epoch
+    ///
+    pub fn at_datetime(self, datetime: DateTime<Utc>) -> Self {
+        self.at_time(time::seconds(datetime))
+    }
+
+    /// Adds an ICAO24 transponder address represented by a hex string (e.g. abc9f3) to filter
+    /// the request by. Calling this function multiple times will append more addresses.
+    ///
+    pub fn with_icao24(mut self, address: String) -> Self {
+        self.inner.icao24_addresses.push(address);
+
+        self
+    }
+
+    /// Limits the request to the data received by the sensor with the given serial number,
+    /// which must be one of your own sensors. Calling this function multiple times will append
+    /// more sensors. Without it, the data of all of your sensors is returned.
+    ///
+    pub fn with_serial(mut self, serial: u64) -> Self {
+        self.inner.serials.push(serial);
+
+        self
+    }
+
+    /// Removes state vectors that fail any of the given validation rules from the response
+    pub fn with_validators(mut self, validators: Validators) -> Self {
+        self.inner.validators = Some(validators);
+
+        self
+    }
+
+    /// Consumes this OwnStatesRequestBuilder and returns a new StateRequest
+    pub fn consume(self) -> StateRequest {
+        self.inner
+    }
+
+    /// Returns a new StateRequest with a copy of the builder's parameters
+    pub fn finish(&self) -> StateRequest {
+        self.inner.clone()
+    }
+
+    /// Consumes this OwnStatesRequestBuilder and sends the request to the API
+    pub async fn send(self) -> Result<States, Error> {
+        self.inner.send().await
+    }
+
+    /// Consumes this OwnStatesRequestBuilder and sends the request to the API, keeping the
+    /// original response body next to the parsed States
+    ///
+    pub async fn send_keep_raw(self) -> Result<WithRaw<States>, Error> {
+        self.inner.send_keep_raw().await
+    }
+}
+
+/// A StateRequest for all aircraft within a radius around a point. The API is queried with the
+/// bounding box enclosing the circle, and the aircraft outside of the circle are removed.
+///
+#[derive(Debug, Clone)]
+pub struct RadiusStateRequest {
+    inner: StateRequest,
+    latitude: f64,
+    longitude: f64,
+    radius_km: f64,
+}
+
+impl RadiusStateRequest {
+    /// Sends the request and returns every aircraft with a known position within the radius,
+    /// closest first.
+    ///
+    pub async fn send(&self) -> Result<NearbyStates, Error> {
+        let states = self.inner.send().await?;
+
+        let mut nearby: Vec<NearbyState> = states
+            .states
+            .into_iter()
+            .filter_map(|state| {
+                let distance = state.distance_to(self.latitude, self.longitude)?;
+
+                (distance <= self.radius_km).then_some(NearbyState { distance, state })
+            })
+            .collect();
+
+        nearby.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+
+        Ok(NearbyStates {
+            time: states.time,
+            states: nearby,
+        })
+    }
 }
 
 impl From<StateRequestBuilder> for StateRequest {
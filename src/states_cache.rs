@@ -0,0 +1,116 @@
+//! Short-lived caching of state vector responses.
+//!
+//! OpenSky only updates state vectors every 10 seconds (5 seconds for contributing users), so
+//! repeating an identical request within that window returns the same data and costs credits
+//! for nothing. A StatesCache keeps every successful state vector response for a configurable
+//! time, and answers identical requests from memory until it expires. Requests are identical
+//! if they ask for the same time, bounding box, and set of ICAO24 addresses, in any order.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use crate::time::Instant;
+
+/// The default time to live of cached responses, which matches OpenSky's data resolution for
+/// anonymous and most registered users
+///
+pub const DEFAULT_STATES_TTL: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone)]
+struct CachedStates {
+    stored_at: Instant,
+    body: Arc<[u8]>,
+}
+
+/// State vector response bodies by request, shared by all requests created from the same
+/// OpenSkyApi instance
+///
+#[derive(Debug, Clone)]
+pub struct StatesCache {
+    ttl: Duration,
+    entries: Arc<Mutex<HashMap<String, CachedStates>>>,
+}
+
+impl Default for StatesCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_STATES_TTL)
+    }
+}
+
+impl StatesCache {
+    /// Creates an empty cache whose responses expire after the given time
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Arc::default(),
+        }
+    }
+
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Returns the number of stored responses, including ones which expired since the last
+    /// response was stored
+    ///
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+
+    /// Returns the stored body of the URL, unless it has expired
+    pub(crate) fn get(&self, url: &str) -> Option<Arc<[u8]>> {
+        let mut entries = self.entries.lock().unwrap();
+        let key = key(url);
+
+        match entries.get(&key) {
+            Some(cached) if cached.stored_at.elapsed() < self.ttl => Some(cached.body.clone()),
+            Some(_) => {
+                entries.remove(&key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Stores the body of a successful response, and removes all expired ones, so that the
+    /// cache does not grow when requests change their time or bounding box
+    ///
+    pub(crate) fn store(&self, url: &str, body: &[u8]) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, cached| cached.stored_at.elapsed() < self.ttl);
+
+        entries.insert(
+            key(url),
+            CachedStates {
+                stored_at: Instant::now(),
+                body: Arc::from(body),
+            },
+        );
+    }
+}
+
+/// Returns the URL with its query parameters sorted, so that the order in which ICAO24
+/// addresses were added does not matter
+///
+fn key(url: &str) -> String {
+    match url.split_once('?') {
+        Some((path, query)) => {
+            let mut parameters: Vec<&str> = query.split('&').collect();
+            parameters.sort_unstable();
+
+            format!("{path}?{}", parameters.join("&"))
+        }
+        None => url.to_string(),
+    }
+}
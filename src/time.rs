@@ -0,0 +1,48 @@
+//! Clocks and timers which also work in browsers, where std's clocks panic and there is no
+//! tokio timer.
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+
+pub(crate) use web_time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// Converts seconds since the Unix Epoch, as used throughout the API, into a DateTime. Times
+/// too far in the future to be represented saturate at the latest possible DateTime.
+///
+pub(crate) fn datetime(seconds: u64) -> DateTime<Utc> {
+    i64::try_from(seconds)
+        .ok()
+        .and_then(|seconds| DateTime::from_timestamp(seconds, 0))
+        .unwrap_or(DateTime::<Utc>::MAX_UTC)
+}
+
+/// Converts a DateTime into seconds since the Unix Epoch. Times before the Unix Epoch saturate
+/// at 0.
+///
+pub(crate) fn seconds(datetime: DateTime<Utc>) -> u64 {
+    datetime.timestamp().max(0) as u64
+}
+
+/// Converts the exclusive end of a time range into the last whole second within the range,
+/// which is what OpenSky's inclusive end parameters expect. Saturates at 0 like seconds().
+///
+pub(crate) fn inclusive_end(end: DateTime<Utc>) -> u64 {
+    if end.timestamp_subsec_nanos() > 0 {
+        seconds(end)
+    } else {
+        seconds(end).saturating_sub(1)
+    }
+}
+
+/// Waits for the given time without blocking the thread
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) async fn sleep(duration: Duration) {
+    tokio::time::sleep(duration).await;
+}
+
+/// Waits for the given time without blocking the thread
+#[cfg(target_arch = "wasm32")]
+pub(crate) async fn sleep(duration: Duration) {
+    gloo_timers::future::sleep(duration).await;
+}
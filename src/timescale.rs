@@ -0,0 +1,268 @@
+//! A sink which stores state vectors in a PostgreSQL database using TimescaleDB hypertables.
+//!
+//! State vectors are queued into a bounded channel and written by a background task in
+//! batches. Once the queue is full, TimescaleSink::push() waits until the writer has caught
+//! up, so a slow database slows down the producer instead of buffering without limit.
+
+use std::{sync::Arc, time::Duration};
+
+use log::{debug, warn};
+use tokio::{
+    sync::{mpsc, oneshot},
+    task::JoinHandle,
+    time::{self, MissedTickBehavior},
+};
+use tokio_postgres::{Client, NoTls};
+
+use crate::{
+    errors::Error,
+    states::{StateVector, States},
+};
+
+/// The schema TimescaleSink writes to. The TimescaleDB extension must be available in the
+/// database. TimescaleSink::create_schema() can be used to create it.
+///
+pub const SCHEMA: &str = "
+CREATE EXTENSION IF NOT EXISTS timescaledb;
+
+CREATE TABLE IF NOT EXISTS state_vectors (
+    time TIMESTAMPTZ NOT NULL,
+    icao24 TEXT NOT NULL,
+    callsign TEXT,
+    origin_country TEXT NOT NULL,
+    time_position TIMESTAMPTZ,
+    last_contact TIMESTAMPTZ NOT NULL,
+    longitude DOUBLE PRECISION,
+    latitude DOUBLE PRECISION,
+    baro_altitude REAL,
+    on_ground BOOLEAN NOT NULL,
+    velocity REAL,
+    true_track REAL,
+    vertical_rate REAL,
+    sensors JSONB,
+    geo_altitude REAL,
+    squawk TEXT,
+    spi BOOLEAN NOT NULL,
+    position_source SMALLINT NOT NULL,
+    category INTEGER
+);
+
+SELECT create_hypertable('state_vectors', 'time', if_not_exists => TRUE);
+
+CREATE INDEX IF NOT EXISTS state_vectors_icao24_time ON state_vectors (icao24, time DESC);
+";
+
+const INSERT: &str = "
+INSERT INTO state_vectors
+SELECT to_timestamp(t), icao24, callsign, origin_country, to_timestamp(tp), to_timestamp(lc),
+    longitude, latitude, baro_altitude, on_ground, velocity, true_track, vertical_rate,
+    sensors::jsonb, geo_altitude, squawk, spi, position_source, category
+FROM unnest(
+    $1::bigint[], $2::text[], $3::text[], $4::text[], $5::bigint[], $6::bigint[], $7::float8[],
+    $8::float8[], $9::real[], $10::boolean[], $11::real[], $12::real[], $13::real[], $14::text[],
+    $15::real[], $16::text[], $17::boolean[], $18::smallint[], $19::integer[]
+) AS v(t, icao24, callsign, origin_country, tp, lc, longitude, latitude, baro_altitude,
+    on_ground, velocity, true_track, vertical_rate, sensors, geo_altitude, squawk, spi,
+    position_source, category)
+";
+
+/// Controls how TimescaleSink batches its inserts
+#[derive(Debug, Clone)]
+pub struct SinkOptions {
+    /// The maximum number of state vectors written in a single INSERT
+    pub batch_size: usize,
+    /// Pending state vectors are written at least this often, even if the batch is not full
+    pub flush_interval: Duration,
+    /// The number of snapshots that can be queued before push() starts waiting
+    pub queue_capacity: usize,
+}
+
+impl Default for SinkOptions {
+    fn default() -> Self {
+        Self {
+            batch_size: 5000,
+            flush_interval: Duration::from_secs(5),
+            queue_capacity: 16,
+        }
+    }
+}
+
+enum Command {
+    Write(u64, Vec<StateVector>),
+    Flush(oneshot::Sender<Result<(), Error>>),
+}
+
+/// Batches state vectors into the `state_vectors` hypertable. See SCHEMA for the table layout.
+pub struct TimescaleSink {
+    sender: mpsc::Sender<Command>,
+    writer: JoinHandle<Result<(), Error>>,
+    client: Arc<Client>,
+}
+
+impl TimescaleSink {
+    /// Connects to the database using a libpq-style connection string (for example
+    /// "host=localhost user=postgres dbname=opensky") and starts the background writer. The
+    /// connection is not encrypted.
+    ///
+    /// Must be called from within a tokio runtime.
+    ///
+    pub async fn connect(config: &str, options: SinkOptions) -> Result<Self, Error> {
+        let (client, connection) = tokio_postgres::connect(config, NoTls).await?;
+
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                warn!("Postgres connection error: {}", e);
+            }
+        });
+
+        let client = Arc::new(client);
+        let (sender, receiver) = mpsc::channel(options.queue_capacity.max(1));
+        let writer = tokio::spawn(write_batches(client.clone(), receiver, options));
+
+        Ok(Self {
+            sender,
+            writer,
+            client,
+        })
+    }
+
+    /// Creates the TimescaleDB extension, the hypertable, and its indices if they do not exist
+    pub async fn create_schema(&self) -> Result<(), Error> {
+        self.client.batch_execute(SCHEMA).await?;
+
+        Ok(())
+    }
+
+    /// Queues all state vectors of the snapshot to be written. This waits if the queue is full.
+    pub async fn push(&self, states: States) -> Result<(), Error> {
+        self.sender
+            .send(Command::Write(states.time, states.states))
+            .await
+            .map_err(|_| Error::SinkClosed)
+    }
+
+    /// Writes all queued state vectors and waits until they are stored
+    pub async fn flush(&self) -> Result<(), Error> {
+        let (tx, rx) = oneshot::channel();
+
+        self.sender
+            .send(Command::Flush(tx))
+            .await
+            .map_err(|_| Error::SinkClosed)?;
+
+        rx.await.map_err(|_| Error::SinkClosed)?
+    }
+
+    /// Writes all queued state vectors and stops the background writer
+    pub async fn close(self) -> Result<(), Error> {
+        drop(self.sender);
+
+        self.writer.await.map_err(|_| Error::SinkClosed)?
+    }
+}
+
+async fn write_batches(
+    client: Arc<Client>,
+    mut receiver: mpsc::Receiver<Command>,
+    options: SinkOptions,
+) -> Result<(), Error> {
+    let mut pending: Vec<(u64, StateVector)> = Vec::new();
+    let mut interval = time::interval(options.flush_interval);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    loop {
+        tokio::select! {
+            command = receiver.recv() => match command {
+                Some(Command::Write(time, states)) => {
+                    pending.extend(states.into_iter().map(|state| (time, state)));
+
+                    while pending.len() >= options.batch_size.max(1) {
+                        let rest = pending.split_off(options.batch_size.max(1));
+                        insert(&client, &pending).await?;
+                        pending = rest;
+                    }
+                }
+                Some(Command::Flush(done)) => {
+                    let result = insert(&client, &pending).await;
+                    pending.clear();
+                    let _ = done.send(result);
+                }
+                None => return insert(&client, &pending).await,
+            },
+            _ = interval.tick() => {
+                insert(&client, &pending).await?;
+                pending.clear();
+            }
+        }
+    }
+}
+
+async fn insert(client: &Client, batch: &[(u64, StateVector)]) -> Result<(), Error> {
+    if batch.is_empty() {
+        return Ok(());
+    }
+
+    debug!("writing {} state vectors", batch.len());
+
+    let times: Vec<i64> = column(batch, |(time, _)| *time as i64);
+    let icao24s: Vec<&str> = column(batch, |(_, s)| s.icao24.as_str());
+    let callsigns: Vec<Option<&str>> = column(batch, |(_, s)| s.callsign.as_deref());
+    let countries: Vec<&str> = column(batch, |(_, s)| s.origin_country.as_str());
+    let time_positions: Vec<Option<i64>> =
+        column(batch, |(_, s)| s.time_position.map(|t| t as i64));
+    let last_contacts: Vec<i64> = column(batch, |(_, s)| s.last_contact as i64);
+    let longitudes: Vec<Option<f64>> = column(batch, |(_, s)| s.longitude);
+    let latitudes: Vec<Option<f64>> = column(batch, |(_, s)| s.latitude);
+    let baro_altitudes: Vec<Option<f32>> = column(batch, |(_, s)| s.baro_altitude);
+    let on_grounds: Vec<bool> = column(batch, |(_, s)| s.on_ground);
+    let velocities: Vec<Option<f32>> = column(batch, |(_, s)| s.velocity);
+    let true_tracks: Vec<Option<f32>> = column(batch, |(_, s)| s.true_track);
+    let vertical_rates: Vec<Option<f32>> = column(batch, |(_, s)| s.vertical_rate);
+    let sensors: Vec<Option<String>> = batch
+        .iter()
+        .map(|(_, s)| s.sensors.as_ref().map(serde_json::to_string).transpose())
+        .collect::<Result<_, _>>()?;
+    let geo_altitudes: Vec<Option<f32>> = column(batch, |(_, s)| s.geo_altitude);
+    let squawks: Vec<Option<String>> =
+        column(batch, |(_, s)| s.squawk.map(|squawk| squawk.to_string()));
+    let spis: Vec<bool> = column(batch, |(_, s)| s.spi);
+    let position_sources: Vec<i16> = column(batch, |(_, s)| s.position_source as i16);
+    let categories: Vec<Option<i32>> = column(batch, |(_, s)| s.category.map(|c| c as i32));
+
+    client
+        .execute(
+            INSERT,
+            &[
+                &times,
+                &icao24s,
+                &callsigns,
+                &countries,
+                &time_positions,
+                &last_contacts,
+                &longitudes,
+                &latitudes,
+                &baro_altitudes,
+                &on_grounds,
+                &velocities,
+                &true_tracks,
+                &vertical_rates,
+                &sensors,
+                &geo_altitudes,
+                &squawks,
+                &spis,
+                &position_sources,
+                &categories,
+            ],
+        )
+        .await?;
+
+    Ok(())
+}
+
+/// Extracts one column of the batch
+fn column<'a, T>(
+    batch: &'a [(u64, StateVector)],
+    f: impl Fn(&'a (u64, StateVector)) -> T,
+) -> Vec<T> {
+    batch.iter().map(f).collect()
+}
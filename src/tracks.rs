@@ -0,0 +1,432 @@
+use chrono::{DateTime, Utc};
+use futures_util::{stream, StreamExt};
+use serde::Deserialize;
+
+use crate::{
+    context::RequestContext, errors::Error, flights::Flight, policy::EndpointClass,
+    response::WithRaw, states::StateVector, time,
+};
+
+/// The default number of track requests a TrackBatchRequest sends at the same time
+pub const DEFAULT_TRACK_CONCURRENCY: usize = 4;
+
+/// The time in seconds an aircraft must remain on the ground for a track to be split into
+/// separate legs by FlightTrack::split_legs()
+///
+pub const DEFAULT_MIN_GROUND_TIME: u64 = 300;
+
+/// The largest gap in seconds between two waypoints of the same leg used by
+/// FlightTrack::split_legs()
+///
+pub const DEFAULT_MAX_GAP: u64 = 1800;
+
+/// The trajectory of a single aircraft, as returned by OpenSky's /tracks endpoint
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct FlightTrack {
+    pub icao24: String,
+    /// Time of the first waypoint in seconds since the Unix Epoch
+    #[serde(rename(deserialize = "startTime"))]
+    pub start_time: u64,
+    /// Time of the last waypoint in seconds since the Unix Epoch
+    #[serde(rename(deserialize = "endTime"))]
+    pub end_time: u64,
+    pub callsign: Option<String>,
+    pub path: Vec<Waypoint>,
+}
+
+/// A single point of a FlightTrack. OpenSky sends these as arrays, which is also the order of
+/// the fields here.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct Waypoint {
+    /// Time of the position in seconds since the Unix Epoch
+    pub time: u64,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    /// Barometric altitude in meters
+    pub baro_altitude: Option<f64>,
+    /// True track in decimal degrees clockwise from north
+    pub true_track: Option<f64>,
+    pub on_ground: bool,
+}
+
+impl Waypoint {
+    /// Returns the time of the position
+    pub fn datetime(&self) -> DateTime<Utc> {
+        time::datetime(self.time)
+    }
+}
+
+impl From<&StateVector> for Waypoint {
+    fn from(state: &StateVector) -> Self {
+        Self {
+            time: state.time_position.unwrap_or(state.last_contact),
+            latitude: state.latitude,
+            longitude: state.longitude,
+            baro_altitude: state.baro_altitude.map(f64::from),
+            true_track: state.true_track.map(f64::from),
+            on_ground: state.on_ground,
+        }
+    }
+}
+
+impl FlightTrack {
+    /// Creates a new empty FlightTrack for the given aircraft
+    pub fn new(icao24: String, callsign: Option<String>) -> Self {
+        Self {
+            icao24,
+            start_time: 0,
+            end_time: 0,
+            callsign,
+            path: Vec::new(),
+        }
+    }
+
+    /// Splits a track which covers more than one flight into one track per leg, using
+    /// DEFAULT_MIN_GROUND_TIME and DEFAULT_MAX_GAP. See split_legs_with().
+    ///
+    pub fn split_legs(&self) -> Vec<FlightTrack> {
+        self.split_legs_with(DEFAULT_MIN_GROUND_TIME, DEFAULT_MAX_GAP)
+    }
+
+    /// Splits a track which covers more than one flight into one track per leg.
+    ///
+    /// A track is cut wherever the aircraft stayed on the ground for at least min_ground_time
+    /// seconds, or wherever two consecutive waypoints are more than max_gap seconds apart. A
+    /// leg ends at the touchdown waypoint and the next leg starts at the last waypoint before
+    /// takeoff, so the time spent parked does not belong to either leg. The start and end
+    /// times of each leg are those of its first and last waypoint.
+    ///
+    /// Legs without any airborne waypoint are dropped, unless the whole track is on the ground,
+    /// in which case the track is returned unchanged.
+    ///
+    pub fn split_legs_with(&self, min_ground_time: u64, max_gap: u64) -> Vec<FlightTrack> {
+        let mut legs: Vec<Vec<Waypoint>> = vec![Vec::new()];
+        let mut index = 0;
+
+        while index < self.path.len() {
+            let waypoint = self.path[index];
+
+            if let Some(last) = legs.last().and_then(|leg| leg.last()) {
+                if waypoint.time.saturating_sub(last.time) > max_gap {
+                    legs.push(Vec::new());
+                }
+            }
+
+            if !waypoint.on_ground {
+                legs.last_mut().unwrap().push(waypoint);
+                index += 1;
+                continue;
+            }
+
+            // Find the end of this run of waypoints on the ground
+            let mut end = index;
+            while end + 1 < self.path.len() && self.path[end + 1].on_ground {
+                end += 1;
+            }
+
+            let left_ground_at = self
+                .path
+                .get(end + 1)
+                .map(|next| next.time)
+                .unwrap_or(self.path[end].time);
+
+            if left_ground_at.saturating_sub(waypoint.time) >= min_ground_time {
+                legs.last_mut().unwrap().push(waypoint);
+                legs.push(vec![self.path[end]]);
+            } else {
+                legs.last_mut()
+                    .unwrap()
+                    .extend_from_slice(&self.path[index..=end]);
+            }
+
+            index = end + 1;
+        }
+
+        let legs: Vec<FlightTrack> = legs
+            .into_iter()
+            .filter(|leg| leg.iter().any(|waypoint| !waypoint.on_ground))
+            .map(|path| FlightTrack {
+                icao24: self.icao24.clone(),
+                start_time: path.first().map(|w| w.time).unwrap_or(self.start_time),
+                end_time: path.last().map(|w| w.time).unwrap_or(self.end_time),
+                callsign: self.callsign.clone(),
+                path,
+            })
+            .collect();
+
+        if legs.is_empty() {
+            vec![self.clone()]
+        } else {
+            legs
+        }
+    }
+}
+
+/// Decides which positions become waypoints of a FlightTrack. Like OpenSky, a new waypoint is
+/// only added when the aircraft changed its track or altitude significantly, or took off or
+/// landed, so straight and level segments are represented by their end points.
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WaypointRules {
+    /// The change of true track in degrees since the last waypoint which creates a new one
+    pub track_threshold: f64,
+    /// The change of barometric altitude in meters since the last waypoint which creates a
+    /// new one
+    pub altitude_threshold: f64,
+    /// A waypoint is added at least this often in seconds, even if nothing changed
+    pub max_interval: u64,
+}
+
+impl Default for WaypointRules {
+    fn default() -> Self {
+        Self {
+            track_threshold: 2.5,
+            altitude_threshold: 30.0,
+            max_interval: 900,
+        }
+    }
+}
+
+impl WaypointRules {
+    /// Returns true if the position should be added to a path which ends with last
+    pub fn is_waypoint(&self, last: &Waypoint, position: &Waypoint) -> bool {
+        if position.on_ground != last.on_ground {
+            return true;
+        }
+
+        if position.time.saturating_sub(last.time) >= self.max_interval {
+            return true;
+        }
+
+        if let (Some(a), Some(b)) = (last.true_track, position.true_track) {
+            let change = (b - a).rem_euclid(360.0);
+
+            if change.min(360.0 - change) >= self.track_threshold {
+                return true;
+            }
+        }
+
+        if let (Some(a), Some(b)) = (last.baro_altitude, position.baro_altitude) {
+            if (b - a).abs() >= self.altitude_threshold {
+                return true;
+            }
+        }
+
+        false
+    }
+}
+
+/// Appends live state vectors to a FlightTrack, selecting waypoints using WaypointRules
+#[derive(Debug, Clone)]
+pub struct FlightTrackBuilder {
+    track: FlightTrack,
+    rules: WaypointRules,
+    latest: Option<Waypoint>,
+}
+
+impl FlightTrackBuilder {
+    /// Creates a new FlightTrackBuilder for the given aircraft, starting with an empty track
+    pub fn new(icao24: String) -> Self {
+        Self::from_track(FlightTrack::new(icao24, None))
+    }
+
+    /// Creates a new FlightTrackBuilder which continues an existing track, for example one
+    /// returned by the API
+    ///
+    pub fn from_track(track: FlightTrack) -> Self {
+        Self {
+            track,
+            rules: WaypointRules::default(),
+            latest: None,
+        }
+    }
+
+    /// Sets the rules used to decide which positions become waypoints
+    pub fn with_rules(mut self, rules: WaypointRules) -> Self {
+        self.rules = rules;
+
+        self
+    }
+
+    /// Adds the position of the state vector to the track. State vectors of other aircraft,
+    /// without a position, or without a newer position than the end of the track are ignored.
+    ///
+    /// Returns true if the position was added as a new waypoint.
+    ///
+    pub fn push(&mut self, state: &StateVector) -> bool {
+        if !state.icao24.eq_ignore_ascii_case(&self.track.icao24) {
+            return false;
+        }
+
+        let position = Waypoint::from(state);
+
+        if position.latitude.is_none() || position.longitude.is_none() {
+            return false;
+        }
+
+        let latest_time =
+            self.latest
+                .map(|latest| latest.time)
+                .or(self.track.path.last().map(|last| last.time));
+
+        if latest_time.is_some_and(|time| position.time <= time) {
+            return false;
+        }
+
+        if self.track.callsign.is_none() {
+            self.track.callsign = state.callsign.clone();
+        }
+
+        let added = match self.track.path.last() {
+            Some(last) => self.rules.is_waypoint(last, &position),
+            None => {
+                self.track.start_time = position.time;
+                true
+            }
+        };
+
+        if added {
+            self.track.path.push(position);
+            self.latest = None;
+        } else {
+            self.latest = Some(position);
+        }
+
+        self.track.end_time = position.time;
+
+        added
+    }
+
+    /// Returns the track built so far, which only contains the selected waypoints
+    pub fn track(&self) -> &FlightTrack {
+        &self.track
+    }
+
+    /// Returns the finished track. The most recent position is always the last waypoint of
+    /// the track, even if it would not have been selected as a waypoint on its own.
+    ///
+    pub fn finish(mut self) -> FlightTrack {
+        if let Some(latest) = self.latest {
+            self.track.path.push(latest);
+        }
+
+        self.track
+    }
+}
+
+/// Gets the track of an aircraft from OpenSky's /tracks endpoint. OpenSky returns the track of
+/// the flight the aircraft was on at the given time, which may be any time between the start
+/// and end of the flight.
+///
+#[derive(Debug, Clone)]
+pub struct TrackRequest {
+    context: RequestContext,
+    icao24: String,
+    time: u64,
+}
+
+impl TrackRequest {
+    /// Creates a new TrackRequest for the aircraft with the ICAO24 address at the time in
+    /// seconds since the Unix Epoch
+    ///
+    pub fn new(context: RequestContext, icao24: String, time: u64) -> Self {
+        Self {
+            context,
+            icao24: icao24.trim().to_lowercase(),
+            time,
+        }
+    }
+
+    /// Creates a new TrackRequest for the track of the flight. The middle of the time the
+    /// aircraft was seen is used, so the request does not depend on the exact start or end of
+    /// the track.
+    ///
+    pub fn for_flight(context: RequestContext, flight: &Flight) -> Self {
+        let time = flight.first_seen + flight.duration() / 2;
+
+        Self::new(context, flight.icao24.clone(), time)
+    }
+
+    /// Renders this request as an equivalent curl command, for debugging and bug reports. The
+    /// password is masked.
+    ///
+    pub fn to_curl(&self) -> String {
+        self.context.curl(&self.url(), false)
+    }
+
+    /// Renders this request as an equivalent curl command, including the password
+    pub fn to_curl_with_credentials(&self) -> String {
+        self.context.curl(&self.url(), true)
+    }
+
+    fn url(&self) -> String {
+        let args = format!("?icao24={}&time={}", self.icao24, self.time);
+
+        self.context.url(&self.context.endpoints().tracks, &args)
+    }
+
+    /// Sends the request. Returns None if OpenSky has no track of the aircraft at that time.
+    pub async fn send(&self) -> Result<Option<FlightTrack>, Error> {
+        self.send_keep_raw().await.map(WithRaw::into_parsed)
+    }
+
+    /// Sends the request like send(), and keeps the original response body next to the
+    /// parsed track
+    ///
+    pub async fn send_keep_raw(&self) -> Result<WithRaw<Option<FlightTrack>>, Error> {
+        let (status, bytes) = self.context.get(EndpointClass::Tracks, &self.url()).await?;
+
+        let parsed = match status {
+            reqwest::StatusCode::OK => Some(self.context.observe_parse(
+                EndpointClass::Tracks,
+                serde_json::from_slice(&bytes).map_err(Error::from),
+            )?),
+            reqwest::StatusCode::NOT_FOUND => None,
+            status => return Err(Error::Http(status)),
+        };
+
+        Ok(WithRaw { parsed, raw: bytes })
+    }
+}
+
+/// Gets the tracks of many flights, with a bounded number of requests in flight
+#[derive(Debug, Clone)]
+pub struct TrackBatchRequest {
+    requests: Vec<TrackRequest>,
+    concurrency: usize,
+}
+
+impl TrackBatchRequest {
+    pub fn new(context: RequestContext, flights: &[Flight]) -> Self {
+        Self {
+            requests: flights
+                .iter()
+                .map(|flight| TrackRequest::for_flight(context.clone(), flight))
+                .collect(),
+            concurrency: DEFAULT_TRACK_CONCURRENCY,
+        }
+    }
+
+    /// Sets the maximum number of track requests that are sent at the same time
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+
+        self
+    }
+
+    /// Gets the tracks of all flights, in the order of the flights. Flights without a track
+    /// are None.
+    ///
+    /// If any request fails, the first error is returned.
+    ///
+    pub async fn send(&self) -> Result<Vec<Option<FlightTrack>>, Error> {
+        let results: Vec<Result<Option<FlightTrack>, Error>> = stream::iter(&self.requests)
+            .map(TrackRequest::send)
+            .buffered(self.concurrency)
+            .collect()
+            .await;
+
+        results.into_iter().collect()
+    }
+}
@@ -0,0 +1,110 @@
+//! The layer which sends HTTP requests, which can be replaced for offline testing.
+//!
+//! By default, requests are sent using the reqwest::Client of the OpenSkyApi instance.
+//! OpenSkyApi::with_transport() replaces it with any other Transport, e.g. a CannedTransport
+//! which answers with fixed responses, so that code using this crate can be tested without
+//! network access. Requests for OAuth2 access tokens always use the reqwest::Client.
+
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+use futures_util::future::BoxFuture;
+
+use crate::errors::Error;
+
+/// Sends a request and returns the response. Authentication headers, the User-Agent, and the
+/// timeout have already been applied to the request, and middleware is called before and
+/// after the transport.
+///
+pub trait Transport: Send + Sync {
+    fn execute(&self, request: reqwest::Request)
+        -> BoxFuture<'_, Result<reqwest::Response, Error>>;
+}
+
+impl Transport for reqwest::Client {
+    fn execute(
+        &self,
+        request: reqwest::Request,
+    ) -> BoxFuture<'_, Result<reqwest::Response, Error>> {
+        Box::pin(async move { Ok(reqwest::Client::execute(self, request).await?) })
+    }
+}
+
+/// A shared Transport, which does not need to implement Debug
+#[derive(Clone)]
+pub(crate) struct SharedTransport(pub(crate) Arc<dyn Transport>);
+
+impl fmt::Debug for SharedTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SharedTransport")
+    }
+}
+
+/// Builds a response with the given status code and body, e.g. for a custom Transport
+pub fn response(status: u16, body: impl Into<String>) -> reqwest::Response {
+    let response = http::Response::builder()
+        .status(status)
+        .body(body.into())
+        .expect("a response with only a status and a body is valid");
+
+    reqwest::Response::from(response)
+}
+
+/// A Transport which answers requests with fixed responses by the path of the URL, and
+/// records the URLs of all requests. Requests to paths without a response are answered with
+/// HTTP 404. Clones share the same responses and records.
+///
+#[derive(Debug, Clone, Default)]
+pub struct CannedTransport {
+    responses: Arc<Mutex<HashMap<String, (u16, String)>>>,
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl CannedTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answers requests to the path (e.g. "/api/states/all", without the query) with the
+    /// given status code and body
+    ///
+    pub fn with_response(self, path: &str, status: u16, body: impl Into<String>) -> Self {
+        self.responses
+            .lock()
+            .unwrap()
+            .insert(path.to_string(), (status, body.into()));
+
+        self
+    }
+
+    /// Returns the URLs of all requests received so far
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+impl Transport for CannedTransport {
+    fn execute(
+        &self,
+        request: reqwest::Request,
+    ) -> BoxFuture<'_, Result<reqwest::Response, Error>> {
+        self.requests
+            .lock()
+            .unwrap()
+            .push(request.url().to_string());
+
+        let canned = self
+            .responses
+            .lock()
+            .unwrap()
+            .get(request.url().path())
+            .cloned();
+
+        let (status, body) = canned.unwrap_or((404, String::new()));
+
+        Box::pin(async move { Ok(response(status, body)) })
+    }
+}
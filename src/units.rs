@@ -0,0 +1,38 @@
+//! Conversions between the SI units used by OpenSky and the units common in aviation.
+
+/// Feet in one meter
+pub const FEET_PER_METER: f64 = 3.280_84;
+/// Knots in one meter per second
+pub const KNOTS_PER_MPS: f64 = 3600.0 / 1852.0;
+/// Feet per minute in one meter per second
+pub const FPM_PER_MPS: f64 = FEET_PER_METER * 60.0;
+
+/// Converts meters to feet
+pub fn meters_to_feet(meters: f64) -> f64 {
+    meters * FEET_PER_METER
+}
+
+/// Converts feet to meters
+pub fn feet_to_meters(feet: f64) -> f64 {
+    feet / FEET_PER_METER
+}
+
+/// Converts meters per second to knots
+pub fn mps_to_knots(mps: f64) -> f64 {
+    mps * KNOTS_PER_MPS
+}
+
+/// Converts knots to meters per second
+pub fn knots_to_mps(knots: f64) -> f64 {
+    knots / KNOTS_PER_MPS
+}
+
+/// Converts meters per second to feet per minute
+pub fn mps_to_fpm(mps: f64) -> f64 {
+    mps * FPM_PER_MPS
+}
+
+/// Converts feet per minute to meters per second
+pub fn fpm_to_mps(fpm: f64) -> f64 {
+    fpm / FPM_PER_MPS
+}
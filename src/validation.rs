@@ -0,0 +1,203 @@
+//! Plausibility checks for state vectors.
+//!
+//! OpenSky passes on what receivers decode, which occasionally includes positions outside of
+//! the valid coordinate range, absurd altitudes, or timestamps in the future. A set of
+//! Validators can be attached to a StateRequest to remove such state vectors from the
+//! response, and to report which ones were removed and why.
+
+use std::{fmt, sync::Arc};
+
+use crate::states::{StateVector, States};
+
+/// A rule which decides whether a state vector is plausible
+pub trait Validator: Send + Sync {
+    /// Returns a description of the problem if the state vector is invalid. snapshot_time is
+    /// the time of the States the state vector belongs to.
+    ///
+    fn check(&self, state: &StateVector, snapshot_time: u64) -> Option<String>;
+}
+
+impl<F> Validator for F
+where
+    F: Fn(&StateVector, u64) -> Option<String> + Send + Sync,
+{
+    fn check(&self, state: &StateVector, snapshot_time: u64) -> Option<String> {
+        self(state, snapshot_time)
+    }
+}
+
+/// Rejects latitudes outside of ±90 and longitudes outside of ±180 degrees
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CoordinateRange;
+
+impl Validator for CoordinateRange {
+    fn check(&self, state: &StateVector, _snapshot_time: u64) -> Option<String> {
+        if let Some(latitude) = state.latitude {
+            if !(-90.0..=90.0).contains(&latitude) {
+                return Some(format!("latitude {} is out of range", latitude));
+            }
+        }
+
+        if let Some(longitude) = state.longitude {
+            if !(-180.0..=180.0).contains(&longitude) {
+                return Some(format!("longitude {} is out of range", longitude));
+            }
+        }
+
+        None
+    }
+}
+
+/// Rejects barometric and geometric altitudes outside of the given range in meters
+#[derive(Debug, Clone, Copy)]
+pub struct AltitudeRange {
+    pub min: f32,
+    pub max: f32,
+}
+
+impl Default for AltitudeRange {
+    /// Allows altitudes from below the Dead Sea shore up to those of high-altitude balloons
+    fn default() -> Self {
+        Self {
+            min: -1000.0,
+            max: 30000.0,
+        }
+    }
+}
+
+impl Validator for AltitudeRange {
+    fn check(&self, state: &StateVector, _snapshot_time: u64) -> Option<String> {
+        [
+            ("barometric", state.baro_altitude),
+            ("geometric", state.geo_altitude),
+        ]
+        .into_iter()
+        .find_map(|(kind, altitude)| {
+            let altitude = altitude?;
+
+            (!(self.min..=self.max).contains(&altitude))
+                .then(|| format!("{} altitude {} m is out of range", kind, altitude))
+        })
+    }
+}
+
+/// Rejects state vectors whose position or last contact is more than tolerance seconds after
+/// the time of the snapshot
+///
+#[derive(Debug, Clone, Copy)]
+pub struct FutureTimestamps {
+    pub tolerance: u64,
+}
+
+impl Default for FutureTimestamps {
+    fn default() -> Self {
+        Self { tolerance: 60 }
+    }
+}
+
+impl Validator for FutureTimestamps {
+    fn check(&self, state: &StateVector, snapshot_time: u64) -> Option<String> {
+        let latest = state
+            .time_position
+            .unwrap_or(state.last_contact)
+            .max(state.last_contact);
+
+        (latest > snapshot_time.saturating_add(self.tolerance)).then(|| {
+            format!(
+                "timestamp {} is after the snapshot time {}",
+                latest, snapshot_time
+            )
+        })
+    }
+}
+
+/// A set of Validators which are applied one after the other
+#[derive(Clone, Default)]
+pub struct Validators {
+    rules: Vec<Arc<dyn Validator>>,
+}
+
+impl fmt::Debug for Validators {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Validators")
+            .field("rules", &self.rules.len())
+            .finish()
+    }
+}
+
+impl Validators {
+    /// Creates an empty set of Validators, which accepts every state vector
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates the default rule set: CoordinateRange, AltitudeRange, and FutureTimestamps,
+    /// each with their default settings
+    ///
+    pub fn default_rules() -> Self {
+        Self::new()
+            .with_rule(CoordinateRange)
+            .with_rule(AltitudeRange::default())
+            .with_rule(FutureTimestamps::default())
+    }
+
+    /// Adds a rule, which can be any Validator or a closure taking the state vector and the
+    /// snapshot time
+    ///
+    pub fn with_rule<V: Validator + 'static>(mut self, rule: V) -> Self {
+        self.rules.push(Arc::new(rule));
+
+        self
+    }
+
+    /// Returns the reason of the first rule that rejects the state vector, or None if the
+    /// state vector passes all rules
+    ///
+    pub fn check(&self, state: &StateVector, snapshot_time: u64) -> Option<String> {
+        self.rules
+            .iter()
+            .find_map(|rule| rule.check(state, snapshot_time))
+    }
+
+    /// Removes every invalid state vector from states, and returns them in a report
+    pub fn apply(&self, states: &mut States) -> ValidationReport {
+        let time = states.time;
+        let mut rejected = Vec::new();
+
+        states.states.retain(|state| match self.check(state, time) {
+            Some(reason) => {
+                rejected.push(Rejection {
+                    state: state.clone(),
+                    reason,
+                });
+                false
+            }
+            None => true,
+        });
+
+        ValidationReport { rejected }
+    }
+}
+
+/// A state vector that was removed by a Validator
+#[derive(Debug, Clone)]
+pub struct Rejection {
+    pub state: StateVector,
+    pub reason: String,
+}
+
+/// The state vectors that were removed from a response because they failed validation
+#[derive(Debug, Clone, Default)]
+pub struct ValidationReport {
+    pub rejected: Vec<Rejection>,
+}
+
+impl ValidationReport {
+    pub fn len(&self) -> usize {
+        self.rejected.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rejected.is_empty()
+    }
+}
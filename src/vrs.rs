@@ -0,0 +1,130 @@
+//! Serialization of States snapshots into the `AircraftList.json` format used by Virtual
+//! Radar Server, so that existing VRS frontends can display data from OpenSky.
+
+use serde::Serialize;
+
+use crate::states::{StateVector, States};
+
+/// The top level object of a VRS `AircraftList.json` response
+#[derive(Debug, Clone, Serialize)]
+pub struct AircraftList {
+    #[serde(rename = "acList")]
+    pub aircraft: Vec<Aircraft>,
+    #[serde(rename = "totalAc")]
+    pub total_aircraft: usize,
+    /// The source of the list, 1 means a live feed
+    #[serde(rename = "src")]
+    pub source: u8,
+    /// The time of the snapshot in milliseconds since the Unix Epoch
+    #[serde(rename = "stm")]
+    pub server_time: u64,
+    /// Whether the list is a full list or only contains changes
+    #[serde(rename = "showFlg")]
+    pub show_flags: bool,
+}
+
+/// A single aircraft in a VRS aircraft list. All units follow VRS: feet, knots, and feet per
+/// minute.
+///
+#[derive(Debug, Clone, Serialize)]
+pub struct Aircraft {
+    #[serde(rename = "Id")]
+    pub id: u32,
+    #[serde(rename = "Icao")]
+    pub icao: String,
+    #[serde(rename = "Call", skip_serializing_if = "Option::is_none")]
+    pub callsign: Option<String>,
+    #[serde(rename = "Lat", skip_serializing_if = "Option::is_none")]
+    pub latitude: Option<f64>,
+    #[serde(rename = "Long", skip_serializing_if = "Option::is_none")]
+    pub longitude: Option<f64>,
+    /// The time of the last position update in milliseconds since the Unix Epoch
+    #[serde(rename = "PosTime", skip_serializing_if = "Option::is_none")]
+    pub position_time: Option<u64>,
+    #[serde(rename = "Alt", skip_serializing_if = "Option::is_none")]
+    pub altitude: Option<i32>,
+    #[serde(rename = "GAlt", skip_serializing_if = "Option::is_none")]
+    pub geometric_altitude: Option<i32>,
+    #[serde(rename = "Spd", skip_serializing_if = "Option::is_none")]
+    pub speed: Option<f32>,
+    /// The speed type, 0 means ground speed
+    #[serde(rename = "SpdTyp")]
+    pub speed_type: u8,
+    #[serde(rename = "Trak", skip_serializing_if = "Option::is_none")]
+    pub track: Option<f32>,
+    #[serde(rename = "Vsi", skip_serializing_if = "Option::is_none")]
+    pub vertical_speed: Option<i32>,
+    #[serde(rename = "Gnd")]
+    pub on_ground: bool,
+    #[serde(rename = "Sqk", skip_serializing_if = "Option::is_none")]
+    pub squawk: Option<String>,
+    /// Whether the aircraft is squawking an emergency code (7500, 7600, or 7700)
+    #[serde(rename = "Help")]
+    pub help: bool,
+    #[serde(rename = "Cou")]
+    pub country: String,
+    /// Whether the position was determined by multilateration
+    #[serde(rename = "Mlat")]
+    pub mlat: bool,
+    /// Whether the aircraft is transmitting the special position indicator
+    #[serde(rename = "SpclPos")]
+    pub spi: bool,
+}
+
+impl From<&StateVector> for Aircraft {
+    fn from(state: &StateVector) -> Self {
+        let squawk = state.squawk.map(|squawk| squawk.to_string());
+        let help = state.squawk.is_some_and(|squawk| squawk.is_any_emergency());
+
+        Self {
+            id: u32::from_str_radix(state.icao24.trim(), 16).unwrap_or_default(),
+            icao: state.icao24.trim().to_uppercase(),
+            callsign: state
+                .callsign
+                .as_deref()
+                .map(str::trim)
+                .filter(|callsign| !callsign.is_empty())
+                .map(String::from),
+            latitude: state.latitude,
+            longitude: state.longitude,
+            position_time: state.time_position.map(|time| time * 1000),
+            altitude: state
+                .baro_altitude_ft()
+                .map(|altitude| altitude.round() as i32),
+            geometric_altitude: state
+                .geo_altitude_ft()
+                .map(|altitude| altitude.round() as i32),
+            speed: state.velocity_knots(),
+            speed_type: 0,
+            track: state.true_track,
+            vertical_speed: state.vertical_rate_fpm().map(|rate| rate.round() as i32),
+            on_ground: state.on_ground,
+            squawk,
+            help,
+            country: state.origin_country.clone(),
+            mlat: state.position_source == 2,
+            spi: state.spi,
+        }
+    }
+}
+
+impl From<&States> for AircraftList {
+    fn from(states: &States) -> Self {
+        let aircraft: Vec<Aircraft> = states.states.iter().map(Aircraft::from).collect();
+
+        Self {
+            total_aircraft: aircraft.len(),
+            aircraft,
+            source: 1,
+            server_time: states.time * 1000,
+            show_flags: true,
+        }
+    }
+}
+
+impl States {
+    /// Serializes this snapshot into a Virtual Radar Server `AircraftList.json` document
+    pub fn to_vrs_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(&AircraftList::from(self))
+    }
+}
@@ -0,0 +1,40 @@
+#![cfg(feature = "aircraft-db")]
+
+use opensky_api::{aircraft_db::AircraftDatabase, OpenSkyApi};
+
+const DATABASE: &str =
+    "'icao24','registration','manufacturerName','model','typecode','operatorIcao'
+'3c6444','D-AIBD','Airbus','A319 112','A319','DLH'
+'3c6586','D-AIPF','Airbus','A320 211','A320','DLH'
+'a0b1c2','','','','',''
+";
+
+#[test]
+fn loads_and_indexes_database() {
+    let database = AircraftDatabase::from_reader(DATABASE.as_bytes()).unwrap();
+
+    assert_eq!(database.len(), 3);
+    assert_eq!(
+        database.get("3C6444").unwrap().typecode.as_deref(),
+        Some("A319")
+    );
+    assert_eq!(database.get("a0b1c2").unwrap().registration, None);
+    assert_eq!(database.by_registration("daibd").unwrap().icao24, "3c6444");
+    assert_eq!(database.by_operator_icao("dlh").len(), 2);
+    assert_eq!(database.by_typecode("A320").len(), 1);
+}
+
+#[tokio::test]
+async fn metadata_requests_use_database() {
+    let database = AircraftDatabase::from_reader(DATABASE.as_bytes()).unwrap();
+    let opensky_api = OpenSkyApi::new().with_aircraft_database(database);
+
+    let metadata = opensky_api
+        .get_aircraft_metadata(String::from("3c6586"))
+        .send()
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(metadata.registration.as_deref(), Some("D-AIPF"));
+}
@@ -0,0 +1,88 @@
+use opensky_api::{
+    airspace::{Airspaces, Geofence},
+    states::States,
+};
+
+const GEOJSON: &str = r#"{"type": "FeatureCollection", "features": [
+    {"type": "Feature", "properties": {"name": "EDGG", "upper": 7500.0},
+        "geometry": {"type": "Polygon", "coordinates": [
+            [[7.0, 49.0], [11.0, 49.0], [11.0, 51.0], [7.0, 51.0], [7.0, 49.0]],
+            [[8.4, 49.9], [8.7, 49.9], [8.7, 50.1], [8.4, 50.1], [8.4, 49.9]]]}},
+    {"type": "Feature", "properties": {"name": "EDUU", "lower": 7500.0},
+        "geometry": {"type": "MultiPolygon", "coordinates": [
+            [[[7.0, 49.0], [11.0, 49.0], [11.0, 51.0], [7.0, 51.0], [7.0, 49.0]]]]}},
+    {"type": "Feature", "properties": {"name": "Fix"},
+        "geometry": {"type": "Point", "coordinates": [8.0, 50.0]}}]}"#;
+
+fn states() -> States {
+    serde_json::from_str(
+        r#"{"time": 1000, "states": [
+            ["000001", null, "Germany", 995, 998, 9.0, 50.5, 3000.0, false,
+                200.0, 90.0, 0.0, null, null, null, false, 0],
+            ["000002", null, "Germany", 995, 998, 9.0, 50.5, 11000.0, false,
+                200.0, 90.0, 0.0, null, null, null, false, 0],
+            ["000003", null, "Germany", 995, 998, 8.57, 50.03, null, true,
+                0.0, 90.0, 0.0, null, null, null, false, 0],
+            ["000004", null, "France", 995, 998, 2.5, 49.0, 3000.0, false,
+                200.0, 90.0, 0.0, null, null, null, false, 0]]}"#,
+    )
+    .unwrap()
+}
+
+#[test]
+fn loads_polygon_features() {
+    let airspaces = Airspaces::from_geojson(GEOJSON).unwrap();
+
+    assert_eq!(airspaces.airspaces.len(), 2);
+    assert_eq!(airspaces.airspaces[0].polygons[0].holes.len(), 1);
+    assert_eq!(airspaces.airspaces[1].lower, Some(7500.0));
+
+    assert!(Airspaces::from_geojson(r#"{"type": "Point"}"#).is_err());
+}
+
+#[test]
+fn tags_states_by_airspace() {
+    let airspaces = Airspaces::from_geojson(GEOJSON).unwrap();
+    let states = states();
+
+    assert_eq!(airspaces.tag_state(&states.states[0]), ["EDGG"]);
+    assert_eq!(airspaces.tag_state(&states.states[1]), ["EDUU"]);
+    // Inside the hole of EDGG
+    assert!(airspaces.tag_state(&states.states[2]).is_empty());
+    assert!(airspaces.tag_state(&states.states[3]).is_empty());
+
+    let occupancy = airspaces.occupancy(&states);
+    assert_eq!(occupancy["EDGG"], 1);
+    assert_eq!(occupancy["EDUU"], 1);
+}
+
+#[test]
+fn filters_states_by_geofence() {
+    let states = states();
+    let icao24s = |states: &States| -> Vec<String> {
+        states.iter().map(|state| state.icao24.clone()).collect()
+    };
+
+    // A triangle around the first three aircraft, as (latitude, longitude) vertices
+    let triangle = Geofence::new(&[(49.5, 8.0), (51.5, 8.0), (50.5, 10.5)]);
+    assert_eq!(
+        icao24s(&states.within(&triangle)),
+        ["000001", "000002", "000003"]
+    );
+
+    // The polygons of both features are combined, so the aircraft in the hole of EDGG is
+    // still within the polygon of EDUU
+    let geofence = Geofence::from_geojson(GEOJSON).unwrap();
+    assert_eq!(geofence.polygons.len(), 2);
+    assert_eq!(
+        icao24s(&states.within(&geofence)),
+        ["000001", "000002", "000003"]
+    );
+
+    let geometry = r#"{"type": "Polygon", "coordinates":
+        [[[2.0, 48.5], [3.0, 48.5], [3.0, 49.5], [2.0, 49.5], [2.0, 48.5]]]}"#;
+    let geofence = Geofence::from_geojson(geometry).unwrap();
+    assert_eq!(icao24s(&states.within(&geofence)), ["000004"]);
+
+    assert!(Geofence::from_geojson(r#"{"type": "Point"}"#).is_err());
+}
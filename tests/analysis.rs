@@ -0,0 +1,61 @@
+use opensky_api::{bounding_box::BoundingBox, states::States};
+
+fn states() -> States {
+    serde_json::from_str(
+        r#"{"time": 1000, "states": [
+            ["000001", null, "Germany", 995, 998, 8.5, 50.0, 10000.0, false,
+                200.0, 90.0, 0.0, null, 10100.0, null, false, 0],
+            ["000002", null, "Germany", 995, 998, 8.6, 50.1, 9000.0, false,
+                200.0, 90.0, 0.0, null, 9110.0, null, false, 0],
+            ["000003", null, "Germany", 995, 998, 8.7, 50.2, 8000.0, false,
+                200.0, 90.0, 0.0, null, 8090.0, null, false, 0],
+            ["000004", null, "Germany", 995, 998, 8.8, 50.3, 7000.0, false,
+                200.0, 90.0, 0.0, null, 7100.0, null, false, 0],
+            ["000005", null, "Germany", 995, 998, 8.9, 50.4, 6000.0, false,
+                200.0, 90.0, 0.0, null, 7000.0, null, false, 0],
+            ["000006", null, "Germany", 995, 998, 8.9, 50.4, 100.0, true,
+                0.0, 90.0, 0.0, null, 500.0, null, false, 0],
+            ["000007", null, "France", 995, 998, 2.5, 49.0, 6000.0, false,
+                200.0, 90.0, 0.0, null, 6050.0, null, false, 0]]}"#,
+    )
+    .unwrap()
+}
+
+#[test]
+fn estimates_altitude_deviation() {
+    let deviation = states().altitude_deviation().unwrap();
+
+    // The aircraft 900 m and 50 m off are outliers, the one on the ground is not a sample
+    assert_eq!(deviation.samples, 4);
+    assert_eq!(deviation.outliers, 2);
+    assert_eq!(deviation.median, 100.0);
+    assert!((deviation.mean - 100.0).abs() < 1e-9);
+}
+
+#[test]
+fn estimates_altitude_deviation_in_region() {
+    let bbox = BoundingBox::new(49.5, 51.0, 8.0, 9.0);
+    let deviation = states().altitude_deviation_in(&bbox).unwrap();
+
+    assert_eq!(deviation.samples + deviation.outliers, 5);
+    assert_eq!(deviation.mean, 100.0);
+
+    let empty = BoundingBox::new(0.0, 1.0, 0.0, 1.0);
+    assert!(states().altitude_deviation_in(&empty).is_none());
+}
+
+#[test]
+fn summarizes_snapshot() {
+    let stats = states().stats();
+
+    assert_eq!(stats.total, 7);
+    assert_eq!((stats.on_ground, stats.airborne), (1, 6));
+    assert_eq!(stats.by_country["Germany"], 6);
+    assert_eq!(stats.by_country["France"], 1);
+    assert_eq!(stats.by_category[&None], 7);
+    assert_eq!(stats.altitude_bands[&6000], 2);
+    assert_eq!(stats.altitude_bands[&10000], 1);
+    assert_eq!(stats.altitude_bands.values().sum::<usize>(), 6);
+    assert_eq!(stats.max_velocity, Some(200.0));
+    assert!((stats.mean_velocity.unwrap() - 1200.0 / 7.0).abs() < 1e-9);
+}
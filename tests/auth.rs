@@ -0,0 +1,30 @@
+mod common;
+
+use opensky_api::{auth::OAuth2Credentials, OpenSkyApi};
+
+fn token(number: usize) -> (u16, &'static str, String) {
+    (
+        200,
+        "Content-Type: application/json\r\n",
+        format!(
+            r#"{{"access_token": "token{}", "expires_in": 1800}}"#,
+            number
+        ),
+    )
+}
+
+#[tokio::test]
+async fn force_refresh_obtains_new_tokens() {
+    let server = common::serve(vec![token(0), token(1)]).await;
+    let credentials = OAuth2Credentials::new(String::from("client"), String::from("secret"))
+        .with_token_url(format!("{}/token", server.url));
+    let opensky_api = OpenSkyApi::with_oauth2_credentials(credentials);
+
+    opensky_api.force_refresh().await.unwrap();
+    opensky_api.force_refresh().await.unwrap();
+
+    assert_eq!(server.hits(), 2);
+
+    // Instances without OAuth2 have nothing to refresh
+    OpenSkyApi::new().force_refresh().await.unwrap();
+}
@@ -0,0 +1,28 @@
+#![cfg(feature = "blocking")]
+
+mod common;
+
+use opensky_api::OpenSkyApi;
+
+const STATES: &str = r#"{"time": 1000, "states": [["3c6444", "DLH9LF  ", "Germany", 995, 998,
+    8.57, 50.03, 10000.0, false, 200.0, 90.0, 0.0, null, null, null, false, 0]]}"#;
+
+#[test]
+fn sends_requests_without_runtime() {
+    // The mock server needs a runtime of its own, which keeps running on its worker threads
+    let server_runtime = tokio::runtime::Runtime::new().unwrap();
+    let server = server_runtime.block_on(common::serve(vec![(
+        200,
+        "Content-Type: application/json\r\n",
+        STATES.to_string(),
+    )]));
+
+    let opensky_api = OpenSkyApi::new().with_base_url(&server.url).blocking();
+
+    let states = opensky_api.get_states().at_time(1000).send().unwrap();
+    assert_eq!(states.states.len(), 1);
+
+    let request = opensky_api.get_states().finish();
+    assert_eq!(request.send().unwrap().time, 1000);
+    assert_eq!(server.hits(), 2);
+}
@@ -0,0 +1,73 @@
+#![cfg(feature = "analytics")]
+
+use opensky_api::{
+    clustering::{resample, TrackClustering, TrackDistance},
+    tracks::{FlightTrack, Waypoint},
+};
+
+fn track(points: &[(f64, f64)]) -> FlightTrack {
+    let mut track = FlightTrack::new(String::from("3c6444"), None);
+
+    track.path = points
+        .iter()
+        .enumerate()
+        .map(|(i, &(latitude, longitude))| Waypoint {
+            time: 1000 + 60 * i as u64,
+            latitude: Some(latitude),
+            longitude: Some(longitude),
+            baro_altitude: Some(10000.0),
+            true_track: None,
+            on_ground: false,
+        })
+        .collect();
+
+    track
+}
+
+fn tracks() -> Vec<FlightTrack> {
+    vec![
+        // Eastbound flow
+        track(&[(50.0, 8.0), (50.0, 9.0), (50.0, 10.0)]),
+        track(&[(50.1, 8.0), (50.1, 10.0)]),
+        track(&[(49.9, 8.0), (49.9, 8.5), (49.9, 9.5), (49.9, 10.0)]),
+        // Northbound flow
+        track(&[(48.0, 12.0), (49.0, 12.0), (50.0, 12.0)]),
+        track(&[(48.0, 12.1), (50.0, 12.1)]),
+        // Not enough positions to be clustered
+        track(&[(48.0, 12.1)]),
+    ]
+}
+
+#[test]
+fn resamples_evenly() {
+    let points = resample(&track(&[(0.0, 0.0), (0.0, 1.0), (0.0, 4.0)]), 5).unwrap();
+
+    let longitudes: Vec<f64> = points.iter().map(|point| point.1).collect();
+    for (longitude, expected) in longitudes.iter().zip([0.0, 1.0, 2.0, 3.0, 4.0]) {
+        assert!((longitude - expected).abs() < 1e-6);
+    }
+}
+
+#[test]
+fn clusters_flows() {
+    for metric in [TrackDistance::Dtw, TrackDistance::Pointwise] {
+        let tracks = tracks();
+        let clusters = TrackClustering::new(2).with_metric(metric).cluster(&tracks);
+
+        assert_eq!(clusters.len(), 2);
+        assert_eq!(clusters.assignments[5], None);
+
+        let eastbound = clusters.assignments[0].unwrap();
+        let northbound = clusters.assignments[3].unwrap();
+        assert_ne!(eastbound, northbound);
+
+        assert_eq!(clusters.members(eastbound), [0, 1, 2]);
+        assert_eq!(clusters.members(northbound), [3, 4]);
+
+        // The middle eastbound track is the most representative one
+        assert!(clusters
+            .medoid_tracks(&tracks)
+            .iter()
+            .any(|medoid| medoid.path[0].latitude == Some(50.0)));
+    }
+}
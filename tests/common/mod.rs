@@ -0,0 +1,86 @@
+//! A minimal HTTP server for tests, which answers every request with the next of a fixed list
+//! of responses and records the requests it received.
+
+#![allow(dead_code)]
+
+use std::sync::{Arc, Mutex};
+
+use opensky_api::flights::Flight;
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+};
+
+pub struct MockServer {
+    pub url: String,
+    pub requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl MockServer {
+    /// Returns the number of requests received so far
+    pub fn hits(&self) -> usize {
+        self.requests.lock().unwrap().len()
+    }
+}
+
+/// Starts a server on localhost. Every response is a status code, extra header lines, and a
+/// body. Once all responses are used up, the last one is repeated.
+///
+pub async fn serve(responses: Vec<(u16, &'static str, String)>) -> MockServer {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let received = requests.clone();
+
+    tokio::spawn(async move {
+        for index in 0.. {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buffer = vec![0; 16384];
+            let read = socket.read(&mut buffer).await.unwrap();
+
+            received
+                .lock()
+                .unwrap()
+                .push(String::from_utf8_lossy(&buffer[..read]).into_owned());
+
+            let (status, headers, body) = &responses[index.min(responses.len() - 1)];
+            let response = format!(
+                "HTTP/1.1 {} Mock\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                headers,
+                body.len(),
+                body
+            );
+
+            socket.write_all(response.as_bytes()).await.unwrap();
+        }
+    });
+
+    MockServer { url, requests }
+}
+
+/// Builds a Flight departing from EDDF from a record in OpenSky's camelCase layout
+pub fn flight(
+    icao24: &str,
+    first_seen: u64,
+    last_seen: u64,
+    callsign: Option<&str>,
+    arrival: Option<&str>,
+) -> Flight {
+    serde_json::from_value(serde_json::json!({
+        "icao24": icao24,
+        "firstSeen": first_seen,
+        "estDepartureAirport": "EDDF",
+        "lastSeen": last_seen,
+        "estArrivalAirport": arrival,
+        "callsign": callsign,
+        "estDepartureAirportHorizDistance": null,
+        "estDepartureAirportVertDistance": null,
+        "estArrivalAirportHorizDistance": null,
+        "estArrivalAirportVertDistance": null,
+        "departureAirportCandidatesCount": 1,
+        "arrivalAirportCandidatesCount": u16::from(arrival.is_some()),
+    }))
+    .unwrap()
+}
@@ -0,0 +1,72 @@
+mod common;
+
+use common::flight;
+use opensky_api::congestion::CongestionReport;
+
+fn report() -> CongestionReport {
+    let begin = 36000;
+    let end = begin + 4 * 3600 - 1;
+
+    let arrivals = [
+        flight("3c6444", 0, begin + 100, Some("DLH400  "), Some("KJFK")),
+        flight("3c6444", 0, begin + 3700, Some("DLH400  "), Some("KJFK")),
+        flight("3c6444", 0, begin + 3800, Some("DLH400  "), Some("KJFK")),
+        flight("3c6444", 0, end + 10, Some("DLH400  "), Some("KJFK")),
+    ];
+    let departures = [
+        flight(
+            "3c6444",
+            begin + 3900,
+            90000,
+            Some("DLH400  "),
+            Some("KJFK"),
+        ),
+        flight(
+            "3c6444",
+            begin + 7300,
+            90000,
+            Some("DLH400  "),
+            Some("KJFK"),
+        ),
+        flight(
+            "3c6444",
+            begin + 10900,
+            90000,
+            Some("DLH400  "),
+            Some("KJFK"),
+        ),
+    ];
+
+    CongestionReport::from_flights("kjfk", begin, end, &arrivals, &departures)
+}
+
+#[test]
+fn counts_hourly_movements() {
+    let report = report();
+
+    assert_eq!(report.airport, "KJFK");
+    assert_eq!(report.hours.len(), 4);
+
+    let totals: Vec<usize> = report.hours.iter().map(|hour| hour.total()).collect();
+    assert_eq!(totals, [1, 3, 1, 1]);
+
+    assert_eq!(report.total_arrivals(), 3);
+    assert_eq!(report.arrival_departure_ratio(), Some(1.0));
+    assert_eq!(report.busiest_hour().unwrap().start, 36000 + 3600);
+}
+
+#[test]
+fn finds_rolling_peaks() {
+    let report = report();
+
+    assert_eq!(
+        report.rolling_totals(2),
+        [(36000, 4), (36000 + 3600, 4), (36000 + 7200, 2)]
+    );
+
+    let peak = report.rolling_peak(2).unwrap();
+    assert_eq!((peak.start, peak.end), (36000, 36000 + 7200));
+    assert_eq!((peak.arrivals, peak.departures), (3, 1));
+
+    assert!(report.rolling_peak(5).is_none());
+}
@@ -0,0 +1,94 @@
+use std::time::Duration;
+
+use opensky_api::{
+    policy::{EndpointClass, RequestPolicy},
+    OpenSkyApi,
+};
+
+#[test]
+fn limits_concurrent_requests() {
+    assert_eq!(OpenSkyApi::new().context().available_permits(), None);
+
+    let opensky_api = OpenSkyApi::new().with_max_concurrent_requests(4);
+    assert_eq!(opensky_api.context().available_permits(), Some(4));
+
+    // A limit of zero would block every request forever
+    let opensky_api = OpenSkyApi::new().with_max_concurrent_requests(0);
+    assert_eq!(opensky_api.context().available_permits(), Some(1));
+}
+
+#[test]
+fn renders_curl_commands() {
+    let opensky_api = OpenSkyApi::with_login(String::from("user"), String::from("it's secret"));
+    let request = opensky_api
+        .get_states()
+        .at_time(1000)
+        .with_icao24(String::from("3c6444"))
+        .consume();
+
+    assert_eq!(
+        request.to_curl(),
+        "curl -u 'user:********' \
+         'https://opensky-network.org/api/states/all?time=1000&icao24=3c6444'"
+    );
+    assert!(request
+        .to_curl_with_credentials()
+        .starts_with(r"curl -u 'user:it'\''s secret' "));
+
+    let anonymous = OpenSkyApi::new().get_flights(1000, 2000).consume();
+    assert_eq!(
+        anonymous.to_curl(),
+        "curl 'https://opensky-network.org/api/flights/all?begin=1000&end=2000'"
+    );
+}
+
+#[test]
+fn renders_oauth2_curl_commands() {
+    let opensky_api = OpenSkyApi::with_oauth2(String::from("client"), String::from("secret"));
+    let request = opensky_api.get_states().consume();
+
+    assert_eq!(
+        request.to_curl(),
+        "curl -H \"Authorization: Bearer ********\" 'https://opensky-network.org/api/states/all'"
+    );
+    assert!(request
+        .to_curl_with_credentials()
+        .contains("$OPENSKY_TOKEN"));
+}
+
+#[test]
+fn configures_policies_per_endpoint_class() {
+    let live = RequestPolicy::new().with_timeout(Duration::from_secs(5));
+    let historical = RequestPolicy::new()
+        .with_timeout(Duration::from_secs(120))
+        .with_max_retries(5)
+        .with_retry_delay(Duration::from_secs(10));
+
+    let opensky_api = OpenSkyApi::new()
+        .with_policy(EndpointClass::States, live)
+        .with_policy(EndpointClass::Flights, historical);
+
+    let policies = opensky_api.context().policies();
+    assert_eq!(*policies.get(EndpointClass::States), live);
+    assert_eq!(policies.flights.max_retries, 5);
+    assert_eq!(*policies.get(EndpointClass::Metadata), RequestPolicy::new());
+}
+
+#[test]
+fn backs_off_exponentially() {
+    let policy = RequestPolicy::new()
+        .with_retry_delay(Duration::from_secs(1))
+        .with_max_retry_delay(Duration::from_secs(5));
+
+    assert_eq!(policy.backoff(1), Duration::from_secs(1));
+    assert_eq!(policy.backoff(3), Duration::from_secs(4));
+    assert_eq!(policy.backoff(10), Duration::from_secs(5));
+
+    for retry in 1..5 {
+        let delay = policy.delay(retry);
+        assert!(delay <= policy.backoff(retry) && delay >= policy.backoff(retry) / 2);
+    }
+
+    let constant = policy.with_backoff_factor(1.0).with_jitter(false);
+    assert_eq!(constant.delay(4), Duration::from_secs(1));
+}
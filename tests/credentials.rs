@@ -0,0 +1,111 @@
+mod common;
+
+use opensky_api::{
+    credentials::{CredentialPool, Rotation},
+    OpenSkyApi,
+};
+
+const STATES: &str = r#"{"time": 1000, "states": []}"#;
+
+fn pool() -> CredentialPool {
+    CredentialPool::new()
+        .with_account(String::from("alice"), String::from("a"))
+        .with_account(String::from("bob"), String::from("b"))
+}
+
+fn users(requests: &[String]) -> Vec<&'static str> {
+    requests
+        .iter()
+        .map(|request| {
+            // alice:a and bob:b in base64
+            if request.contains("Basic YWxpY2U6YQ==") {
+                "alice"
+            } else if request.contains("Basic Ym9iOmI=") {
+                "bob"
+            } else {
+                "none"
+            }
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn rotates_accounts_per_request() {
+    let server = common::serve(vec![(
+        200,
+        "X-Rate-Limit-Remaining: 3990\r\n",
+        STATES.to_string(),
+    )])
+    .await;
+    let opensky_api = OpenSkyApi::with_credential_pool(pool()).with_base_url(&server.url);
+
+    for _ in 0..3 {
+        opensky_api.get_states().send().await.unwrap();
+    }
+
+    let requests = server.requests.lock().unwrap().clone();
+    assert_eq!(users(&requests), vec!["alice", "bob", "alice"]);
+
+    let usage = opensky_api.context().credential_pool().unwrap().usage();
+    assert_eq!(usage[0].requests, 2);
+    assert_eq!(usage[1].requests, 1);
+    assert_eq!(usage[1].remaining_credits, Some(3990));
+}
+
+#[tokio::test]
+async fn switches_accounts_when_rate_limited() {
+    let server = common::serve(vec![
+        (200, "", STATES.to_string()),
+        (
+            429,
+            "X-Rate-Limit-Retry-After-Seconds: 3600\r\n",
+            String::new(),
+        ),
+        (200, "", STATES.to_string()),
+    ])
+    .await;
+    let opensky_api = OpenSkyApi::with_credential_pool(pool().with_rotation(Rotation::OnRateLimit))
+        .with_base_url(&server.url);
+
+    opensky_api.get_states().send().await.unwrap();
+    opensky_api.get_states().send().await.unwrap();
+    opensky_api.get_states().send().await.unwrap();
+
+    let requests = server.requests.lock().unwrap().clone();
+    assert_eq!(users(&requests), vec!["alice", "alice", "bob", "bob"]);
+
+    let usage = opensky_api.context().credential_pool().unwrap().usage();
+    assert!(usage[0].rate_limited_for.unwrap().as_secs() > 3500);
+    assert!(usage[1].rate_limited_for.is_none());
+}
+
+#[tokio::test]
+async fn does_not_switch_accounts_forever_when_rate_limited() {
+    let server = common::serve(vec![(429, "Retry-After: 0\r\n", String::new())]).await;
+    let opensky_api = OpenSkyApi::with_credential_pool(pool()).with_base_url(&server.url);
+
+    let result = opensky_api.get_states().send().await;
+
+    assert!(matches!(
+        result,
+        Err(opensky_api::errors::Error::RateLimited { .. })
+    ));
+    assert_eq!(server.hits(), 1);
+
+    let usage = opensky_api.context().credential_pool().unwrap().usage();
+    assert!(usage[0].rate_limited_for.is_some());
+}
+
+#[tokio::test]
+async fn switches_each_account_at_most_once_per_request() {
+    let server = common::serve(vec![(
+        429,
+        "X-Rate-Limit-Retry-After-Seconds: 1\r\n",
+        String::new(),
+    )])
+    .await;
+    let opensky_api = OpenSkyApi::with_credential_pool(pool()).with_base_url(&server.url);
+
+    assert!(opensky_api.get_states().send().await.is_err());
+    assert!(server.hits() <= 3);
+}
@@ -0,0 +1,90 @@
+#![cfg(feature = "csv")]
+
+use opensky_api::{
+    csv::{FlightsCsvWriter, FLIGHT_COLUMNS},
+    errors::Error,
+    flights::Flight,
+    squawk::Squawk,
+    states::States,
+};
+
+#[test]
+fn round_trips_states() {
+    let states: States = serde_json::from_str(
+        r#"{"time": 1000, "states": [
+            ["3c6444", "DLH9LF  ", "Germany", 995, 998, 8.57, 50.03, 10000.0, false,
+                200.0, 90.0, -5.5, [1, 2], 10100.0, "7700", false, 0, 4],
+            ["a0b1c2", null, "United States", null, 400, null, null, null, true,
+                null, null, null, null, null, null, false, 0]]}"#,
+    )
+    .unwrap();
+
+    let mut csv = Vec::new();
+    states.write_csv(&mut csv).unwrap();
+    let csv = String::from_utf8(csv).unwrap();
+
+    let mut lines = csv.lines();
+    assert!(lines
+        .next()
+        .unwrap()
+        .starts_with("time,icao24,callsign,origin_country,"));
+    assert_eq!(
+        lines.next().unwrap(),
+        "1000,3c6444,DLH9LF  ,Germany,995,998,8.57,50.03,10000,false,200,90,-5.5,1;2,10100,\
+         7700,false,0,4"
+    );
+    assert_eq!(
+        lines.next().unwrap(),
+        "1000,a0b1c2,,United States,,400,,,,true,,,,,,,false,0,"
+    );
+
+    let snapshots = States::read_csv(csv.as_bytes()).unwrap();
+    assert_eq!(snapshots.len(), 1);
+
+    let state = &snapshots[0].states[0];
+    assert_eq!(snapshots[0].time, 1000);
+    assert_eq!(state.callsign.as_deref(), Some("DLH9LF  "));
+    assert_eq!(state.latitude, Some(50.03));
+    assert_eq!(state.sensors, Some(vec![1, 2]));
+    assert_eq!(state.squawk, Some(Squawk::EMERGENCY));
+    assert_eq!(state.category, Some(4));
+    assert_eq!(snapshots[0].states[1].callsign, None);
+}
+
+#[test]
+fn rejects_invalid_csv() {
+    let missing = "time,icao24\n1000,3c6444\n";
+    assert!(matches!(
+        States::read_csv(missing.as_bytes()),
+        Err(Error::InvalidCsv(_))
+    ));
+}
+
+#[test]
+fn writes_flights() {
+    let flight: Flight = serde_json::from_value(serde_json::json!({
+        "icao24": "3c6444",
+        "firstSeen": 1000,
+        "estDepartureAirport": "EDDF",
+        "lastSeen": 5000,
+        "estArrivalAirport": null,
+        "callsign": "DLH9LF  ",
+        "estDepartureAirportHorizDistance": 100,
+        "estDepartureAirportVertDistance": 10,
+        "estArrivalAirportHorizDistance": null,
+        "estArrivalAirportVertDistance": null,
+        "departureAirportCandidatesCount": 1,
+        "arrivalAirportCandidatesCount": 0,
+    }))
+    .unwrap();
+
+    let mut writer = FlightsCsvWriter::new(Vec::new());
+    writer.write(std::slice::from_ref(&flight)).unwrap();
+    writer.write(&[flight]).unwrap();
+    let csv = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0], FLIGHT_COLUMNS.join(","));
+    assert_eq!(lines[1], "3c6444,1000,EDDF,5000,,DLH9LF  ,100,10,,,1,0");
+}
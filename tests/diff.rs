@@ -0,0 +1,39 @@
+use opensky_api::states::States;
+
+fn snapshot(json: &str) -> States {
+    serde_json::from_str(json).unwrap()
+}
+
+#[test]
+fn diffs_consecutive_snapshots() {
+    let previous = snapshot(
+        r#"{"time": 1000, "states": [
+            ["000001", null, "Germany", 995, 998, 8.5, 50.0, 10000.0, false,
+                200.0, 90.0, 0.0, null, 10100.0, null, false, 0],
+            ["000002", null, "Germany", 995, 998, 8.6, 50.1, 9000.0, false,
+                200.0, 90.0, 0.0, null, 9110.0, null, false, 0],
+            ["000003", null, "Germany", 995, 998, 8.7, 50.2, 8000.0, false,
+                200.0, 90.0, 0.0, null, 8090.0, null, false, 0]]}"#,
+    );
+    let current = snapshot(
+        r#"{"time": 1010, "states": [
+            ["000002", null, "Germany", 1005, 1008, 8.7, 50.1, 9000.0, false,
+                200.0, 90.0, 0.0, null, 9110.0, null, false, 0],
+            ["000003", null, "Germany", 995, 998, 8.7, 50.2, 8000.0, false,
+                200.0, 90.0, 0.0, null, 8090.0, null, false, 0],
+            ["000004", null, "France", 1005, 1008, 2.5, 49.0, 6000.0, false,
+                200.0, 90.0, 0.0, null, 6050.0, null, false, 0]]}"#,
+    );
+
+    let diff = current.diff(&previous);
+
+    assert_eq!(diff.added.len(), 1);
+    assert_eq!(diff.added[0].icao24, "000004");
+    assert_eq!(diff.removed.len(), 1);
+    assert_eq!(diff.removed[0].icao24, "000001");
+    assert_eq!(diff.updated.len(), 1);
+    assert_eq!(diff.updated[0].previous.last_contact, 998);
+    assert_eq!(diff.updated[0].current.last_contact, 1008);
+
+    assert!(current.diff(&current).is_empty());
+}
@@ -0,0 +1,31 @@
+use opensky_api::{downsample::Downsampler, states::StateVector};
+
+fn sample(time: u64, longitude: f32, on_ground: bool, true_track: f32) -> StateVector {
+    serde_json::from_str(&format!(
+        r#"["3c6444", "DLH9LF  ", "Germany", {time}, {time}, {longitude}, 50.0, 1000.0,
+            {on_ground}, 100.0, {true_track}, 0.0, null, null, null, false, 0]"#
+    ))
+    .unwrap()
+}
+
+#[test]
+fn keeps_one_sample_per_interval_and_key_points() {
+    let mut series: Vec<StateVector> = (0..10)
+        .map(|i| sample(i * 10, 10.0 + i as f32 * 0.01, i < 3, 90.0))
+        .collect();
+    // A turn in the middle of an otherwise straight segment
+    series[6].true_track = Some(180.0);
+
+    let kept = Downsampler::every(60).keep(&series);
+
+    // First sample, both sides of the takeoff, the turn and the turn back, the interval
+    // sample, and the last sample
+    assert_eq!(kept, vec![0, 2, 3, 6, 7, 9]);
+}
+
+#[test]
+fn adaptive_drops_stationary_samples() {
+    let series: Vec<StateVector> = (0..10).map(|i| sample(i * 10, 10.0, true, 0.0)).collect();
+
+    assert_eq!(Downsampler::adaptive(100.0).keep(&series), vec![0, 9]);
+}
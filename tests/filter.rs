@@ -0,0 +1,84 @@
+mod common;
+
+use common::flight;
+use opensky_api::{
+    filter::{FlightsFilter, StatesFilter},
+    flights::Flight,
+    states::States,
+};
+
+fn states() -> States {
+    serde_json::from_str(
+        r#"{"time": 1000, "states": [
+            ["000001", "DLH9LF  ", "Germany", 995, 998, 8.5, 50.0, 10000.0, false,
+                230.0, 90.0, 0.0, null, 10100.0, null, false, 0, 4],
+            ["000002", "dlh4ab  ", "Germany", 995, 998, 8.6, 50.1, 3000.0, false,
+                120.0, 90.0, 0.0, null, 3110.0, null, false, 0, 3],
+            ["000003", "AFR12   ", "France", 995, 998, 2.5, 49.0, null, false,
+                200.0, 90.0, 0.0, null, 9000.0, null, false, 0, 4],
+            ["000004", null, "Germany", 995, 998, 8.7, 50.2, null, true,
+                5.0, 90.0, null, null, null, null, false, 0, 17]]}"#,
+    )
+    .unwrap()
+}
+
+fn icao24s(states: &States) -> Vec<&str> {
+    states.iter().map(|state| state.icao24.as_str()).collect()
+}
+
+#[test]
+fn filters_states() {
+    let states = states();
+
+    assert_eq!(states.filter(&StatesFilter::new()).len(), 4);
+
+    let cruising = StatesFilter::new().with_altitude(8000.0, 12000.0);
+    // The French aircraft only reports a geometric altitude
+    assert_eq!(icao24s(&states.filter(&cruising)), vec!["000001", "000003"]);
+
+    let lufthansa = StatesFilter::new()
+        .with_callsign_prefix("DLH")
+        .with_velocity(200.0, 300.0);
+    assert_eq!(icao24s(&states.filter(&lufthansa)), vec!["000001"]);
+
+    let german_ground = StatesFilter::new()
+        .with_country("Germany")
+        .on_ground(true)
+        .with_category(17);
+    assert_eq!(icao24s(&states.filter(&german_ground)), vec!["000004"]);
+
+    let mut heavy = states;
+    heavy.retain_matching(&StatesFilter::new().with_category(4).with_category(5));
+    assert_eq!(icao24s(&heavy), vec!["000001", "000003"]);
+}
+
+#[test]
+fn filters_flights() {
+    let flights = vec![
+        flight("000001", 1000, 2000, Some("DLH9LF  "), Some("EGLL")),
+        flight("000002", 1000, 2000, Some("dlh4ab  "), Some("KJFK")),
+        flight("000003", 1000, 2000, Some("AFR12   "), Some("EGLL")),
+        flight("000004", 1000, 2000, None, None),
+    ];
+    let icao24s = |flights: Vec<Flight>| -> Vec<String> {
+        flights.into_iter().map(|flight| flight.icao24).collect()
+    };
+
+    assert_eq!(FlightsFilter::new().filter(&flights).len(), 4);
+
+    let airlines = FlightsFilter::new()
+        .with_callsign_prefix("dlh")
+        .with_callsign_prefix("AFR");
+    assert_eq!(icao24s(airlines.filter(&flights)).len(), 3);
+
+    let exact = FlightsFilter::new().with_callsign("DLH4AB");
+    assert_eq!(icao24s(exact.filter(&flights)), ["000002"]);
+
+    let to_heathrow = FlightsFilter::new()
+        .with_callsign_prefix("DLH")
+        .arrived_at("EGLL".parse().unwrap());
+    assert_eq!(icao24s(to_heathrow.filter(&flights)), ["000001"]);
+
+    let from_frankfurt = FlightsFilter::new().departed_from("EDDF".parse().unwrap());
+    assert!(from_frankfurt.matches(&flights[3]));
+}
@@ -0,0 +1,293 @@
+mod common;
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, NaiveDate};
+use chrono_tz::America::New_York;
+use common::flight;
+use opensky_api::{
+    airport::AirportIcao,
+    errors::Error,
+    flights::{
+        dedup_flights, local_days, merge_fragments, write_flights_jsonl, Flight,
+        DEFAULT_MAX_FRAGMENT_GAP, MAX_AIRPORT_INTERVAL,
+    },
+    OpenSkyApi,
+};
+
+fn airport(code: &str) -> AirportIcao {
+    code.parse().unwrap()
+}
+
+#[test]
+fn dedups_overlapping_records() {
+    let flights = vec![
+        flight("3c6444", 1000, 5000, Some("DLH9LF  "), None),
+        flight("3c6444", 1000, 5030, Some("DLH9LF  "), Some("EGLL")),
+        flight("3c6444", 9000, 12000, Some("DLH9LF  "), None),
+        flight("a0b1c2", 500, 4000, Some("DLH9LF  "), None),
+    ];
+
+    let flights = dedup_flights(flights);
+
+    assert_eq!(flights.len(), 3);
+    assert_eq!(flights[0].icao24, "a0b1c2");
+    assert_eq!(flights[1].last_seen, 5030);
+    assert_eq!(flights[1].est_arrival_airport.unwrap(), "EGLL");
+    assert_eq!(flights[1].est_departure_airport.unwrap(), "EDDF");
+    assert_eq!(flights[2].first_seen, 9000);
+}
+
+#[test]
+fn computes_flight_properties() {
+    let flight = flight("3c6444", 1000, 5000, Some("DLH9LF  "), Some("EGLL"));
+
+    assert_eq!(flight.duration(), 4000);
+    assert_eq!(flight.clone(), flight);
+    assert_eq!(HashSet::from([flight.clone(), flight.clone()]).len(), 1);
+    assert!(flight.is_complete());
+    assert!(flight.departed_from("eddf"));
+    assert!(flight.arrived_at("EGLL"));
+    assert!(flight.matches_callsign("dlh"));
+    assert!(!flight.matches_callsign("DLH9LFX"));
+    assert_eq!(flight.first_seen_datetime().timestamp(), 1000);
+    assert_eq!(
+        flight.last_seen_datetime().to_rfc3339(),
+        "1970-01-01T01:23:20+00:00"
+    );
+}
+
+#[test]
+fn computes_local_days() {
+    let march_3 = NaiveDate::from_ymd_opt(2024, 3, 3).unwrap();
+    let (begin, end) = local_days(march_3, march_3, &New_York);
+
+    // Midnight in New York is 05:00 UTC during standard time
+    assert_eq!(begin, 1709442000);
+    assert_eq!(end - begin + 1, 24 * 3600);
+
+    // Daylight saving time starts on March 10th, which only has 23 hours
+    let march_10 = NaiveDate::from_ymd_opt(2024, 3, 10).unwrap();
+    let (begin, end) = local_days(march_10, march_10, &New_York);
+    assert_eq!(end - begin + 1, 23 * 3600);
+}
+
+#[test]
+fn chunks_airport_requests() {
+    let march_1 = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+    let march_5 = NaiveDate::from_ymd_opt(2024, 3, 5).unwrap();
+    let (begin, end) = local_days(march_1, march_5, &New_York);
+
+    let request = OpenSkyApi::new().get_departures(airport("kjfk"), begin, end);
+    let chunks = request.chunks();
+
+    assert_eq!(chunks.len(), 3);
+    assert_eq!(chunks[0].0, begin);
+    assert_eq!(chunks[2].1, end);
+    assert!(chunks
+        .windows(2)
+        .all(|pair| pair[0].1 == pair[1].0 && pair[0].1 - pair[0].0 == MAX_AIRPORT_INTERVAL));
+}
+
+#[test]
+fn validates_intervals_before_sending() {
+    let opensky_api = OpenSkyApi::new();
+    let invalid = |result: Result<(), Error>| matches!(result, Err(Error::InvalidRequest(_)));
+
+    assert!(opensky_api
+        .get_flights(1000, 2000)
+        .consume()
+        .validate()
+        .is_ok());
+    assert!(invalid(
+        opensky_api.get_flights(2000, 1000).consume().validate()
+    ));
+    assert!(invalid(
+        opensky_api
+            .get_flights(0, 3 * 24 * 60 * 60)
+            .consume()
+            .validate()
+    ));
+
+    let mut by_aircraft = opensky_api.get_flights(0, 3 * 24 * 60 * 60);
+    by_aircraft.by_aircraft(String::from("3c6444"));
+    assert!(by_aircraft.finish().validate().is_ok());
+
+    assert!(opensky_api
+        .get_arrivals(airport("EDDF"), 0, 7 * 24 * 60 * 60)
+        .validate()
+        .is_ok());
+    assert!(invalid(
+        opensky_api
+            .get_arrivals(airport("EDDF"), 2000, 1000)
+            .validate()
+    ));
+}
+
+#[test]
+fn accepts_datetime_ranges() {
+    let opensky_api = OpenSkyApi::new();
+    let begin = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+    let end = DateTime::from_timestamp(1_700_003_600, 0).unwrap();
+
+    let request = opensky_api.get_flights_in(begin..end).consume();
+    assert!(request
+        .to_curl()
+        .ends_with("/api/flights/all?begin=1700000000&end=1700003599'"));
+
+    let mut builder = opensky_api.get_flights(0, 1);
+    builder.in_time_range(begin..end);
+    assert_eq!(builder.finish().to_curl(), request.to_curl());
+
+    // A partial last second is still within the range
+    let end = DateTime::from_timestamp(1_700_003_600, 500_000_000).unwrap();
+    builder.in_time_range(begin..end);
+    assert!(builder.finish().to_curl().ends_with("&end=1700003600'"));
+
+    let states = opensky_api.get_states().at_datetime(begin).consume();
+    assert!(states
+        .to_curl()
+        .ends_with("/api/states/all?time=1700000000'"));
+}
+
+#[test]
+fn round_trips_flights() {
+    let flight = flight("3c6444", 1000, 2000, Some("DLH9LF  "), Some("EGLL"));
+
+    let snake_case = serde_json::to_value(&flight).unwrap();
+    assert_eq!(snake_case["first_seen"], 1000);
+    assert_eq!(
+        serde_json::from_value::<Flight>(snake_case).unwrap(),
+        flight
+    );
+
+    let camel_case = flight.to_opensky_json();
+    assert_eq!(camel_case["estArrivalAirport"], "EGLL");
+    assert_eq!(
+        serde_json::from_value::<Flight>(camel_case).unwrap(),
+        flight
+    );
+}
+
+#[test]
+fn parses_airport_codes() {
+    let airport: AirportIcao = " eddf ".parse().unwrap();
+    assert_eq!(airport.as_str(), "EDDF");
+    assert_eq!(airport, "eddf");
+    assert!("EG74".parse::<AirportIcao>().is_ok());
+    assert!(matches!(
+        "FRA".parse::<AirportIcao>(),
+        Err(Error::InvalidAirport(_))
+    ));
+    assert!("ED-F".parse::<AirportIcao>().is_err());
+
+    let flights = vec![
+        flight("3c6444", 1000, 2000, Some("DLH9LF  "), Some("EGLL")),
+        flight("4b1814", 1000, 2000, Some("DLH9LF  "), Some("egll")),
+        flight("a0b1c2", 1000, 2000, Some("DLH9LF  "), None),
+    ];
+    let mut by_arrival: HashMap<Option<AirportIcao>, usize> = HashMap::new();
+    for flight in &flights {
+        *by_arrival.entry(flight.est_arrival_airport).or_default() += 1;
+    }
+    assert_eq!(by_arrival[&Some("EGLL".parse().unwrap())], 2);
+
+    // Codes which are not ICAO airport codes are treated like missing estimates
+    let parsed: Flight = serde_json::from_value(serde_json::json!({
+        "icao24": "3c6444",
+        "firstSeen": 1000,
+        "estDepartureAirport": "FRA",
+        "lastSeen": 2000,
+        "estArrivalAirport": "",
+        "callsign": null,
+        "estDepartureAirportHorizDistance": null,
+        "estDepartureAirportVertDistance": null,
+        "estArrivalAirportHorizDistance": null,
+        "estArrivalAirportVertDistance": null,
+        "departureAirportCandidatesCount": 0,
+        "arrivalAirportCandidatesCount": 0,
+    }))
+    .unwrap();
+    assert_eq!(parsed.est_departure_airport, None);
+    assert_eq!(parsed.est_arrival_airport, None);
+}
+
+#[test]
+fn compares_flight_intervals() {
+    let flight = flight("3c6444", 1000, 4600, Some("DLH9LF  "), None);
+
+    assert_eq!(flight.duration(), 3600);
+    assert_eq!(flight.chrono_duration(), chrono::Duration::hours(1));
+    assert!(flight
+        .datetime_range()
+        .contains(&DateTime::from_timestamp(2000, 0).unwrap()));
+
+    assert!(flight.overlaps(0, 1000));
+    assert!(flight.overlaps(2000, 3000));
+    assert!(!flight.overlaps(4601, 5000));
+    assert!(flight.contains(4600));
+    assert!(!flight.contains(999));
+}
+
+#[test]
+fn merges_fragmented_records() {
+    let mut other_callsign = flight("4b1814", 1200, 2000, Some("DLH9LF  "), None);
+    other_callsign.callsign = Some(String::from("SWR12"));
+
+    let flights = vec![
+        flight("3c6444", 1000, 5000, Some("DLH9LF  "), None),
+        flight("3c6444", 5600, 9000, Some("DLH9LF  "), Some("EGLL")),
+        // Departs again after landing at EGLL
+        flight("3c6444", 9600, 12000, Some("DLH9LF  "), None),
+        flight("4b1814", 0, 1000, Some("DLH9LF  "), None),
+        other_callsign,
+        flight("a0b1c2", 0, 1000, Some("DLH9LF  "), None),
+        flight(
+            "a0b1c2",
+            1000 + DEFAULT_MAX_FRAGMENT_GAP + 1,
+            5000,
+            Some("DLH9LF  "),
+            None,
+        ),
+    ];
+
+    let flights = merge_fragments(flights);
+    let windows: Vec<_> = flights
+        .iter()
+        .map(|flight| (flight.icao24.as_str(), flight.first_seen, flight.last_seen))
+        .collect();
+
+    assert_eq!(
+        windows,
+        [
+            ("4b1814", 0, 1000),
+            ("a0b1c2", 0, 1000),
+            ("3c6444", 1000, 9000),
+            ("4b1814", 1200, 2000),
+            ("a0b1c2", 2801, 5000),
+            ("3c6444", 9600, 12000),
+        ]
+    );
+    assert_eq!(flights[2].est_arrival_airport.unwrap(), "EGLL");
+}
+
+#[test]
+fn writes_json_lines() {
+    let flights = vec![
+        flight("3c6444", 1000, 2000, Some("DLH9LF  "), Some("EGLL")),
+        flight("a0b1c2", 3000, 4000, Some("DLH9LF  "), None),
+    ];
+
+    let mut jsonl = Vec::new();
+    write_flights_jsonl(&mut jsonl, &flights).unwrap();
+    write_flights_jsonl(&mut jsonl, &flights[..1]).unwrap();
+    let jsonl = String::from_utf8(jsonl).unwrap();
+
+    let parsed: Vec<Flight> = jsonl
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(parsed.len(), 3);
+    assert_eq!(parsed[..2], flights[..]);
+    assert!(jsonl.starts_with(r#"{"icao24":"3c6444","first_seen":1000,"#));
+}
@@ -0,0 +1,32 @@
+#![cfg(feature = "geo")]
+
+use geo_types::{coord, Point, Rect};
+use opensky_api::{bounding_box::BoundingBox, states::StateVector, tracks::Waypoint, OpenSkyApi};
+
+#[test]
+fn converts_geometries() {
+    let state: StateVector = serde_json::from_str(
+        r#"["3c6444", "DLH9LF  ", "Germany", 995, 998, 8.57, 50.03, 10000.0, false,
+            200.0, 90.0, 0.0, null, null, null, false, 0]"#,
+    )
+    .unwrap();
+
+    assert_eq!(state.to_point(), Some(Point::new(8.57, 50.03)));
+    assert_eq!(
+        Waypoint::from(&state).to_point(),
+        Some(Point::new(8.57, 50.03))
+    );
+
+    let rect = Rect::new(coord! { x: 5.0, y: 47.0 }, coord! { x: 15.0, y: 55.0 });
+    let bbox = BoundingBox::from(rect);
+    assert_eq!(
+        (bbox.lat_min, bbox.lat_max, bbox.long_min, bbox.long_max),
+        (47.0, 55.0, 5.0, 15.0)
+    );
+    assert_eq!(Rect::from(bbox), rect);
+
+    let request = OpenSkyApi::new().get_states().with_bbox(rect).consume();
+    assert!(request
+        .to_curl()
+        .contains("lamin=47&lomin=5&lamax=55&lomax=15"));
+}
@@ -0,0 +1,32 @@
+#![cfg(feature = "geojson")]
+
+use opensky_api::states::States;
+
+#[test]
+fn converts_states_to_feature_collection() {
+    let states: States = serde_json::from_str(
+        r#"{"time": 1000, "states": [
+            ["3c6444", "DLH9LF  ", "Germany", 1000, 1000, 10.0, 50.0, 10000.0, false,
+                200.0, 90.0, -5.0, null, 10100.0, null, false, 0],
+            ["a0b1c2", null, "United States", null, 1000, null, null, null, false,
+                null, null, null, null, null, null, false, 0]]}"#,
+    )
+    .unwrap();
+
+    let collection = states.to_geojson();
+    assert_eq!(collection.features.len(), 1);
+
+    let json: serde_json::Value = serde_json::from_str(&collection.to_string()).unwrap();
+    let feature = &json["features"][0];
+
+    assert_eq!(json["type"], "FeatureCollection");
+    assert_eq!(feature["id"], "3c6444");
+    assert_eq!(feature["geometry"]["type"], "Point");
+    assert_eq!(
+        feature["geometry"]["coordinates"],
+        serde_json::json!([10.0, 50.0])
+    );
+    assert_eq!(feature["properties"]["callsign"], "DLH9LF");
+    assert_eq!(feature["properties"]["altitude"], 10000.0);
+    assert_eq!(feature["properties"]["heading"], 90.0);
+}
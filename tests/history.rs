@@ -0,0 +1,42 @@
+use opensky_api::{history::StateHistory, states::States};
+
+fn snapshot(time: u64, longitude: f64) -> States {
+    serde_json::from_str(&format!(
+        r#"{{"time": {time}, "states": [
+            ["3c6444", "DLH9LF  ", "Germany", {time}, {time}, {longitude}, 50.0, 10000.0,
+                false, 200.0, 90.0, 0.0, null, null, null, false, 0],
+            ["a0b1c2", null, "United States", null, 500, null, null, null, true,
+                null, null, null, null, null, null, false, 0]]}}"#
+    ))
+    .unwrap()
+}
+
+#[test]
+fn accumulates_state_histories() {
+    let mut history = StateHistory::new().with_max_len(3).with_max_age(600);
+
+    for (time, longitude) in [(1000, 8.0), (1000, 8.0), (1010, 8.1), (1020, 8.2)] {
+        history.ingest(&snapshot(time, longitude));
+    }
+
+    // Repeated snapshots do not add duplicates
+    assert_eq!(history.states("3c6444").unwrap().len(), 3);
+    assert_eq!(history.latest("3c6444").unwrap().last_contact, 1020);
+
+    let track = history.trajectory("3c6444").unwrap();
+    assert_eq!((track.start_time, track.end_time), (1000, 1020));
+    assert_eq!(track.path.len(), 3);
+    assert_eq!(track.callsign.as_deref(), Some("DLH9LF  "));
+
+    // The aircraft without a position has a history, but no trajectory
+    assert!(history.trajectory("a0b1c2").is_none());
+    assert_eq!(history.len(), 2);
+
+    history.ingest(&snapshot(1030, 8.3));
+    assert_eq!(history.states("3c6444").unwrap()[0].last_contact, 1010);
+
+    // The second aircraft was last heard from at 500, more than 600 seconds ago
+    history.ingest(&snapshot(1101, 8.4));
+    assert!(history.states("a0b1c2").is_none());
+    assert_eq!(history.icao24s().collect::<Vec<_>>(), vec!["3c6444"]);
+}
@@ -0,0 +1,60 @@
+use opensky_api::{
+    filter::StatesFilter,
+    icao24::{self, COUNTRY_BLOCKS, MILITARY_RANGES},
+    states::{StateVector, States},
+};
+
+#[test]
+fn parses_addresses() {
+    assert_eq!(icao24::parse("3c6444"), Some(0x3c6444));
+    assert_eq!(icao24::parse(" AE1234 "), Some(0xae1234));
+    assert_eq!(icao24::parse("3c64444"), None);
+    assert_eq!(icao24::parse("zzzzzz"), None);
+    assert_eq!(icao24::parse(""), None);
+}
+
+#[test]
+fn classifies_military_addresses() {
+    assert!(MILITARY_RANGES.windows(2).all(|pair| pair[0].1 < pair[1].0));
+
+    assert!(icao24::is_likely_military(0xae1234));
+    assert!(icao24::is_likely_military(0x3f4000));
+    assert!(icao24::is_likely_military(0x3fbfff));
+    assert!(!icao24::is_likely_military(0x3c6444));
+    assert!(!icao24::is_likely_military(0x000000));
+    assert!(!icao24::is_likely_military(0xffffff));
+
+    let states: States = serde_json::from_str(
+        r#"{"time": 1000, "states": [
+            ["ae1234", "RCH123  ", "United States", 995, 998, 8.5, 50.0, 10000.0, false,
+                200.0, 90.0, 0.0, null, null, null, false, 0],
+            ["3c6444", "DLH9LF  ", "Germany", 995, 998, 8.5, 50.0, 10000.0, false,
+                200.0, 90.0, 0.0, null, null, null, false, 0]]}"#,
+    )
+    .unwrap();
+
+    assert!(states.states[0].is_likely_military());
+    let military = states.filter(&StatesFilter::new().military(true));
+    assert_eq!(military.len(), 1);
+    assert_eq!(military.states[0].icao24, "ae1234");
+    assert_eq!(states.filter(&StatesFilter::new().military(false)).len(), 1);
+}
+
+#[test]
+fn looks_up_countries() {
+    assert!(COUNTRY_BLOCKS.windows(2).all(|pair| pair[0].1 < pair[1].0));
+
+    assert_eq!(icao24::country_code(0x3c6444), Some("DE"));
+    assert_eq!(icao24::country_code(0xa00000), Some("US"));
+    assert_eq!(icao24::country_code(0x4b1814), Some("CH"));
+    assert_eq!(icao24::country_code(0x7c0000), Some("AU"));
+    assert_eq!(icao24::country_code(0x000000), None);
+    assert_eq!(icao24::country_code(0xffffff), None);
+
+    let state: StateVector = serde_json::from_str(
+        r#"["a0b1c2", null, "United States", null, 400, null, null, null, true,
+            null, null, null, null, null, null, false, 0]"#,
+    )
+    .unwrap();
+    assert_eq!(state.registration_country_code(), Some("US"));
+}
@@ -0,0 +1,42 @@
+use opensky_api::states::States;
+
+fn snapshot(time: u64, states: &str) -> States {
+    serde_json::from_str(&format!(r#"{{"time": {time}, "states": [{states}]}}"#)).unwrap()
+}
+
+#[test]
+fn interpolates_state_vectors() {
+    let previous = snapshot(
+        1000,
+        r#"["3c6444", "DLH9LF  ", "Germany", 1000, 1000, 179.0, 50.0, 10000.0, false,
+            200.0, 350.0, 0.0, null, null, null, false, 0],
+           ["a0b1c2", null, "United States", 1000, 1000, null, null, null, true,
+            null, null, null, null, null, null, false, 0]"#,
+    );
+    let next = snapshot(
+        1010,
+        r#"["3c6444", "DLH9LF  ", "Germany", 1010, 1010, -179.0, 51.0, 11000.0, false,
+            210.0, 10.0, 5.0, null, null, null, false, 0],
+           ["4b1814", null, "Switzerland", 1010, 1010, null, null, null, true,
+            null, null, null, null, null, null, false, 0]"#,
+    );
+
+    let state = previous.states[0].interpolate(&next.states[0], 0.25);
+    assert_eq!(state.last_contact, 1003);
+    assert_eq!(state.latitude, Some(50.25));
+    // The aircraft crosses the antimeridian and turns through north
+    assert_eq!(state.longitude, Some(179.5));
+    assert_eq!(state.true_track, Some(355.0));
+    assert_eq!(state.baro_altitude, Some(10250.0));
+    assert_eq!(state.velocity, Some(202.5));
+
+    let early = previous.interpolate_with(&next, 0.25);
+    assert_eq!(early.time, 1003);
+    let icao24s: Vec<&str> = early.iter().map(|state| state.icao24.as_str()).collect();
+    assert_eq!(icao24s, vec!["3c6444", "a0b1c2"]);
+
+    let late = previous.interpolate_with(&next, 0.75);
+    let icao24s: Vec<&str> = late.iter().map(|state| state.icao24.as_str()).collect();
+    assert_eq!(icao24s, vec!["3c6444", "4b1814"]);
+    assert_eq!(late.states[0].longitude, Some(-179.5));
+}
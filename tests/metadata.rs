@@ -0,0 +1,59 @@
+use opensky_api::{flights::Flight, metadata::AircraftMetadata, OpenSkyApi};
+
+#[tokio::test]
+async fn batch_lookup_uses_cache() {
+    let opensky_api = OpenSkyApi::new();
+
+    let metadata: AircraftMetadata =
+        serde_json::from_str(r#"{"icao24": "3c6444", "registration": "D-AIBD"}"#).unwrap();
+
+    opensky_api
+        .metadata_cache()
+        .insert("3c6444", Some(metadata));
+    opensky_api.metadata_cache().insert("a0b1c2", None);
+
+    let result = opensky_api
+        .get_aircraft_metadata_many(["3C6444", "3c6444", "a0b1c2"])
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(result.len(), 1);
+    assert_eq!(result["3c6444"].registration.as_deref(), Some("D-AIBD"));
+}
+
+#[tokio::test]
+async fn enriches_flights_with_metadata() {
+    let opensky_api = OpenSkyApi::new();
+
+    let metadata: AircraftMetadata =
+        serde_json::from_str(r#"{"icao24": "3c6444", "typecode": "A320"}"#).unwrap();
+    opensky_api
+        .metadata_cache()
+        .insert("3c6444", Some(metadata));
+    opensky_api.metadata_cache().insert("a0b1c2", None);
+
+    let flights: Vec<Flight> = ["a0b1c2", "3C6444", "3c6444"]
+        .iter()
+        .map(|icao24| {
+            serde_json::from_value(serde_json::json!({
+                "icao24": icao24, "firstSeen": 1000, "estDepartureAirport": null,
+                "lastSeen": 2000, "estArrivalAirport": null, "callsign": null,
+                "estDepartureAirportHorizDistance": null, "estDepartureAirportVertDistance": null,
+                "estArrivalAirportHorizDistance": null, "estArrivalAirportVertDistance": null,
+                "departureAirportCandidatesCount": 0, "arrivalAirportCandidatesCount": 0,
+            }))
+            .unwrap()
+        })
+        .collect();
+
+    let enriched = opensky_api.enrich(flights).send().await.unwrap();
+
+    assert_eq!(enriched.len(), 3);
+    assert_eq!(enriched[0].record.icao24, "a0b1c2");
+    assert!(enriched[0].metadata.is_none());
+    for flight in &enriched[1..] {
+        let metadata = flight.metadata.as_ref().unwrap();
+        assert_eq!(metadata.typecode.as_deref(), Some("A320"));
+    }
+}
@@ -0,0 +1,37 @@
+mod common;
+
+use opensky_api::{metrics::MetricsCounter, policy::EndpointClass, OpenSkyApi};
+
+const STATES: &str = r#"{"time": 1000, "states": []}"#;
+
+#[tokio::test]
+async fn counts_requests_per_endpoint_class() {
+    let server = common::serve(vec![
+        (200, "", STATES.to_string()),
+        (200, "", String::from("not json")),
+        (404, "", String::new()),
+    ])
+    .await;
+    let counter = MetricsCounter::new();
+    let opensky_api = OpenSkyApi::new()
+        .with_base_url(&server.url)
+        .with_metrics(counter.clone());
+
+    assert!(opensky_api.get_states().send().await.is_ok());
+    assert!(opensky_api.get_states().send().await.is_err());
+    assert!(opensky_api.get_flights(1000, 2000).send().await.is_err());
+
+    let states = counter.get(EndpointClass::States);
+    assert_eq!(states.responses, 2);
+    assert_eq!(states.parse_failures, 1);
+    assert_eq!(states.bytes, (STATES.len() + "not json".len()) as u64);
+    assert_eq!(states.statuses.get(&200), Some(&2));
+    assert!(states.average_latency().is_some());
+
+    let flights = counter.get(EndpointClass::Flights);
+    assert_eq!(flights.responses, 1);
+    assert_eq!(flights.parse_failures, 0);
+    assert_eq!(flights.statuses.get(&404), Some(&1));
+
+    assert_eq!(counter.get(EndpointClass::Metadata), Default::default());
+}
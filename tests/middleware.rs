@@ -0,0 +1,53 @@
+mod common;
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use opensky_api::{
+    middleware::{DefaultHeader, Middleware},
+    OpenSkyApi,
+};
+use reqwest::header::{HeaderName, HeaderValue};
+
+const STATES: &str = r#"{"time": 1000, "states": []}"#;
+
+#[derive(Default)]
+struct Statuses(Arc<AtomicUsize>);
+
+impl Middleware for Statuses {
+    fn on_response(&self, response: &mut reqwest::Response) {
+        if response.status().is_success() {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+}
+
+#[tokio::test]
+async fn passes_requests_through_middleware() {
+    let server = common::serve(vec![
+        (200, "", STATES.to_string()),
+        (200, "", String::from("[]")),
+    ])
+    .await;
+    let successes = Arc::new(AtomicUsize::new(0));
+    let opensky_api = OpenSkyApi::new()
+        .with_base_url(&server.url)
+        .with_middleware(DefaultHeader::new(
+            HeaderName::from_static("x-tracker"),
+            HeaderValue::from_static("test"),
+        ))
+        .with_middleware(Statuses(successes.clone()));
+
+    assert_eq!(opensky_api.context().middleware().len(), 2);
+
+    opensky_api.get_states().send().await.unwrap();
+    opensky_api.get_flights(1000, 2000).send().await.unwrap();
+
+    let requests = server.requests.lock().unwrap().clone();
+    assert!(requests
+        .iter()
+        .all(|request| request.contains("x-tracker: test")));
+    assert_eq!(successes.load(Ordering::SeqCst), 2);
+}
@@ -0,0 +1,46 @@
+use opensky_api::{
+    mvt::{TileId, MAX_ZOOM},
+    states::States,
+};
+
+fn states() -> States {
+    serde_json::from_str(
+        r#"{"time": 1000, "states": [
+            ["3c6444", "DLH9LF  ", "Germany", 1000, 1000, 8.57, 50.03, 10000.0, false,
+                200.0, 90.0, 0.0, null, null, null, false, 0],
+            ["a0b1c2", null, "United States", 1000, 1000, -73.78, 40.64, null, true,
+                5.0, 180.0, null, null, null, null, false, 0]]}"#,
+    )
+    .unwrap()
+}
+
+#[test]
+fn encodes_tiles_containing_aircraft() {
+    let states = states();
+    let frankfurt = TileId::containing(8, 50.03, 8.57);
+
+    let tile = states.to_mvt(frankfurt);
+
+    // The tile consists of exactly one layer with a single feature
+    assert_eq!(tile[0], 0x1a);
+    assert!(tile.windows(8).any(|w| w == b"aircraft"));
+    assert!(tile.windows(6).any(|w| w == b"DLH9LF"));
+    assert!(!tile.windows(6).any(|w| w == b"a0b1c2"));
+
+    assert!(states.to_mvt(TileId::new(8, 0, 0)).is_empty());
+    assert_eq!(states.to_mvt_tiles(8).len(), 2);
+}
+
+#[test]
+fn clamps_zoom_levels() {
+    let deepest = TileId::containing(MAX_ZOOM, 85.1, 180.0);
+    assert_eq!(deepest.z, 32);
+    assert_eq!((deepest.x, deepest.y), (u32::MAX, 0));
+
+    assert_eq!(TileId::containing(64, 50.03, 8.57).z, MAX_ZOOM);
+    assert_eq!(TileId::new(255, 0, 0).z, MAX_ZOOM);
+
+    let tiles = states().to_mvt_tiles(u8::MAX);
+    assert_eq!(tiles.len(), 2);
+    assert!(tiles.keys().all(|tile| tile.z == MAX_ZOOM));
+}
@@ -0,0 +1,43 @@
+#![cfg(feature = "parquet")]
+
+use std::fs::File;
+
+use opensky_api::{parquet::write_states, states::States};
+use parquet::{
+    file::reader::{FileReader, SerializedFileReader},
+    record::RowAccessor,
+};
+
+#[test]
+fn writes_states() {
+    let states: States = serde_json::from_str(
+        r#"{"time": 1000, "states": [
+            ["3c6444", "DLH9LF  ", "Germany", 995, 998, 8.57, 50.03, 10000.0, false,
+                200.0, 90.0, -5.5, [1, 2], 10100.0, "7700", false, 0, 4],
+            ["a0b1c2", null, "United States", null, 400, null, null, null, true,
+                null, null, null, null, null, null, false, 0]]}"#,
+    )
+    .unwrap();
+
+    let path = std::env::temp_dir().join("opensky_api_writes_states.parquet");
+    write_states(File::create(&path).unwrap(), &states).unwrap();
+
+    let reader = SerializedFileReader::new(File::open(&path).unwrap()).unwrap();
+    assert_eq!(reader.metadata().file_metadata().num_rows(), 2);
+
+    let rows: Vec<_> = reader
+        .get_row_iter(None)
+        .unwrap()
+        .map(Result::unwrap)
+        .collect();
+
+    assert_eq!(rows[0].get_string(0).unwrap(), "3c6444");
+    assert_eq!(rows[0].get_double(6).unwrap(), 50.03);
+    assert_eq!(rows[0].get_list(12).unwrap().len(), 2);
+    assert_eq!(rows[0].get_string(14).unwrap(), "7700");
+    assert_eq!(rows[0].get_int(17).unwrap(), 4);
+    assert!(rows[1].get_string(1).is_err());
+    assert!(rows[1].get_bool(8).unwrap());
+
+    std::fs::remove_file(path).unwrap();
+}
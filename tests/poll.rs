@@ -0,0 +1,26 @@
+use std::time::{Duration, UNIX_EPOCH};
+
+use opensky_api::poll::PollSchedule;
+
+#[test]
+fn aligns_to_wall_clock() {
+    let schedule = PollSchedule::every(Duration::from_secs(10)).aligned();
+    let now = UNIX_EPOCH + Duration::from_millis(1_000_005_500);
+
+    assert_eq!(schedule.delay_from(now), Duration::from_millis(4500));
+
+    let offset = PollSchedule::every(Duration::from_secs(10)).with_offset(Duration::from_secs(7));
+
+    assert_eq!(offset.delay_from(now), Duration::from_millis(1500));
+}
+
+#[test]
+fn jitter_stays_within_bounds() {
+    let schedule = PollSchedule::every(Duration::from_secs(10)).with_jitter(Duration::from_secs(2));
+
+    for _ in 0..100 {
+        let delay = schedule.next_delay();
+
+        assert!(delay >= Duration::from_secs(10) && delay <= Duration::from_secs(12));
+    }
+}
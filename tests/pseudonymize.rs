@@ -0,0 +1,32 @@
+#![cfg(feature = "pseudonymize")]
+
+use opensky_api::{pseudonymize::Pseudonymizer, states::StateVector};
+
+#[test]
+fn pseudonyms_are_stable_per_key() {
+    let a = Pseudonymizer::new(b"first key");
+    let b = Pseudonymizer::new(b"second key");
+
+    assert_eq!(a.icao24("3C6444"), a.icao24("3c6444"));
+    assert_eq!(a.callsign("DLH9LF  "), a.callsign("DLH9LF"));
+    assert_ne!(a.icao24("3c6444"), b.icao24("3c6444"));
+    assert_ne!(a.icao24("3c6444"), a.callsign("3c6444"));
+}
+
+#[test]
+fn redacts_state_vectors() {
+    let state: StateVector = serde_json::from_str(
+        r#"["3c6444", "DLH9LF  ", "Germany", 1000, 1000, 10.0, 50.0, 10000.0, false,
+            200.0, 90.0, -5.0, [123], 10100.0, "1000", false, 0]"#,
+    )
+    .unwrap();
+
+    let pseudonymizer = Pseudonymizer::random().unwrap();
+    let redacted = pseudonymizer.state_vector(&state);
+
+    assert_eq!(redacted.icao24, pseudonymizer.icao24("3c6444"));
+    assert_eq!(redacted.callsign, Some(pseudonymizer.callsign("DLH9LF")));
+    assert_eq!(redacted.squawk, None);
+    assert_eq!(redacted.sensors, None);
+    assert_eq!(redacted.origin_country, "Germany");
+}
@@ -0,0 +1,72 @@
+#![cfg(feature = "sqlite")]
+
+use opensky_api::{bounding_box::BoundingBox, recorder::SqliteRecorder, states::States};
+
+fn snapshot(time: u64, longitude: f32) -> States {
+    serde_json::from_str(&format!(
+        r#"{{"time": {time}, "states": [["3c6444", "DLH9LF  ", "Germany", {time}, {time},
+            {longitude}, 50.0, 10000.0, false, 200.0, 90.0, 0.0, [1, 2], null, null, false, 0]]}}"#
+    ))
+    .unwrap()
+}
+
+#[test]
+fn records_and_queries_history() {
+    let mut recorder = SqliteRecorder::in_memory().unwrap();
+
+    recorder.record(&snapshot(1000, 10.0)).unwrap();
+    recorder.record(&snapshot(1010, 10.1)).unwrap();
+    recorder.record(&snapshot(1020, 10.2)).unwrap();
+
+    let history = recorder.history("3c6444", 1005, 1020).unwrap();
+
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0].0, 1010);
+    assert_eq!(history[1].1.sensors, Some(vec![1, 2]));
+}
+
+#[test]
+fn queries_bbox_at_time() {
+    let mut recorder = SqliteRecorder::in_memory().unwrap();
+
+    recorder.record(&snapshot(1000, 10.0)).unwrap();
+    recorder.record(&snapshot(1010, 12.0)).unwrap();
+
+    let bbox = BoundingBox::new(49.0, 51.0, 9.0, 11.0);
+
+    assert_eq!(
+        recorder
+            .in_bbox_at(bbox, 1005)
+            .unwrap()
+            .unwrap()
+            .states
+            .len(),
+        1
+    );
+    assert!(recorder
+        .in_bbox_at(bbox, 1015)
+        .unwrap()
+        .unwrap()
+        .states
+        .is_empty());
+    assert!(recorder.in_bbox_at(bbox, 999).unwrap().is_none());
+}
+
+#[test]
+fn replaces_snapshots_recorded_twice() {
+    let mut recorder = SqliteRecorder::in_memory().unwrap();
+
+    let mut first = snapshot(1000, 10.0);
+    let mut other = first.states[0].clone();
+    other.icao24 = String::from("4b1814");
+    first.states.push(other);
+
+    recorder.record(&first).unwrap();
+    recorder.record(&snapshot(1000, 10.0)).unwrap();
+
+    let bbox = BoundingBox::new(49.0, 51.0, 9.0, 11.0);
+    let states = recorder.in_bbox_at(bbox, 1000).unwrap().unwrap().states;
+
+    assert_eq!(states.len(), 1);
+    assert_eq!(states[0].icao24, "3c6444");
+}
@@ -0,0 +1,45 @@
+#![cfg(feature = "sim")]
+
+use opensky_api::{
+    sim::{extrapolate, flightgear_position, xplane_vehx, xtraffic, MAX_EXTRAPOLATION_SECS},
+    states::StateVector,
+};
+
+fn state() -> StateVector {
+    serde_json::from_str(
+        r#"["3c6444", "DLH9LF  ", "Germany", 1000, 1000, 10.0, 50.0, 10000.0, false,
+            200.0, 90.0, -5.0, null, 10100.0, "1000", false, 0]"#,
+    )
+    .unwrap()
+}
+
+#[test]
+fn extrapolates_along_track() {
+    let position = extrapolate(&state(), 1010.0).unwrap();
+
+    assert!(position.longitude > 10.0);
+    assert!((position.latitude - 50.0).abs() < 1e-3);
+    assert!((position.altitude - 10050.0).abs() < 1e-6);
+}
+
+#[test]
+fn limits_extrapolation_horizon() {
+    let limited = extrapolate(&state(), 1000.0 + MAX_EXTRAPOLATION_SECS).unwrap();
+    let late = extrapolate(&state(), 5000.0).unwrap();
+
+    assert_eq!(limited.longitude, late.longitude);
+}
+
+#[test]
+fn encodes_packets() {
+    let position = extrapolate(&state(), 1000.0).unwrap();
+
+    assert_eq!(xplane_vehx(1, &position).len(), 45);
+    assert_eq!(
+        flightgear_position("DLH9LF", "Aircraft/ufo", &position, 0.0).len(),
+        232
+    );
+    assert!(xtraffic("OpenSky", &state(), &position)
+        .unwrap()
+        .ends_with(",DLH9LF"));
+}
@@ -0,0 +1,39 @@
+use opensky_api::{squawk::Squawk, states::States};
+
+#[test]
+fn parses_octal_codes() {
+    let squawk: Squawk = "0400".parse().unwrap();
+    assert_eq!(squawk.code(), 400);
+    assert_eq!(squawk.to_string(), "0400");
+
+    assert!("7800".parse::<Squawk>().is_err());
+    assert!("770".parse::<Squawk>().is_err());
+    assert!("+770".parse::<Squawk>().is_err());
+    assert_eq!(Squawk::new(1289), None);
+}
+
+#[test]
+fn detects_emergency_codes() {
+    let hijack: Squawk = "7500".parse().unwrap();
+    assert!(hijack.is_hijack() && hijack.is_any_emergency());
+    assert!(!hijack.is_emergency());
+
+    assert!(Squawk::RADIO_FAILURE.is_radio_failure());
+    assert!(Squawk::EMERGENCY.is_emergency());
+    assert!(!Squawk::new(7000).unwrap().is_any_emergency());
+}
+
+#[test]
+fn ignores_invalid_squawks_in_states() {
+    let states: States = serde_json::from_str(
+        r#"{"time": 1000, "states": [
+            ["000001", null, "Germany", 995, 998, 8.5, 50.0, null, true,
+                null, null, null, null, null, "7700", false, 0],
+            ["000002", null, "Germany", 995, 998, 8.5, 50.0, null, true,
+                null, null, null, null, null, "9999", false, 0]]}"#,
+    )
+    .unwrap();
+
+    assert_eq!(states.states[0].squawk, Some(Squawk::EMERGENCY));
+    assert_eq!(states.states[1].squawk, None);
+}
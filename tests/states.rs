@@ -0,0 +1,369 @@
+use opensky_api::{
+    bounding_box::BoundingBox,
+    dedup::PositionDedup,
+    errors::Error,
+    response::WithRaw,
+    states::{self, States},
+    units, OpenSkyApi,
+};
+
+fn states() -> States {
+    serde_json::from_str(
+        r#"{"time": 1000, "states": [
+            ["3c6444", "DLH9LF  ", "Germany", 995, 998, 8.57, 50.03, 10000.0, false,
+                200.0, 90.0, 0.0, null, null, null, false, 0],
+            ["a0b1c2", null, "United States", 400, 400, -73.78, 40.64, null, true,
+                5.0, 180.0, null, null, null, null, false, 0]]}"#,
+    )
+    .unwrap()
+}
+
+#[test]
+fn retains_recent_states() {
+    let mut states = states();
+
+    assert_eq!(states.states[0].age_at(states.time), 2);
+    assert_eq!(states.states[1].age_at(states.time), 600);
+    assert_eq!(states.states[0].last_contact_datetime().timestamp(), 998);
+    assert_eq!(
+        states.states[0]
+            .time_position_datetime()
+            .map(|time| time.timestamp()),
+        Some(995)
+    );
+
+    states.retain_recent(60);
+
+    assert_eq!(states.states.len(), 1);
+    assert_eq!(states.states[0].icao24, "3c6444");
+}
+
+#[test]
+fn measures_distance_to_point() {
+    let states = states();
+
+    // Frankfurt to New York JFK is roughly 6200 km
+    let distance = states.states[0].distance_to(40.64, -73.78).unwrap();
+    assert!((distance - 6200.0).abs() < 50.0);
+
+    assert!(states.states[0].distance_to(50.03, 8.57).unwrap() < 1e-3);
+
+    // The great circle from Frankfurt to New York starts out west-northwest
+    let bearing = states.states[0].bearing_to(40.64, -73.78).unwrap();
+    assert!((bearing - 294.4).abs() < 0.1);
+
+    // Seen from a point due south, the aircraft is straight north
+    let bearing = states.states[0].bearing_from(40.0, 8.57).unwrap();
+    assert!(bearing.abs() < 1e-9);
+}
+
+#[test]
+fn sorts_by_distance() {
+    let mut states = states();
+    let original = states.clone();
+
+    states.sort_by_distance_to(40.0, -74.0);
+    assert_ne!(states, original);
+    assert_eq!(states.states[0].icao24, "a0b1c2");
+
+    states.sort_by_distance_to(50.0, 8.0);
+    assert_eq!(states.states[0].icao24, "3c6444");
+}
+
+#[test]
+fn encloses_circle() {
+    let bbox = BoundingBox::enclosing_circle(60.0, 10.0, 100.0);
+
+    assert!((bbox.lat_max - 60.9).abs() < 0.01);
+    assert!((bbox.lat_min - 59.1).abs() < 0.01);
+    // Degrees of longitude are half as long at 60 degrees north
+    assert!((bbox.long_max - 11.8).abs() < 0.01);
+    assert!((bbox.long_min - 8.2).abs() < 0.01);
+
+    let polar = BoundingBox::enclosing_circle(89.5, 10.0, 100.0);
+    assert_eq!(
+        (polar.long_min, polar.long_max, polar.lat_max),
+        (-180.0, 180.0, 90.0)
+    );
+}
+
+#[test]
+fn retains_states_within_radius() {
+    let mut states: States = serde_json::from_str(
+        r#"{"time": 1000, "states": [
+            ["000001", null, "Germany", 995, 998, 8.57, 50.03, null, true,
+                null, null, null, null, null, null, false, 0],
+            ["000002", null, "Germany", 995, 998, 9.99, 53.63, null, true,
+                null, null, null, null, null, null, false, 0],
+            ["000003", null, "Germany", 995, 998, null, null, null, true,
+                null, null, null, null, null, null, false, 0]]}"#,
+    )
+    .unwrap();
+
+    // Frankfurt and Hamburg are roughly 400 km apart
+    states.retain_within_radius(50.0, 8.6, 100.0);
+
+    assert_eq!(states.states.len(), 1);
+    assert_eq!(states.states[0].icao24, "000001");
+}
+
+#[test]
+fn keeps_full_coordinate_precision() {
+    let states: States = serde_json::from_str(
+        r#"{"time": 1000, "states": [["3c6444", null, "Germany", 995, 998, 8.5712345,
+            50.0312345, null, true, null, null, null, null, null, null, false, 0]]}"#,
+    )
+    .unwrap();
+
+    assert_eq!(states.states[0].latitude, Some(50.0312345));
+    assert_eq!(states.states[0].longitude, Some(8.5712345));
+}
+
+#[test]
+fn decomposes_velocity() {
+    let states = states();
+
+    // 200 m/s on a track of 90 degrees is due east
+    let (north, east) = states.states[0].ground_velocity().unwrap();
+    assert!(north.abs() < 1e-9);
+    assert!((east - 200.0).abs() < 1e-9);
+
+    // 5 m/s on a track of 180 degrees is due south
+    let velocity = states.states[1].velocity_vector().unwrap();
+    assert!((velocity.north + 5.0).abs() < 1e-9);
+    assert!(velocity.east.abs() < 1e-9);
+    assert_eq!(velocity.up, 0.0);
+    assert!((velocity.track() - 180.0).abs() < 1e-9);
+    assert!((velocity.ground_speed() - 5.0).abs() < 1e-9);
+}
+
+#[test]
+fn converts_units() {
+    let states = states();
+    let state = &states.states[0];
+
+    assert!((state.velocity_knots().unwrap() - 388.77).abs() < 0.01);
+    assert!((state.baro_altitude_ft().unwrap() - 32808.4).abs() < 0.1);
+    assert_eq!(state.vertical_rate_fpm(), Some(0.0));
+    assert_eq!(state.geo_altitude_ft(), None);
+
+    assert!((units::feet_to_meters(units::meters_to_feet(1234.5)) - 1234.5).abs() < 1e-9);
+    assert!((units::knots_to_mps(1.0) - 0.514_444).abs() < 1e-6);
+    assert!((units::mps_to_fpm(1.0) - 196.85).abs() < 0.01);
+}
+
+#[test]
+fn keeps_raw_bodies() {
+    let raw = br#"{"time": 1000, "states": null}"#.to_vec();
+    let response = WithRaw {
+        parsed: serde_json::from_slice::<serde_json::Value>(&raw).unwrap(),
+        raw,
+    };
+
+    assert_eq!(response.raw_str(), r#"{"time": 1000, "states": null}"#);
+    assert_eq!(response.into_parsed()["time"], 1000);
+}
+
+#[test]
+fn requests_extended_states() {
+    let request = OpenSkyApi::new().get_states().extended().consume();
+
+    assert_eq!(
+        request.to_curl(),
+        "curl 'https://opensky-network.org/api/states/all?extended=1'"
+    );
+}
+
+#[test]
+fn requests_own_states() {
+    let opensky_api = OpenSkyApi::new();
+
+    assert_eq!(
+        opensky_api.get_own_states().consume().to_curl(),
+        "curl 'https://opensky-network.org/api/states/own'"
+    );
+    assert_eq!(
+        opensky_api
+            .get_own_states()
+            .with_serial(1)
+            .with_serial(2)
+            .consume()
+            .to_curl(),
+        "curl 'https://opensky-network.org/api/states/own?serials=1&serials=2'"
+    );
+}
+
+#[tokio::test]
+async fn rejects_invalid_bounding_boxes() {
+    assert!(BoundingBox::try_new(45.0, 50.0, 5.0, 10.0).is_ok());
+    assert!(BoundingBox::try_new(90.5, 91.0, 5.0, 10.0).is_err());
+    assert!(BoundingBox::try_new(45.0, 50.0, -200.0, 10.0).is_err());
+    assert!(BoundingBox::try_new(50.0, 45.0, 5.0, 10.0).is_err());
+    assert!(BoundingBox::try_new(f64::NAN, 45.0, 5.0, 10.0).is_err());
+
+    // Invalid boxes are rejected before anything is sent
+    let result = OpenSkyApi::new()
+        .with_base_url("http://opensky.invalid")
+        .get_states()
+        .with_bbox(BoundingBox::new(90.5, -200.0, 5.0, 10.0))
+        .send()
+        .await;
+
+    assert!(matches!(result, Err(Error::InvalidRequest(_))));
+}
+
+#[test]
+fn splits_bounding_box_into_tiles() {
+    let bbox = BoundingBox::new(40.0, 50.0, 0.0, 25.0);
+    let tiles = bbox.tiles(10.0).unwrap();
+
+    // One row of three columns
+    assert_eq!(tiles.len(), 3);
+    assert!(tiles
+        .iter()
+        .all(|tile| tile.long_max - tile.long_min <= 10.0));
+    assert_eq!((tiles[0].lat_min, tiles[0].lat_max), (40.0, 50.0));
+    assert_eq!(tiles[2].long_max, 25.0);
+
+    assert_eq!(bbox.tiles(100.0).unwrap().len(), 1);
+
+    for max_degrees in [0.0, -1.0, 1e-9, f64::NAN, f64::INFINITY] {
+        assert!(matches!(
+            bbox.tiles(max_degrees),
+            Err(Error::InvalidRequest(_))
+        ));
+    }
+}
+
+#[test]
+fn iterates_over_states() {
+    let states = states();
+    let count = states.len();
+
+    assert!(!states.is_empty());
+    assert_eq!(states[0].icao24, "3c6444");
+    assert_eq!((&states).into_iter().count(), count);
+    assert_eq!(states.iter().filter(|state| state.on_ground).count(), 1);
+
+    let icao24s: Vec<String> = states.into_iter().map(|state| state.icao24).collect();
+    assert_eq!(icao24s.len(), count);
+}
+
+#[test]
+fn reports_invalid_state_vectors() {
+    let null_address = r#"{"time": 1000, "states": [[null, null, "Germany", null, 998, null,
+        null, null, false, null, null, null, null, null, null, false, 0]]}"#;
+    let error = serde_json::from_str::<States>(null_address).unwrap_err();
+    assert!(error.to_string().contains("invalid icao24 at index 0"));
+
+    let wrong_type = r#"{"time": 1000, "states": [["3c6444", null, "Germany", null, 998, null,
+        null, null, "no", null, null, null, null, null, null, false, 0]]}"#;
+    let error = serde_json::from_str::<States>(wrong_type).unwrap_err();
+    assert!(error.to_string().contains("invalid on_ground at index 8"));
+
+    let truncated = r#"{"time": 1000, "states": [["3c6444", null, "Germany"]]}"#;
+    assert!(serde_json::from_str::<States>(truncated).is_err());
+}
+
+#[test]
+fn serializes_into_opensky_layout() {
+    let states = states();
+
+    let array = states.states[1].to_opensky_array();
+    assert_eq!(array.as_array().unwrap().len(), 17);
+    assert_eq!(array[0], "a0b1c2");
+    assert!(array[1].is_null());
+
+    let json = states.to_opensky_json();
+    assert_eq!(json["time"], 1000);
+    assert_eq!(serde_json::from_value::<States>(json).unwrap(), states);
+}
+
+#[test]
+fn merges_snapshots() {
+    let older = states();
+    let newer: States = serde_json::from_str(
+        r#"{"time": 1010, "states": [
+            ["3c6444", "DLH9LF  ", "Germany", 1005, 1008, 8.67, 50.03, 10000.0, false,
+                200.0, 90.0, 0.0, null, null, null, false, 0],
+            ["4b1814", null, "Switzerland", 1000, 1000, 8.55, 47.45, null, true,
+                0.0, 0.0, null, null, null, null, false, 0]]}"#,
+    )
+    .unwrap();
+
+    let merged = States::merge(vec![newer, older]);
+
+    assert_eq!(merged.time, 1010);
+    let icao24s: Vec<&str> = merged.iter().map(|state| state.icao24.as_str()).collect();
+    assert_eq!(icao24s, ["3c6444", "4b1814", "a0b1c2"]);
+    assert_eq!(merged.states[0].last_contact, 1008);
+}
+
+#[test]
+fn snaps_times() {
+    assert_eq!(states::snap_time(1009, states::ANONYMOUS_RESOLUTION), 1000);
+    assert_eq!(
+        states::snap_time(1009, states::AUTHENTICATED_RESOLUTION),
+        1005
+    );
+    assert_eq!(states::snap_time(1010, states::ANONYMOUS_RESOLUTION), 1010);
+    assert_eq!(states::snap_time(1009, 0), 1009);
+}
+
+#[test]
+fn drops_unchanged_positions() {
+    let mut dedup = PositionDedup::new();
+
+    assert_eq!(dedup.updated(states()).states.len(), 2);
+    assert!(dedup.updated(states()).states.is_empty());
+
+    let mut moved = states();
+    moved.states[0].time_position = Some(1005);
+    let updated = dedup.updated(moved);
+    assert_eq!(updated.states.len(), 1);
+    assert_eq!(updated.states[0].icao24, "3c6444");
+
+    dedup.clear();
+    assert_eq!(dedup.updated(states()).states.len(), 2);
+}
+
+#[test]
+fn scores_quality() {
+    let states = states();
+    let (complete, on_ground) = (&states.states[0], &states.states[1]);
+
+    assert_eq!(complete.position_age(states.time), Some(5));
+    // 5 of 60 seconds old, ADS-B, and nothing is unknown
+    let expected = 1.0 - 5.0 / 60.0;
+    assert!((complete.quality(states.time) - expected).abs() < 1e-9);
+
+    assert!(complete.quality(2000) == 0.0);
+    assert!(on_ground.quality(400) < complete.quality(995));
+}
+
+#[test]
+fn separates_aircraft_from_vehicles_and_obstacles() {
+    let states: States = serde_json::from_str(
+        r#"{"time": 1000, "states": [
+            ["000001", null, "Germany", 995, 998, 8.5, 50.0, 10000.0, false,
+                200.0, 90.0, 0.0, null, null, null, false, 0, 6],
+            ["000002", null, "Germany", 995, 998, 8.5, 50.0, null, true,
+                5.0, 90.0, null, null, null, null, false, 0, 17],
+            ["000003", null, "Germany", 995, 998, 8.5, 50.0, 150.0, false,
+                0.0, null, null, null, null, null, false, 0, 18],
+            ["000004", null, "Germany", 995, 998, 8.5, 50.0, 3000.0, false,
+                100.0, 90.0, 0.0, null, null, null, false, 0]]}"#,
+    )
+    .unwrap();
+
+    assert!(states.states[1].is_surface_vehicle());
+    assert!(states.states[2].is_obstacle());
+    assert!(states.states[3].is_aircraft());
+
+    let icao24s: Vec<String> = states
+        .aircraft_only()
+        .iter()
+        .map(|state| state.icao24.clone())
+        .collect();
+    assert_eq!(icao24s, ["000001", "000004"]);
+}
@@ -0,0 +1,73 @@
+use opensky_api::{
+    states::StateVector,
+    tracks::{FlightTrack, FlightTrackBuilder, Waypoint},
+};
+
+fn state(time: u64, true_track: f32, baro_altitude: f32) -> StateVector {
+    serde_json::from_str(&format!(
+        r#"["3c6444", "DLH9LF  ", "Germany", {time}, {time}, 10.0, 50.0, {baro_altitude},
+            false, 200.0, {true_track}, 0.0, null, null, null, false, 0]"#
+    ))
+    .unwrap()
+}
+
+#[test]
+fn deserializes_tracks() {
+    let track: FlightTrack = serde_json::from_str(
+        r#"{"icao24": "3c6444", "startTime": 1000, "endTime": 2000, "callsign": "DLH9LF  ",
+            "path": [[1000, 50.0, 8.5, null, 90.0, true], [2000, 51.0, 9.5, 3000.0, 91.0, false]]}"#,
+    )
+    .unwrap();
+
+    assert_eq!(track.path.len(), 2);
+    assert_eq!(track.path[1].baro_altitude, Some(3000.0));
+    assert!(track.path[0].on_ground);
+    assert_eq!(track.path[1].datetime().timestamp(), 2000);
+}
+
+#[test]
+fn builds_tracks_from_states() {
+    let waypoint = Waypoint::from(&state(1000, 90.0, 1000.0));
+
+    assert_eq!(waypoint.time, 1000);
+    assert_eq!(waypoint.latitude, Some(50.0));
+
+    let mut builder = FlightTrackBuilder::new(String::from("3c6444"));
+
+    assert!(builder.push(&state(1000, 90.0, 1000.0)));
+    assert!(!builder.push(&state(1010, 90.5, 1005.0)));
+    assert!(!builder.push(&state(1010, 120.0, 1005.0)));
+    assert!(builder.push(&state(1020, 120.0, 1005.0)));
+    assert!(builder.push(&state(1030, 120.0, 1500.0)));
+    assert!(!builder.push(&state(1040, 120.0, 1500.0)));
+
+    let track = builder.finish();
+
+    assert_eq!(track.start_time, 1000);
+    assert_eq!(track.end_time, 1040);
+    assert_eq!(track.callsign.as_deref(), Some("DLH9LF  "));
+    assert_eq!(track.path.len(), 4);
+}
+
+#[test]
+fn splits_legs_at_turnarounds() {
+    let track: FlightTrack = serde_json::from_str(
+        r#"{"icao24": "3c6444", "startTime": 1000, "endTime": 9000, "callsign": null, "path": [
+            [1000, 50.0, 8.5, null, 90.0, true],
+            [2000, 50.1, 8.6, 1000.0, 90.0, false],
+            [3000, 51.0, 9.5, null, 90.0, true],
+            [3100, 51.0, 9.5, null, 90.0, true],
+            [4000, 51.0, 9.5, null, 270.0, true],
+            [4100, 51.0, 9.4, 500.0, 270.0, false],
+            [5000, 50.5, 9.0, 500.0, 270.0, false],
+            [9000, 50.0, 8.5, 500.0, 270.0, false]]}"#,
+    )
+    .unwrap();
+
+    let legs = track.split_legs();
+
+    assert_eq!(legs.len(), 3);
+    assert_eq!((legs[0].start_time, legs[0].end_time), (1000, 3000));
+    assert_eq!((legs[1].start_time, legs[1].end_time), (4000, 5000));
+    assert_eq!((legs[2].start_time, legs[2].end_time), (9000, 9000));
+}
@@ -0,0 +1,40 @@
+use opensky_api::{
+    policy::{EndpointClass, RequestPolicy},
+    transport::CannedTransport,
+    OpenSkyApi,
+};
+
+#[tokio::test]
+async fn answers_requests_with_canned_responses() {
+    let transport = CannedTransport::new()
+        .with_response("/api/states/all", 200, r#"{"time": 1000, "states": []}"#)
+        .with_response("/api/flights/all", 503, "");
+    let opensky_api = OpenSkyApi::new()
+        .with_policy(
+            EndpointClass::Flights,
+            RequestPolicy::new().without_retries(),
+        )
+        .with_transport(transport.clone());
+
+    let states = opensky_api.get_states().at_time(1000).send().await.unwrap();
+    assert_eq!(states.time, 1000);
+
+    assert!(opensky_api.get_flights(1000, 2000).send().await.is_err());
+
+    // Paths without a response are answered with 404
+    let metadata = opensky_api
+        .get_aircraft_metadata(String::from("3c6444"))
+        .send()
+        .await
+        .unwrap();
+    assert!(metadata.is_none());
+
+    assert_eq!(
+        transport.requests(),
+        vec![
+            "https://opensky-network.org/api/states/all?time=1000",
+            "https://opensky-network.org/api/flights/all?begin=1000&end=2000",
+            "https://opensky-network.org/api/metadata/aircraft/icao/3c6444",
+        ]
+    );
+}
@@ -0,0 +1,60 @@
+use opensky_api::{
+    states::{StateVector, States},
+    validation::{AltitudeRange, Validators},
+};
+
+fn states() -> States {
+    serde_json::from_str(
+        r#"{"time": 1000, "states": [
+            ["3c6444", "DLH9LF  ", "Germany", 995, 998, 8.57, 50.03, 10000.0, false,
+                200.0, 90.0, 0.0, null, null, null, false, 0],
+            ["3c6445", null, "Germany", 995, 998, 8.57, 95.0, 10000.0, false,
+                200.0, 90.0, 0.0, null, null, null, false, 0],
+            ["3c6446", null, "Germany", 995, 998, 8.57, 50.03, 90000.0, false,
+                200.0, 90.0, 0.0, null, null, null, false, 0],
+            ["3c6447", null, "Germany", 5000, 5000, 8.57, 50.03, 10000.0, false,
+                200.0, 90.0, 0.0, null, null, null, false, 0]]}"#,
+    )
+    .unwrap()
+}
+
+#[test]
+fn default_rules_reject_implausible_states() {
+    let mut states = states();
+    let report = Validators::default_rules().apply(&mut states);
+
+    assert_eq!(states.states.len(), 1);
+    assert_eq!(states.states[0].icao24, "3c6444");
+
+    let rejected: Vec<&str> = report
+        .rejected
+        .iter()
+        .map(|rejection| rejection.state.icao24.as_str())
+        .collect();
+    assert_eq!(rejected, ["3c6445", "3c6446", "3c6447"]);
+}
+
+#[test]
+fn accepts_custom_rules() {
+    let validators = Validators::new()
+        .with_rule(AltitudeRange {
+            min: 0.0,
+            max: 100000.0,
+        })
+        .with_rule(|state: &StateVector, _time: u64| {
+            state
+                .callsign
+                .is_none()
+                .then(|| String::from("missing callsign"))
+        });
+
+    let mut states = states();
+    let report = validators.apply(&mut states);
+
+    assert_eq!(states.states.len(), 1);
+    assert_eq!(report.len(), 3);
+    assert!(report
+        .rejected
+        .iter()
+        .all(|rejection| rejection.reason == "missing callsign"));
+}
@@ -0,0 +1,21 @@
+use opensky_api::states::States;
+
+#[test]
+fn serializes_aircraft_list() {
+    let states: States = serde_json::from_str(
+        r#"{"time": 1000, "states": [["3c6444", "DLH9LF  ", "Germany", 1000, 1000, 10.0,
+            50.0, 10000.0, false, 200.0, 90.0, -5.0, null, 10100.0, "7700", false, 2]]}"#,
+    )
+    .unwrap();
+
+    let list: serde_json::Value = serde_json::from_str(&states.to_vrs_json().unwrap()).unwrap();
+    let aircraft = &list["acList"][0];
+
+    assert_eq!(list["totalAc"], 1);
+    assert_eq!(list["stm"], 1_000_000);
+    assert_eq!(aircraft["Icao"], "3C6444");
+    assert_eq!(aircraft["Call"], "DLH9LF");
+    assert_eq!(aircraft["Alt"], 32808);
+    assert_eq!(aircraft["Help"], true);
+    assert_eq!(aircraft["Mlat"], true);
+}